@@ -0,0 +1,165 @@
+use std::{fs, io, path::Path};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{PageOutput, escape_html, slugify, urls::UrlResolver};
+
+/// Directory of the actor and outbox, relative to the output directory.
+pub const AP_DIR: &str = "ap";
+
+/// WebFinger response, relative to the output directory.
+pub const WEBFINGER_PERMALINK: &str = ".well-known/webfinger";
+
+/// Posts listed in the outbox when `outbox_size` is not configured.
+const DEFAULT_OUTBOX_SIZE: usize = 20;
+
+/// ActivityStreams audience of public posts.
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+/// Headers by path pattern that hosts must send for the Fediverse to accept the documents, merged
+/// into the `_headers` of [`crate::deploy::write_headers`].
+pub const HEADERS: &[(&str, &str, &str)] = &[
+    ("ap/*", "Content-Type", "application/activity+json"),
+    (".well-known/webfinger", "Content-Type", "application/jrd+json"),
+    (".well-known/webfinger", "Access-Control-Allow-Origin", "*"),
+];
+
+/// Read-only ActivityPub presence of the site, read from the `activitypub` block of
+/// `cats-ssg.json`; nothing is written when this block is absent.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct ActivityPubConfig {
+    /// Name of the account, as in `@username@example.com`
+    pub username: String,
+    /// Display name of the account (default: `username`)
+    pub name: Option<String>,
+    /// Bio of the account, as HTML
+    pub summary: Option<String>,
+    /// Avatar of the account, as a site path or an absolute URL
+    pub icon: Option<String>,
+    /// PEM file of the public key of the account, which some servers require to show it
+    pub public_key: Option<std::path::PathBuf>,
+    /// Newest posts listed in the outbox (default: 20)
+    pub outbox_size: Option<usize>,
+}
+
+/// Write the actor (`ap/actor.json`), its outbox of the newest posts of `pages`
+/// (`ap/outbox.json`) and the WebFinger response of the account (`.well-known/webfinger`)
+/// under `dist_path`, so the site can be followed read-only from the Fediverse.
+///
+/// Posts are the dated pages of `pages`, in their order, without `noindex` or `exclude_from_index`;
+/// each is a `Create` activity of an `Article` with the title, summary, link and tags of the page.
+/// The actor has no working inbox, so replies and follows are not recorded anywhere.
+///
+/// WebFinger is looked up at the root of the domain, so the account can only be found when the
+/// site is served from it; hosts must also serve the documents with the types of [`HEADERS`].
+///
+/// Returns the output paths relative to `dist_path`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{activitypub::{write_activitypub, ActivityPubConfig}, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("activitypub-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// let config = ActivityPubConfig { username: "cats".to_string(), ..Default::default() };
+/// let written = write_activitypub(&dist, &config, &[], &UrlResolver::new("https://example.com", "/")).unwrap();
+/// assert_eq!(written, ["ap/actor.json", "ap/outbox.json", ".well-known/webfinger"]);
+/// let webfinger: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dist.join(".well-known/webfinger")).unwrap()).unwrap();
+/// assert_eq!(webfinger["subject"], "acct:cats@example.com");
+/// assert_eq!(webfinger["links"][0]["href"], "https://example.com/ap/actor.json");
+/// ```
+pub fn write_activitypub(dist_path: &Path, config: &ActivityPubConfig, pages: &[PageOutput], urls: &UrlResolver) -> io::Result<Vec<String>> {
+    let actor_url = urls.url(&format!("{}/actor.json", AP_DIR));
+    let outbox_url = urls.url(&format!("{}/outbox.json", AP_DIR));
+    let site_url = urls.url("");
+    let host = urls.domain().split("://").nth(1).unwrap_or(urls.domain()).trim_end_matches('/');
+
+    let mut actor = json!({
+        "@context": ["https://www.w3.org/ns/activitystreams", "https://w3id.org/security/v1"],
+        "id": actor_url,
+        "type": "Person",
+        "preferredUsername": config.username,
+        "name": config.name.as_deref().unwrap_or(&config.username),
+        "url": site_url,
+        "inbox": urls.url(&format!("{}/inbox", AP_DIR)),
+        "outbox": outbox_url,
+        "discoverable": true,
+    });
+    if let Some(summary) = &config.summary {
+        actor["summary"] = json!(summary);
+    }
+    if let Some(icon) = &config.icon {
+        actor["icon"] = json!({ "type": "Image", "url": urls.absolute_url(icon) });
+    }
+    if let Some(public_key) = &config.public_key {
+        let pem = fs::read_to_string(public_key)?;
+        actor["publicKey"] = json!({ "id": format!("{}#main-key", actor_url), "owner": actor_url, "publicKeyPem": pem.trim() });
+    }
+
+    let posts: Vec<Value> = pages
+        .iter()
+        .filter(|page| !page.meta.noindex.unwrap_or(false) && !page.meta.exclude_from_index.unwrap_or(false))
+        .filter_map(|page| Some((page, page.meta.date.as_deref()?)))
+        .take(config.outbox_size.unwrap_or(DEFAULT_OUTBOX_SIZE))
+        .map(|(page, date)| {
+            let url = urls.absolute_url(&page.href);
+            let tags: Vec<Value> = page
+                .meta
+                .tags
+                .iter()
+                .flatten()
+                .map(|tag| json!({ "type": "Hashtag", "name": format!("#{}", tag), "href": urls.url(&format!("tags/{}/index.html", slugify(tag))) }))
+                .collect();
+            let content = format!("<p>{}</p><p><a href=\"{}\">{}</a></p>", escape_html(&page.summary), escape_html(&url), escape_html(&url));
+            json!({
+                "id": format!("{}#create", url),
+                "type": "Create",
+                "actor": actor_url,
+                "published": date,
+                "to": [PUBLIC],
+                "object": {
+                    "id": url,
+                    "type": "Article",
+                    "name": page.title,
+                    "content": content,
+                    "url": url,
+                    "attributedTo": actor_url,
+                    "published": date,
+                    "to": [PUBLIC],
+                    "tag": tags,
+                },
+            })
+        })
+        .collect();
+    let outbox = json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": outbox_url,
+        "type": "OrderedCollection",
+        "totalItems": posts.len(),
+        "orderedItems": posts,
+    });
+
+    let webfinger = json!({
+        "subject": format!("acct:{}@{}", config.username, host),
+        "aliases": [actor_url, site_url],
+        "links": [
+            { "rel": "self", "type": "application/activity+json", "href": actor_url },
+            { "rel": "http://webfinger.net/rel/profile-page", "type": "text/html", "href": site_url },
+        ],
+    });
+
+    let mut written = Vec::new();
+    for (rel, value) in [
+        (format!("{}/actor.json", AP_DIR), actor),
+        (format!("{}/outbox.json", AP_DIR), outbox),
+        (WEBFINGER_PERMALINK.to_string(), webfinger),
+    ] {
+        let dest = dist_path.join(&rel);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&dest, value.to_string())?;
+        written.push(rel);
+    }
+    Ok(written)
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{escape_html, inject_into_head, urls::UrlResolver};
+
+/// Privacy-friendly analytics service counting the visits of the site.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsProvider {
+    /// [Plausible](https://plausible.io)
+    #[default]
+    Plausible,
+    /// [GoatCounter](https://www.goatcounter.com)
+    GoatCounter,
+    /// [Umami](https://umami.is)
+    Umami,
+}
+
+/// Analytics snippet added to the `<head>` of every page, read from the `analytics` block of
+/// `cats-ssg.json`; pages opt out with `analytics: false`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct AnalyticsConfig {
+    #[serde(default)]
+    pub provider: AnalyticsProvider,
+    /// The site for the provider: the domain registered with Plausible (default: the host of the
+    /// site), the GoatCounter code or the Umami website id
+    pub id: Option<String>,
+    /// URL of the script, for self-hosted instances
+    pub script: Option<String>,
+    /// URL visits are counted at, for self-hosted GoatCounter instances (default:
+    /// `https://<id>.goatcounter.com/count`)
+    pub endpoint: Option<String>,
+}
+
+impl AnalyticsConfig {
+    /// URL of the script of the provider.
+    pub fn script_url(&self) -> &str {
+        self.script.as_deref().unwrap_or(match self.provider {
+            AnalyticsProvider::Plausible => "https://plausible.io/js/script.js",
+            AnalyticsProvider::GoatCounter => "https://gc.zgo.at/count.js",
+            AnalyticsProvider::Umami => "https://cloud.umami.is/script.js",
+        })
+    }
+
+    /// The `<script>` of the provider for the site of `urls`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::{analytics::{AnalyticsConfig, AnalyticsProvider}, urls::UrlResolver};
+    /// let urls = UrlResolver::new("https://example.com", "/blog/");
+    /// assert_eq!(
+    ///     AnalyticsConfig::default().snippet(&urls),
+    ///     r#"<script defer data-domain="example.com" src="https://plausible.io/js/script.js"></script>"#
+    /// );
+    /// let goatcounter = AnalyticsConfig { provider: AnalyticsProvider::GoatCounter, id: Some("cats".to_string()), ..Default::default() };
+    /// assert_eq!(
+    ///     goatcounter.snippet(&urls),
+    ///     r#"<script async data-goatcounter="https://cats.goatcounter.com/count" src="https://gc.zgo.at/count.js"></script>"#
+    /// );
+    /// ```
+    pub fn snippet(&self, urls: &UrlResolver) -> String {
+        let src = escape_html(self.script_url());
+        let id = self.id.as_deref().unwrap_or_default();
+        match self.provider {
+            AnalyticsProvider::Plausible => {
+                let host = urls.domain().split("://").nth(1).unwrap_or(urls.domain()).trim_end_matches('/');
+                let domain = self.id.as_deref().unwrap_or(host);
+                format!(r#"<script defer data-domain="{}" src="{}"></script>"#, escape_html(domain), src)
+            }
+            AnalyticsProvider::GoatCounter => {
+                let endpoint = self.endpoint.clone().unwrap_or_else(|| format!("https://{}.goatcounter.com/count", id));
+                format!(r#"<script async data-goatcounter="{}" src="{}"></script>"#, escape_html(&endpoint), src)
+            }
+            AnalyticsProvider::Umami => {
+                format!(r#"<script defer data-website-id="{}" src="{}"></script>"#, escape_html(id), src)
+            }
+        }
+    }
+}
+
+/// `html` with `snippet` (see [`AnalyticsConfig::snippet`]) at the end of its `<head>`, unless it
+/// already loads the script at `script_url`.
+pub fn inject_analytics(html: &str, snippet: &str, script_url: &str) -> String {
+    if html.contains(script_url) {
+        html.to_string()
+    } else {
+        inject_into_head(html, snippet)
+    }
+}
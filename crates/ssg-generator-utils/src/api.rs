@@ -0,0 +1,118 @@
+use std::{fs, io, path::Path};
+use serde::Serialize;
+
+use crate::{Meta, PageOutput, urls::UrlResolver};
+
+/// Directory of the JSON content API, relative to the output directory.
+pub const API_DIR: &str = "api";
+
+/// `api/pages/<slug>.json`: everything a headless frontend needs to show a page.
+#[derive(Serialize)]
+pub struct PageDocument<'a> {
+    pub slug: &'a str,
+    pub title: &'a str,
+    pub href: &'a str,
+    /// Absolute URL of the page
+    pub url: String,
+    /// Date of the page in RFC 3339 form
+    pub date: Option<&'a str>,
+    pub summary: &'a str,
+    pub word_count: usize,
+    /// Body of the page as rendered to HTML, without the site template
+    pub html: &'a str,
+    /// Text of the page without markup (see [`crate::search::plain_text`])
+    pub text: String,
+    /// Front matter merged with the metadata file and cascaded defaults
+    pub meta: &'a Meta,
+}
+
+/// `api/index.json` entry of a page.
+#[derive(Serialize)]
+struct IndexEntry<'a> {
+    slug: &'a str,
+    title: &'a str,
+    href: &'a str,
+    url: String,
+    date: Option<&'a str>,
+    summary: &'a str,
+    tags: &'a [String],
+    /// Site path of the page document
+    api: String,
+}
+
+/// Slug of the page written to `output_path` (relative to the output directory): that path without
+/// its `.html` extension, so the documents mirror the pages and, like them, never collide.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::api::page_slug;
+/// assert_eq!(page_slug(Path::new("posts/hello/index.html")), "posts/hello/index");
+/// assert_eq!(page_slug(Path::new("posts-hello.html")), "posts-hello");
+/// assert_eq!(page_slug(Path::new("index.html")), "index");
+/// ```
+pub fn page_slug(output_path: &Path) -> String {
+    let path = output_path.to_string_lossy().replace('\\', "/");
+    path.strip_suffix(".html").unwrap_or(&path).to_string()
+}
+
+/// Permalink of the document of the page with slug `slug`.
+pub fn page_permalink(slug: &str) -> String {
+    format!("{}/pages/{}.json", API_DIR, slug)
+}
+
+/// Write `document` to its permalink under `dist_path`, returning the permalink.
+pub fn write_page(dist_path: &Path, document: &PageDocument) -> io::Result<String> {
+    let rel = page_permalink(document.slug);
+    let dest = dist_path.join(&rel);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&dest, serde_json::to_string(document).map_err(io::Error::other)?)?;
+    Ok(rel)
+}
+
+/// Write `api/index.json` under `dist_path`: the `slug`, `title`, `href`, `url`, `date`, `summary`,
+/// `tags` and the site path of the document (`api`) of every page of `pages` with a document, in
+/// their order, leaving out those with `exclude_from_index`. Returns its permalink.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{api::write_index, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("api-index-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// let rel = write_index(&dist, &[], &UrlResolver::new("https://example.com", "/")).unwrap();
+/// assert_eq!(std::fs::read_to_string(dist.join(rel)).unwrap(), r#"{"pages":[]}"#);
+/// ```
+pub fn write_index(dist_path: &Path, pages: &[PageOutput], urls: &UrlResolver) -> io::Result<String> {
+    #[derive(Serialize)]
+    struct Index<'a> {
+        pages: Vec<IndexEntry<'a>>,
+    }
+
+    let entries = pages
+        .iter()
+        .filter(|page| !page.meta.exclude_from_index.unwrap_or(false))
+        .filter_map(|page| {
+            let rel = page.api_rel_path.as_deref()?;
+            let slug = rel.strip_prefix(&format!("{}/pages/", API_DIR))?.strip_suffix(".json")?;
+            Some(IndexEntry {
+                slug,
+                title: &page.title,
+                href: &page.href,
+                url: urls.absolute_url(&page.href),
+                date: page.meta.date.as_deref(),
+                summary: &page.summary,
+                tags: page.meta.tags.as_deref().unwrap_or_default(),
+                api: urls.href(rel),
+            })
+        })
+        .collect();
+    let rel = format!("{}/index.json", API_DIR);
+    let dest = dist_path.join(&rel);
+    fs::create_dir_all(dist_path.join(API_DIR))?;
+    fs::write(&dest, serde_json::to_string(&Index { pages: entries }).map_err(io::Error::other)?)?;
+    Ok(rel)
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+/// Source files that are consumed by the generator itself and never copied.
+const GENERATOR_FILES: &[&str] = &["meta.yml", "meta.yaml", "meta.toml", "meta.json", "_defaults.yml"];
+
+/// Copy every non-Markdown file under `base_path` into `dist_path`, preserving relative paths.
+///
+/// `page_paths` maps the source of every page, relative to `base_path`, to where it was written,
+/// relative to `dist_path` (see [`crate::urls::UrlResolver::page_path`]). Files next to an
+/// `index.md` (and anything in folders below it) follow the page to its output directory, wherever
+/// a `page_slug`, a permalink pattern or pretty URLs put it, so `![](diagram.png)` keeps resolving.
+/// Markdown sources, metadata files (see [`crate::META_FILES`]), `_defaults.yml` files, dotfiles and anything already inside `dist_path` are skipped.
+///
+/// Returns the copied paths relative to `dist_path`.
+///
+/// # Examples
+///
+/// ```
+/// use std::{collections::HashMap, path::Path};
+/// use ssg_generator_utils::{assets::copy_content_assets, parse_front_matter, urls::UrlResolver};
+/// let root = std::env::temp_dir().join("content-assets-doctest");
+/// std::fs::create_dir_all(root.join("pages/posts/hello")).unwrap();
+/// std::fs::write(root.join("pages/posts/hello/index.md"), "# Hello\n\n![](diagram.png)").unwrap();
+/// std::fs::write(root.join("pages/posts/hello/diagram.png"), b"png").unwrap();
+///
+/// let urls = UrlResolver::new("https://example.com", "/").with_permalink(Some("/:year/:month/:slug/"));
+/// let date = chrono::DateTime::parse_from_rfc3339("2024-05-01T00:00:00Z").unwrap();
+/// let src = Path::new("posts/hello/index.md");
+/// let output = urls.page_path(src, &parse_front_matter("title: Hello").unwrap(), Some(&date));
+/// let page_paths = HashMap::from([(src.to_path_buf(), output)]);
+///
+/// let copied = copy_content_assets(&root.join("pages"), &root.join("dist"), &page_paths).unwrap();
+/// assert_eq!(copied, vec!["2024/05/hello/diagram.png"]);
+/// assert!(root.join("dist/2024/05/hello/diagram.png").exists());
+/// ```
+pub fn copy_content_assets(
+    base_path: &Path,
+    dist_path: &Path,
+    page_paths: &HashMap<PathBuf, PathBuf>,
+) -> std::io::Result<Vec<String>> {
+    let bundle_dirs = bundle_dirs(page_paths);
+    let mut copied = Vec::new();
+    for src in list_files(base_path)? {
+        if src.starts_with(dist_path) {
+            continue;
+        }
+        let rel = src.strip_prefix(base_path).unwrap();
+        let is_markdown = src.extension().is_some_and(|e| e == "md");
+        let is_generator_file = src
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| GENERATOR_FILES.contains(&n));
+        if is_markdown || is_generator_file {
+            continue;
+        }
+        let out_rel = relocate(rel, &bundle_dirs);
+        copy_if_changed(&src, &dist_path.join(&out_rel))?;
+        copied.push(out_rel.to_string_lossy().replace('\\', "/"));
+    }
+    Ok(copied)
+}
+
+/// Extensions of the static files [`fingerprint_static_dir`] renames.
+const FINGERPRINTED_EXTENSIONS: &[&str] = &["avif", "css", "gif", "jpeg", "jpg", "js", "png", "svg", "webp", "woff", "woff2"];
+
+/// Map the path of every stylesheet, script, image and font under `static_path` to a name carrying
+/// a hash of its content, such as `css/app.css` to `css/app.3fa9c2d1.css`, both relative to
+/// `static_path`.
+///
+/// A missing `static_path` maps nothing.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::assets::fingerprint_static_dir;
+/// let root = std::env::temp_dir().join("fingerprint-doctest");
+/// std::fs::create_dir_all(root.join("css")).unwrap();
+/// std::fs::write(root.join("css/app.css"), "body {}").unwrap();
+/// std::fs::write(root.join("robots.txt"), "").unwrap();
+/// let fingerprints = fingerprint_static_dir(&root).unwrap();
+/// assert!(fingerprints["css/app.css"].starts_with("css/app.") && fingerprints["css/app.css"].ends_with(".css"));
+/// assert!(!fingerprints.contains_key("robots.txt"));
+/// ```
+pub fn fingerprint_static_dir(static_path: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    if !static_path.is_dir() {
+        return Ok(BTreeMap::new());
+    }
+    let mut fingerprints = BTreeMap::new();
+    for src in list_files(static_path)? {
+        let (Some(stem), Some(ext)) = (src.file_stem().and_then(|s| s.to_str()), src.extension().and_then(|e| e.to_str()))
+        else {
+            continue;
+        };
+        if !FINGERPRINTED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) {
+            continue;
+        }
+        let mut hasher = DefaultHasher::new();
+        fs::read(&src)?.hash(&mut hasher);
+        let rel = src.strip_prefix(static_path).unwrap();
+        let fingerprinted = rel.with_file_name(format!("{}.{:08x}.{}", stem, hasher.finish() as u32, ext));
+        fingerprints.insert(rel.to_string_lossy().replace('\\', "/"), fingerprinted.to_string_lossy().replace('\\', "/"));
+    }
+    Ok(fingerprints)
+}
+
+/// Copy the whole `static_path` directory into the root of `dist_path`, adding a copy under the
+/// fingerprinted name of the files found in `fingerprints` (see [`fingerprint_static_dir`]).
+///
+/// Files keep their original name as well, so stylesheets, scripts and pages that reference them
+/// directly rather than through the `asset` filter still resolve.
+///
+/// Returns the copied paths relative to `dist_path`. A missing `static_path` copies nothing.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::assets::copy_static_dir;
+/// let root = std::env::temp_dir().join("copy-static-doctest");
+/// std::fs::create_dir_all(root.join("static/fonts")).unwrap();
+/// std::fs::write(root.join("static/fonts/a.woff2"), b"font").unwrap();
+/// let copied = copy_static_dir(&root.join("static"), &root.join("dist"), &Default::default()).unwrap();
+/// assert_eq!(copied, vec!["fonts/a.woff2"]);
+/// assert!(root.join("dist/fonts/a.woff2").exists());
+///
+/// let fingerprints = [("fonts/a.woff2".to_string(), "fonts/a.1234abcd.woff2".to_string())].into();
+/// let copied = copy_static_dir(&root.join("static"), &root.join("dist"), &fingerprints).unwrap();
+/// assert_eq!(copied, vec!["fonts/a.woff2", "fonts/a.1234abcd.woff2"]);
+/// assert!(root.join("dist/fonts/a.1234abcd.woff2").exists());
+/// ```
+pub fn copy_static_dir(
+    static_path: &Path,
+    dist_path: &Path,
+    fingerprints: &BTreeMap<String, String>,
+) -> std::io::Result<Vec<String>> {
+    if !static_path.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut copied = Vec::new();
+    for src in list_files(static_path)? {
+        let rel = src.strip_prefix(static_path).unwrap().to_string_lossy().replace('\\', "/");
+        copy_if_changed(&src, &dist_path.join(&rel))?;
+        if let Some(fingerprinted) = fingerprints.get(&rel) {
+            copy_if_changed(&src, &dist_path.join(fingerprinted))?;
+            copied.push(rel);
+            copied.push(fingerprinted.clone());
+        } else {
+            copied.push(rel);
+        }
+    }
+    Ok(copied)
+}
+
+/// Recursively list the files under `dir`, skipping dotfiles and dot-directories.
+pub(crate) fn list_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.')) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Map the relative directories holding an `index.md` to the output directory of its page.
+fn bundle_dirs(page_paths: &HashMap<PathBuf, PathBuf>) -> HashMap<PathBuf, PathBuf> {
+    page_paths
+        .iter()
+        .filter(|(src, _)| src.file_name().is_some_and(|n| n == "index.md"))
+        .map(|(src, output)| {
+            let parent = |path: &Path| path.parent().unwrap_or(Path::new("")).to_path_buf();
+            (parent(src), parent(output))
+        })
+        .collect()
+}
+
+/// Rewrite `rel` to live under the output directory of its closest bundle ancestor, if any.
+fn relocate(rel: &Path, bundle_dirs: &HashMap<PathBuf, PathBuf>) -> PathBuf {
+    for ancestor in rel.ancestors().skip(1) {
+        if let Some(out_dir) = bundle_dirs.get(ancestor) {
+            return out_dir.join(rel.strip_prefix(ancestor).unwrap());
+        }
+    }
+    rel.to_path_buf()
+}
+
+/// Copy `src` to `dest` unless `dest` already has the same size and is at least as new.
+fn copy_if_changed(src: &Path, dest: &Path) -> std::io::Result<()> {
+    let src_meta = fs::metadata(src)?;
+    if let Ok(dest_meta) = fs::metadata(dest) {
+        let fresh = dest_meta.len() == src_meta.len()
+            && matches!((dest_meta.modified(), src_meta.modified()), (Ok(d), Ok(s)) if d >= s);
+        if fresh {
+            return Ok(());
+        }
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(src, dest)?;
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use minijinja::{Environment, context};
+use serde::{Deserialize, Serialize};
+
+use crate::{PageOutput, error::SsgError, git::GitAuthor, nav::PageRef, slugify, urls::UrlResolver};
+
+/// Profiles of the authors of a multi-author blog by commit email, read from `authors.yml` in the
+/// data directory.
+pub type Authors = BTreeMap<String, AuthorProfile>;
+
+/// How an author of commits is shown on the pages they wrote.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct AuthorProfile {
+    /// Display name (default: the name of their commits)
+    pub name: Option<String>,
+    /// Picture of the author, as a site path or an absolute URL
+    pub avatar: Option<String>,
+    /// Home page of the author, as a site path or an absolute URL
+    pub url: Option<String>,
+}
+
+/// Someone who wrote a page, handed to templates in `contributors`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Contributor {
+    pub name: String,
+    pub email: String,
+    /// Absolute URL of their picture, when their profile has one
+    pub avatar: Option<String>,
+    /// Absolute URL of their home page, when their profile has one
+    pub url: Option<String>,
+    /// Commits of theirs touching the page
+    pub commits: usize,
+}
+
+/// Read `authors.yml` (or `authors.yaml`) from `data_path`; `Ok(None)` when there is no such file.
+///
+/// The file maps the emails of commits to profiles:
+///
+/// ```yaml
+/// ana@example.com:
+///   name: Ana
+///   avatar: /images/ana.png
+/// ```
+pub fn load_authors(data_path: &Path) -> Result<Option<Authors>, String> {
+    for name in ["authors.yml", "authors.yaml"] {
+        let path = data_path.join(name);
+        if path.is_file() {
+            let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            let authors: Authors = serde_yaml::from_str(&content).map_err(|e| e.to_string())?;
+            return Ok(Some(authors.into_iter().map(|(email, profile)| (email.to_lowercase(), profile)).collect()));
+        }
+    }
+    Ok(None)
+}
+
+/// The `git_authors` of a page (see [`crate::git::FileHistory`]), in their order, shown with their
+/// profile in `authors`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{authors::{contributors, AuthorProfile, Authors}, git::GitAuthor, urls::UrlResolver};
+/// let authors = Authors::from([(
+///     "ana@example.com".to_string(),
+///     AuthorProfile { name: Some("Ana".to_string()), avatar: Some("/images/ana.png".to_string()), url: None },
+/// )]);
+/// let git_authors = [
+///     GitAuthor { name: "ana".to_string(), email: "Ana@example.com".to_string(), commits: 3 },
+///     GitAuthor { name: "Bo".to_string(), email: "bo@example.com".to_string(), commits: 1 },
+/// ];
+/// let contributors = contributors(&git_authors, &authors, &UrlResolver::new("https://example.com", "/"));
+/// assert_eq!(contributors[0].name, "Ana");
+/// assert_eq!(contributors[0].avatar.as_deref(), Some("https://example.com/images/ana.png"));
+/// assert_eq!(contributors[1].name, "Bo");
+/// assert_eq!(contributors[1].avatar, None);
+/// ```
+pub fn contributors(git_authors: &[GitAuthor], authors: &Authors, urls: &UrlResolver) -> Vec<Contributor> {
+    git_authors
+        .iter()
+        .map(|author| {
+            let profile = authors.get(&author.email.to_lowercase());
+            Contributor {
+                name: profile.and_then(|profile| profile.name.clone()).unwrap_or_else(|| author.name.clone()),
+                email: author.email.clone(),
+                avatar: profile.and_then(|profile| profile.avatar.as_deref()).map(|avatar| urls.absolute_url(avatar)),
+                url: profile.and_then(|profile| profile.url.as_deref()).map(|url| urls.absolute_url(url)),
+                commits: author.commits,
+            }
+        })
+        .collect()
+}
+
+/// The profile in `authors` of the author called `name`, matched on its display name.
+fn profile_by_name<'a>(authors: &'a Authors, name: &str) -> Option<&'a AuthorProfile> {
+    authors.values().find(|profile| profile.name.as_deref() == Some(name))
+}
+
+/// An author and the pages they wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorPages {
+    /// Name of the author as spelled on the first page crediting them
+    pub name: String,
+    pub pages: Vec<PageRef>,
+}
+
+/// Group the pages of `pages` by author (see [`crate::Meta::author_names`]), keyed by the slug of
+/// their name (see [`author_href`]) so spellings differing only in case or punctuation share one
+/// page, each author listing their pages in the order of `pages`. Names without any letter or digit
+/// have no slug and are left out.
+pub fn collect_authors(pages: &[PageOutput]) -> BTreeMap<String, AuthorPages> {
+    let mut by_author: BTreeMap<String, AuthorPages> = BTreeMap::new();
+    for page in pages {
+        for name in page.meta.author_names() {
+            let slug = slugify(&name);
+            if slug.is_empty() {
+                continue;
+            }
+            let author = by_author.entry(slug).or_insert_with(|| AuthorPages { name, pages: Vec::new() });
+            if !author.pages.iter().any(|other| other.href == page.href) {
+                author.pages.push(PageRef { title: page.title.clone(), href: page.href.clone(), summary: page.summary.clone() });
+            }
+        }
+    }
+    by_author
+}
+
+/// Site-absolute href of the page listing the posts of the author `name`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{authors::author_href, urls::UrlResolver};
+/// assert_eq!(author_href("Ana Smith", &UrlResolver::new("https://example.com", "/blog/")), "/blog/authors/ana-smith/");
+/// ```
+pub fn author_href(name: &str, urls: &UrlResolver) -> String {
+    urls.href(&format!("authors/{}/", slugify(name)))
+}
+
+/// Render `authors/<slug>/index.html` for every author of `by_author` (keyed by slug, see
+/// [`collect_authors`]) plus an `authors/index.html` overview
+/// through the `author.html` template.
+///
+/// Author pages receive `{ title, author: { name, href, avatar, url }, pages: [{ title, href, summary }] }`
+/// and the overview receives `{ title, authors: [{ name, href, avatar, count }] }`, so the template can
+/// tell them apart with `author is defined`. `avatar` and `url` come from the profile in `profiles`
+/// whose `name` is that of the author, as absolute URLs.
+///
+/// Returns the output paths relative to `dist_path` (e.g. `authors/ana/index.html`) so they can be
+/// added to the sitemap.
+pub fn write_author_pages(
+    dist_path: &Path,
+    by_author: &BTreeMap<String, AuthorPages>,
+    profiles: &Authors,
+    env: &Environment,
+    urls: &UrlResolver,
+) -> Result<Vec<String>, SsgError> {
+    let tmpl = env.get_template("author.html").map_err(SsgError::template("author.html"))?;
+    let mut written = Vec::new();
+    let mut overview = Vec::new();
+
+    for (slug, AuthorPages { name, pages }) in by_author {
+        let profile = profile_by_name(profiles, name);
+        let avatar = profile.and_then(|profile| profile.avatar.as_deref()).map(|avatar| urls.absolute_url(avatar));
+        let href = author_href(name, urls);
+        let rendered = tmpl
+            .render(context! {
+                title => format!("Posts by {}", name),
+                author => context! {
+                    name => name,
+                    href => &href,
+                    avatar => &avatar,
+                    url => profile.and_then(|profile| profile.url.as_deref()).map(|url| urls.absolute_url(url)),
+                },
+                pages => pages,
+            })
+            .map_err(SsgError::template("author.html"))?;
+
+        let rel = format!("authors/{}/index.html", slug);
+        let dest = dist_path.join(&rel);
+        fs::create_dir_all(dest.parent().unwrap()).map_err(SsgError::io(dest.parent().unwrap()))?;
+        fs::write(&dest, rendered).map_err(SsgError::io(&dest))?;
+        written.push(rel);
+
+        overview.push(context! {
+            name => name,
+            href => href,
+            avatar => avatar,
+            count => pages.len(),
+        });
+    }
+
+    let rendered = tmpl
+        .render(context! {
+            title => "Authors",
+            authors => overview,
+        })
+        .map_err(SsgError::template("author.html"))?;
+    let rel = "authors/index.html".to_string();
+    let dest = dist_path.join(&rel);
+    fs::create_dir_all(dest.parent().unwrap()).map_err(SsgError::io(dest.parent().unwrap()))?;
+    fs::write(&dest, rendered).map_err(SsgError::io(&dest))?;
+    written.push(rel);
+
+    Ok(written)
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use minijinja::Environment;
+use serde::{Deserialize, Serialize};
+
+use crate::{PageOutput, include::expand_includes, find_meta_file, git::FileHistory, load_page_meta, meta_dirs, nav::PageNav, split_front_matter, templates::DEFAULT_TEMPLATES};
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: u64,
+    page: PageOutput,
+}
+
+/// Pages rendered by a previous build, keyed by source path relative to the content root.
+///
+/// A page is reused when its [`page_key`] is unchanged and the files it wrote still exist, so
+/// deleting the cache file (or the output directory) simply forces a full render.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::{Meta, PageOutput, cache::PageCache};
+/// let dist = std::env::temp_dir().join("page-cache-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// std::fs::write(dist.join("post.html"), "<p>Hi</p>").unwrap();
+///
+/// let mut cache = PageCache::default();
+/// let page = PageOutput {
+///     title: "Post".to_string(),
+///     href: "/post.html".to_string(),
+///     source_path: "pages/post.md".into(),
+///     output_path: "post.html".into(),
+///     md_rel_path: None,
+///     llm_tokens: None,
+///     txt_rel_path: None,
+///     gmi_path: None,
+///     api_rel_path: None,
+///     card_rel_path: None,
+///     word_count: 1,
+///     summary: String::new(),
+///     meta: Meta::default(),
+/// };
+/// cache.insert(Path::new("post.md"), 42, page);
+/// cache.save(&dist.join("pages.json")).unwrap();
+///
+/// let cache = PageCache::load(&dist.join("pages.json"));
+/// assert!(cache.get(Path::new("post.md"), 42, &dist).is_some());
+/// assert!(cache.get(Path::new("post.md"), 7, &dist).is_none());
+/// ```
+#[derive(Default, Serialize, Deserialize)]
+pub struct PageCache {
+    pages: HashMap<String, Entry>,
+}
+
+impl PageCache {
+    /// Read the cache written by [`PageCache::save`]; a missing or unreadable file yields an empty cache.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the cache to `path`, creating its parent directory.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The page cached for `rel_src` under `key`, if its HTML (and Markdown copy, plain text and card) are still in `dist_path`.
+    pub fn get(&self, rel_src: &Path, key: u64, dist_path: &Path) -> Option<PageOutput> {
+        let entry = self.pages.get(&rel_src.to_string_lossy().replace('\\', "/"))?;
+        let page = &entry.page;
+        let outputs_exist = dist_path.join(&page.output_path).exists()
+            && page.md_rel_path.as_ref().is_none_or(|md| dist_path.join(md).exists())
+            && page.txt_rel_path.as_ref().is_none_or(|txt| dist_path.join(txt).exists())
+            && page.gmi_path.as_ref().is_none_or(|gmi| gmi.exists())
+            && page.api_rel_path.as_ref().is_none_or(|api| dist_path.join(api).exists())
+            && page.card_rel_path.as_ref().is_none_or(|card| dist_path.join(card).exists());
+        (entry.key == key && outputs_exist).then(|| entry.page.clone())
+    }
+
+    /// Remember `page` as the result of rendering `rel_src` with inputs hashing to `key`.
+    pub fn insert(&mut self, rel_src: &Path, key: u64, page: PageOutput) {
+        self.pages.insert(rel_src.to_string_lossy().replace('\\', "/"), Entry { key, page });
+    }
+}
+
+/// Hashes of the inputs shared by every page, as computed by [`site_key`].
+#[derive(Debug, Clone, Copy)]
+pub struct SiteKey {
+    shared: u64,
+    site: u64,
+}
+
+/// Hash everything that affects every page: generator `options` (any stable rendering of them),
+/// the templates and data directories and the syntax set.
+///
+/// The serialized `site` global changes whenever any page does (undated pages are dated by their
+/// modification time), so it only counts for every page when a template uses `site`; otherwise
+/// [`page_key`] adds it just for pages with `templated: true`. The generator's own version and
+/// executable are included, so upgrading it invalidates the cache.
+pub fn site_key(
+    options: &str,
+    templates_path: &Path,
+    data_path: Option<&Path>,
+    syntaxes_path: Option<&Path>,
+    site: &str,
+) -> SiteKey {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    std::env::current_exe().ok().map(|exe| stat(&exe)).hash(&mut hasher);
+    options.hash(&mut hasher);
+    DEFAULT_TEMPLATES.hash(&mut hasher);
+    hash_dir(templates_path, &mut hasher);
+    if let Some(data_path) = data_path {
+        hash_dir(data_path, &mut hasher);
+    }
+    syntaxes_path.map(stat).hash(&mut hasher);
+
+    let mut site_hasher = DefaultHasher::new();
+    site.hash(&mut site_hasher);
+    let site = site_hasher.finish();
+    if templates_use_site(templates_path) {
+        site.hash(&mut hasher);
+    }
+    SiteKey { shared: hasher.finish(), site }
+}
+
+/// Hash the inputs of a single page on top of `site_key`: its source with includes expanded, the
+/// metadata and `_defaults.yml` files of its directory and those above it, the size and
+/// modification time of the other files next to it (such as images), the other pages it links
+/// to through `nav` and its git `history`, which dates it and lists its contributors.
+///
+/// Returns `None` when the source cannot be read.
+pub fn page_key(
+    site_key: SiteKey,
+    src_path: &Path,
+    base_path: &Path,
+    nav: &PageNav,
+    history: Option<&FileHistory>,
+) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    site_key.shared.hash(&mut hasher);
+    src_path.hash(&mut hasher);
+    nav.hash(&mut hasher);
+    history.hash(&mut hasher);
+    let content = fs::read_to_string(src_path).ok()?;
+    let (front_matter, _) = split_front_matter(&content);
+    if load_page_meta(src_path, base_path, front_matter).templated.unwrap_or(false) {
+        site_key.site.hash(&mut hasher);
+    }
+    expand_includes(&content, base_path).hash(&mut hasher);
+    let dir = src_path.parent().unwrap_or(Path::new(""));
+    for meta_dir in meta_dirs(src_path, base_path) {
+        find_meta_file(meta_dir).map(|meta_path| (fs::read(&meta_path).ok(), meta_path)).hash(&mut hasher);
+        fs::read(meta_dir.join("_defaults.yml")).ok().hash(&mut hasher);
+    }
+    let mut siblings: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(Result::ok).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    siblings.retain(|p| p.is_file() && p.extension().is_none_or(|e| e != "md"));
+    siblings.sort();
+    for sibling in siblings {
+        (&sibling, stat(&sibling)).hash(&mut hasher);
+    }
+    Some(hasher.finish())
+}
+
+/// Size and modification time of `path`, cheaper to hash than its contents.
+fn stat(path: &Path) -> Option<(u64, std::time::SystemTime)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
+
+/// Whether any template under `templates_path` (or a built-in one) reads the `site` global.
+/// Templates that fail to parse count as using it.
+fn templates_use_site(templates_path: &Path) -> bool {
+    let env = Environment::new();
+    let uses_site = |source: &str| {
+        env.template_from_str(source)
+            .map_or(true, |tmpl| tmpl.undeclared_variables(false).contains("site"))
+    };
+    DEFAULT_TEMPLATES.iter().any(|(_, source)| uses_site(source))
+        || list_files(templates_path)
+            .iter()
+            .any(|file| fs::read_to_string(file).map_or(true, |source| uses_site(&source)))
+}
+
+fn hash_dir(dir: &Path, hasher: &mut DefaultHasher) {
+    for file in list_files(dir) {
+        (&file, fs::read(&file).ok()).hash(hasher);
+    }
+}
+
+/// Every file under `dir`, sorted; a missing `dir` has none.
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut pending = vec![dir.to_path_buf()];
+    let mut files = Vec::new();
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
@@ -0,0 +1,141 @@
+use std::{fs, path::Path};
+
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
+use image::{ImageError, Rgba, RgbaImage, imageops::FilterType};
+use serde::{Deserialize, Serialize};
+
+use crate::slugify;
+
+/// Size of every card, the one Open Graph and Twitter recommend for large images.
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 630;
+
+/// Space kept free around the text.
+const MARGIN: f32 = 80.0;
+
+const TITLE_SIZE: f32 = 72.0;
+const SITE_NAME_SIZE: f32 = 36.0;
+
+/// Most title lines drawn; longer titles end with an ellipsis.
+const MAX_TITLE_LINES: usize = 4;
+
+/// Settings for the share images of posts, read from the `cards` block of the `seo` settings.
+///
+/// Cards are only rendered when this block is present.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CardConfig {
+    /// TrueType or OpenType font the title and site name are written with
+    pub font: String,
+    /// `#rrggbb` color, or the path of an image cropped to fill the card (default: `#1e293b`)
+    pub background: Option<String>,
+    /// `#rrggbb` color of the text (default: `#ffffff`)
+    pub text_color: Option<String>,
+}
+
+/// Permalink of the card of the page written to `output_path` (relative to the output directory):
+/// `og/<slug>.png`, with the slug built from the whole output path so cards never collide.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::cards::card_permalink;
+/// assert_eq!(card_permalink(Path::new("posts/hello/index.html")), "og/posts-hello.png");
+/// assert_eq!(card_permalink(Path::new("about.html")), "og/about.png");
+/// assert_eq!(card_permalink(Path::new("index.html")), "og/index.png");
+/// ```
+pub fn card_permalink(output_path: &Path) -> String {
+    let path = output_path.to_string_lossy().replace('\\', "/");
+    let path = path.strip_suffix("index.html").unwrap_or(&path);
+    let path = path.strip_suffix(".html").unwrap_or(path);
+    match slugify(path) {
+        slug if slug.is_empty() => "og/index.png".to_string(),
+        slug => format!("og/{}.png", slug),
+    }
+}
+
+/// Render the card of a post titled `title` to `dest` as a PNG: the title wrapped over the
+/// background, and `site_name` under it when given.
+pub fn write_card(dest: &Path, title: &str, site_name: Option<&str>, config: &CardConfig) -> Result<(), ImageError> {
+    let font = FontVec::try_from_vec(fs::read(&config.font)?)
+        .map_err(|e| ImageError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+    let background = config.background.as_deref().unwrap_or("#1e293b");
+    let mut card = match parse_color(background) {
+        Some(color) => RgbaImage::from_pixel(WIDTH, HEIGHT, color),
+        None => image::open(background)?.resize_to_fill(WIDTH, HEIGHT, FilterType::Lanczos3).to_rgba8(),
+    };
+    let color = config.text_color.as_deref().and_then(parse_color).unwrap_or(Rgba([255, 255, 255, 255]));
+
+    let title_font = font.as_scaled(PxScale::from(TITLE_SIZE));
+    let mut lines = wrap(title, &font, TITLE_SIZE, WIDTH as f32 - 2.0 * MARGIN);
+    if lines.len() > MAX_TITLE_LINES {
+        lines.truncate(MAX_TITLE_LINES);
+        lines[MAX_TITLE_LINES - 1].push('…');
+    }
+    let line_height = title_font.height() + title_font.line_gap();
+    let mut y = MARGIN;
+    for line in &lines {
+        draw_text(&mut card, &font, TITLE_SIZE, MARGIN, y, line, color);
+        y += line_height;
+    }
+    if let Some(site_name) = site_name {
+        let site_font = font.as_scaled(PxScale::from(SITE_NAME_SIZE));
+        draw_text(&mut card, &font, SITE_NAME_SIZE, MARGIN, HEIGHT as f32 - MARGIN - site_font.height(), site_name, color);
+    }
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    card.save(dest)
+}
+
+/// Parse a `#rrggbb` color.
+fn parse_color(value: &str) -> Option<Rgba<u8>> {
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Rgba([channel(0)?, channel(2)?, channel(4)?, 255]))
+}
+
+/// Split `text` into lines no wider than `max_width` at `size`, breaking between words.
+fn wrap(text: &str, font: &FontVec, size: f32, max_width: f32) -> Vec<String> {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let width = |text: &str| text.chars().map(|c| scaled.h_advance(scaled.glyph_id(c))).sum::<f32>();
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if width(line) + width(" ") + width(word) <= max_width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// Draw `text` on `card` with its top left corner at `(x, y)`, blending the glyph coverage with
+/// the background.
+fn draw_text(card: &mut RgbaImage, font: &FontVec, size: f32, x: f32, y: f32, text: &str, color: Rgba<u8>) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut caret = x;
+    for c in text.chars() {
+        let glyph = scaled.scaled_glyph(c);
+        let advance = scaled.h_advance(glyph.id);
+        let glyph = ab_glyph::Glyph { position: ab_glyph::point(caret, y + scaled.ascent()), ..glyph };
+        caret += advance;
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let (px, py) = (bounds.min.x as i64 + gx as i64, bounds.min.y as i64 + gy as i64);
+            if px < 0 || py < 0 || px >= WIDTH as i64 || py >= HEIGHT as i64 {
+                return;
+            }
+            let pixel = card.get_pixel_mut(px as u32, py as u32);
+            for i in 0..3 {
+                pixel.0[i] = (pixel.0[i] as f32 * (1.0 - coverage) + color.0[i] as f32 * coverage).round() as u8;
+            }
+        });
+    }
+}
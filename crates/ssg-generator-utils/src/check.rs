@@ -0,0 +1,455 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{assets::list_files, links::normalize, urls::UrlResolver};
+
+/// Why a link found by [`check_links`] does not resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkProblem {
+    /// No file of the output directory matches the link
+    MissingPage,
+    /// The page exists but has no element with the `id` of the fragment
+    MissingAnchor,
+}
+
+/// An internal link of a generated page that does not resolve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// Page holding the link, relative to the output directory with `/` separators
+    pub page: String,
+    /// The `href` as written in the page
+    pub href: String,
+    pub problem: LinkProblem,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self.problem {
+            LinkProblem::MissingPage => "no such page",
+            LinkProblem::MissingAnchor => "no such anchor",
+        };
+        write!(f, "{}: broken link {} ({})", self.page, self.href, problem)
+    }
+}
+
+/// Check every `<a href>` of the HTML pages under `dist_path` that points inside the site.
+///
+/// Site-absolute hrefs must start with the base path of `urls`; they and relative hrefs must name
+/// a file of `dist_path`, or a directory holding an `index.html`. A fragment must match the `id`
+/// (or `name`) of an element of the target page, except for the empty one and `#top`. Links with a
+/// scheme (`https:`, `mailto:`, ...) are not checked. Broken links are returned in page order.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{check::{check_links, LinkProblem}, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("check-links-doctest");
+/// std::fs::create_dir_all(dist.join("posts/foo")).unwrap();
+/// std::fs::write(dist.join("posts/foo/index.html"), r#"<h2 id="setup">Setup</h2>"#).unwrap();
+/// std::fs::write(
+///     dist.join("index.html"),
+///     r#"<a href="/blog/posts/foo/#setup">ok</a><a href="posts/foo#usage">no anchor</a><a href="/blog/bar/">no page</a>"#,
+/// )
+/// .unwrap();
+/// let broken = check_links(&dist, &UrlResolver::new("https://example.com", "/blog/")).unwrap();
+/// assert_eq!(broken.len(), 2);
+/// assert_eq!(broken[0].problem, LinkProblem::MissingAnchor);
+/// assert_eq!(broken[1].to_string(), "index.html: broken link /blog/bar/ (no such page)");
+/// ```
+pub fn check_links(dist_path: &Path, urls: &UrlResolver) -> std::io::Result<Vec<BrokenLink>> {
+    let Scan { files, hrefs, ids } = scan(dist_path)?;
+    let mut broken = Vec::new();
+    for (page, href) in hrefs {
+        if is_external(&href) {
+            continue;
+        }
+        let fragment = href.split_once('#').map_or("", |(_, fragment)| fragment);
+        let target = link_target(&files, &page, &href, urls.base_path());
+        let problem = match target {
+            None => Some(LinkProblem::MissingPage),
+            Some(target) => {
+                let missing = ids.get(&target).is_some_and(|ids| !ids.contains(fragment));
+                (!fragment.is_empty() && fragment != "top" && missing).then_some(LinkProblem::MissingAnchor)
+            }
+        };
+        if let Some(problem) = problem {
+            broken.push(BrokenLink { page, href, problem });
+        }
+    }
+    Ok(broken)
+}
+
+/// The generated site as seen by the link checks.
+struct Scan {
+    /// Every file of the output directory, relative to it with `/` separators
+    files: HashSet<String>,
+    /// `(page, href)` of every `<a href>`, in page order
+    hrefs: Vec<(String, String)>,
+    /// `id` and `name` attributes by page
+    ids: HashMap<String, HashSet<String>>,
+}
+
+fn scan(dist_path: &Path) -> std::io::Result<Scan> {
+    let files: HashSet<String> = list_files(dist_path)?
+        .iter()
+        .map(|file| file.strip_prefix(dist_path).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect();
+    let href_re = Regex::new(r#"(?i)<a\s[^>]*?\bhref\s*=\s*["']([^"']*)["']"#).unwrap();
+    let id_re = Regex::new(r#"(?i)\s(?:id|name)\s*=\s*["']([^"']*)["']"#).unwrap();
+
+    let mut pages: Vec<&String> = files.iter().filter(|file| file.ends_with(".html")).collect();
+    pages.sort();
+    let mut hrefs = Vec::new();
+    let mut ids = HashMap::new();
+    for page in pages {
+        let html = fs::read_to_string(dist_path.join(page))?;
+        hrefs.extend(href_re.captures_iter(&html).map(|caps| (page.clone(), unescape(&caps[1]))));
+        ids.insert(page.clone(), id_re.captures_iter(&html).map(|caps| unescape(&caps[1])).collect());
+    }
+    Ok(Scan { files, hrefs, ids })
+}
+
+/// Whether `href` has a scheme (`https:`, `mailto:`, ...) or is protocol-relative, so does not point
+/// inside the site.
+pub(crate) fn is_external(href: &str) -> bool {
+    href.starts_with("//") || href.split_once(':').is_some_and(|(scheme, _)| !scheme.contains(['/', '?', '#']))
+}
+
+/// The file of `files` the internal link `href` of `page` leads to, leaving out its query and
+/// fragment: `page` itself for a bare fragment, and `None` when no file matches. Site-absolute hrefs
+/// must start with `base`, the base path of the site.
+pub(crate) fn link_target(files: &HashSet<String>, page: &str, href: &str, base: &str) -> Option<String> {
+    let path = href.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        Some(page.to_string())
+    } else if path.starts_with('/') {
+        // `/blog` is the root of a site served from `/blog/` too
+        let rel = path.strip_prefix(base).or_else(|| (path == base.trim_end_matches('/')).then_some(""));
+        rel.and_then(|rel| resolve(files, rel))
+    } else {
+        let dir = Path::new(page).parent().unwrap_or(Path::new(""));
+        let rel = normalize(&dir.join(path)).to_string_lossy().replace('\\', "/");
+        resolve(files, &rel)
+    }
+}
+
+/// The file of `files` served for `rel`: itself, or the `index.html` of the directory it names.
+fn resolve(files: &HashSet<String>, rel: &str) -> Option<String> {
+    let rel = rel.trim_end_matches('/');
+    let index = if rel.is_empty() { "index.html".to_string() } else { format!("{}/index.html", rel) };
+    [rel.to_string(), index].into_iter().find(|candidate| files.contains(candidate))
+}
+
+/// Decode the character references templates use when escaping attribute values.
+pub(crate) fn unescape(value: &str) -> String {
+    let entity = Regex::new(r"&(#[xX][0-9a-fA-F]+|#[0-9]+|amp|quot|apos|lt|gt);").unwrap();
+    entity
+        .replace_all(value, |caps: &regex::Captures| {
+            let code = match &caps[1] {
+                "amp" => return "&".to_string(),
+                "quot" => return "\"".to_string(),
+                "apos" => return "'".to_string(),
+                "lt" => return "<".to_string(),
+                "gt" => return ">".to_string(),
+                hex if hex[1..].starts_with(['x', 'X']) => u32::from_str_radix(&hex[2..], 16).ok(),
+                dec => dec[1..].parse().ok(),
+            };
+            code.and_then(char::from_u32).map(String::from).unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// A violation of the llms.txt structure, or a broken entry, found by [`check_llms_txt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmsProblem {
+    /// Line of `llms.txt`, from 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LlmsProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "llms.txt:{}: {}", self.line, self.message)
+    }
+}
+
+/// Check the `llms.txt` of `dist_path` against the structure of the llms.txt spec: a single `#` title
+/// first, then free text, then `##` sections holding only `- [name](url): notes` list items.
+///
+/// Every entry linking inside the site (an absolute URL under the site of `urls`, a site path or a
+/// path relative to the site root) must name a file of `dist_path`, or a directory holding an
+/// `index.html`. With `require_descriptions`, every entry must also have notes.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{check::check_llms_txt, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("check-llms-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// std::fs::write(dist.join("cats.md"), "# Cats").unwrap();
+/// std::fs::write(dist.join("llms.txt"), "# Blog\n\nAbout cats.\n\n## Contents\n\n- [Cats](https://example.com/blog/cats.md): All about cats\n- [Dogs](https://example.com/blog/dogs.md)\n").unwrap();
+/// let urls = UrlResolver::new("https://example.com", "/blog/");
+/// let problems = check_llms_txt(&dist, &urls, true).unwrap();
+/// let problems: Vec<_> = problems.iter().map(|problem| problem.to_string()).collect();
+/// assert_eq!(problems, vec![
+///     "llms.txt:8: https://example.com/blog/dogs.md is not a file of the site",
+///     "llms.txt:8: entry Dogs has no description",
+/// ]);
+/// ```
+pub fn check_llms_txt(dist_path: &Path, urls: &UrlResolver, require_descriptions: bool) -> std::io::Result<Vec<LlmsProblem>> {
+    let content = fs::read_to_string(dist_path.join("llms.txt"))?;
+    let files: HashSet<String> = list_files(dist_path)?
+        .iter()
+        .map(|file| file.strip_prefix(dist_path).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect();
+    let entry_re = Regex::new(r"^- \[([^\]]+)\]\(([^)\s]+)\)(?::\s*(.*))?$").unwrap();
+    let site = urls.url("");
+    let base = urls.base_path();
+
+    let mut problems = Vec::new();
+    let mut problem = |line: usize, message: String| problems.push(LlmsProblem { line: line + 1, message });
+    let mut title = None;
+    let mut in_section = false;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(heading) = line.strip_prefix("# ") {
+            match title {
+                Some(_) => problem(i, "only one `#` title is allowed".to_string()),
+                None if heading.trim().is_empty() => problem(i, "the title is empty".to_string()),
+                None => {}
+            }
+            title = Some(i);
+            continue;
+        }
+        if title.is_none() {
+            problem(i, "llms.txt must start with a `#` title".to_string());
+            title = Some(i);
+        }
+        if line.starts_with("## ") {
+            in_section = true;
+        } else if line.starts_with('#') {
+            problem(i, "only `#` and `##` headings are allowed".to_string());
+        } else if in_section {
+            let Some(caps) = entry_re.captures(line) else {
+                problem(i, format!("`{}` is not a `- [name](url): notes` entry", line));
+                continue;
+            };
+            let url = &caps[2];
+            let path = url.split(['#', '?']).next().unwrap_or_default();
+            let rel = if let Some(rel) = path.strip_prefix(&site) {
+                Some(rel)
+            } else if path.starts_with('/') {
+                Some(path.strip_prefix(base).unwrap_or(path))
+            } else if path.contains(':') || path.starts_with("//") {
+                None
+            } else {
+                Some(path)
+            };
+            if let Some(rel) = rel
+                && resolve(&files, &normalize(Path::new(rel)).to_string_lossy().replace('\\', "/")).is_none()
+            {
+                problem(i, format!("{} is not a file of the site", url));
+            }
+            if require_descriptions && caps.get(3).is_none_or(|notes| notes.as_str().trim().is_empty()) {
+                problem(i, format!("entry {} has no description", &caps[1]));
+            }
+        }
+    }
+    if title.is_none() {
+        problem(0, "llms.txt is empty".to_string());
+    }
+    Ok(problems)
+}
+
+/// Settings for `check --external`, read from the `link_check` block of `cats-ssg.json`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct LinkCheckConfig {
+    /// URL prefixes never requested, such as sites that turn crawlers away
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    /// Requests in flight at once (default: 8)
+    pub concurrency: Option<usize>,
+    /// Requests started per second, over all hosts (default: 10)
+    pub requests_per_second: Option<u32>,
+    /// Days a URL that answered successfully is not requested again (default: 7)
+    pub cache_days: Option<u64>,
+    /// Seconds to wait for each answer (default: 10)
+    pub timeout: Option<u64>,
+}
+
+/// What is wrong with an external link found by [`check_external_links`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ExternalProblem {
+    /// The server answered with an error status, such as 404
+    Status { status: u16 },
+    /// The server answered with a redirect to `location`
+    Redirect { status: u16, location: String },
+    /// No answer: DNS, connection, TLS or timeout failures
+    Unreachable { reason: String },
+}
+
+/// An external URL that did not answer with a success status, and the pages linking to it.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExternalLink {
+    pub url: String,
+    /// Pages linking to `url`, relative to the output directory
+    pub pages: Vec<String>,
+    pub problem: ExternalProblem,
+}
+
+impl fmt::Display for ExternalLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            ExternalProblem::Status { status } => write!(f, "{} answered {}", self.url, status)?,
+            ExternalProblem::Redirect { status, location } => {
+                write!(f, "{} redirects ({}) to {}", self.url, status, location)?
+            }
+            ExternalProblem::Unreachable { reason } => write!(f, "{} is unreachable: {}", self.url, reason)?,
+        }
+        write!(f, " (linked from {})", self.pages.join(", "))
+    }
+}
+
+/// Request every `http(s)` link of the HTML pages under `dist_path` and report those that do not
+/// answer with a success status.
+///
+/// Each URL is requested once, without its fragment and without following redirects, with `HEAD`
+/// (falling back to `GET` for servers that refuse it). Requests run in parallel, throttled by
+/// `config`, and skip URLs starting with an allowlisted prefix. URLs that answered successfully
+/// are remembered in `cache_path` and not requested again for `cache_days`. Problems are returned
+/// sorted by URL.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::check::{check_external_links, LinkCheckConfig};
+/// let root = std::env::temp_dir().join("check-external-doctest");
+/// std::fs::create_dir_all(root.join("dist")).unwrap();
+/// std::fs::write(root.join("dist/index.html"), r#"<a href="https://example.com/">Example</a>"#).unwrap();
+/// let config = LinkCheckConfig { allowlist: vec!["https://example.com".to_string()], ..Default::default() };
+/// let problems = check_external_links(&root.join("dist"), &config, &root.join("links.json")).unwrap();
+/// assert!(problems.is_empty());
+/// ```
+pub fn check_external_links(
+    dist_path: &Path,
+    config: &LinkCheckConfig,
+    cache_path: &Path,
+) -> std::io::Result<Vec<ExternalLink>> {
+    let mut cache: HashMap<String, u64> = fs::read_to_string(cache_path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let max_age = config.cache_days.unwrap_or(7) * 24 * 60 * 60;
+
+    let mut links: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (page, href) in scan(dist_path)?.hrefs {
+        let url = href.split('#').next().unwrap_or_default();
+        let external = url.starts_with("http://") || url.starts_with("https://");
+        let allowed = config.allowlist.iter().any(|prefix| url.starts_with(prefix.as_str()));
+        let fresh = cache.get(url).is_some_and(|checked| now.saturating_sub(*checked) < max_age);
+        if external && !allowed && !fresh {
+            let pages = links.entry(url.to_string()).or_default();
+            if !pages.contains(&page) {
+                pages.push(page);
+            }
+        }
+    }
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .max_redirects(0)
+        .http_status_as_error(false)
+        .timeout_global(Some(Duration::from_secs(config.timeout.unwrap_or(10))))
+        .user_agent(concat!("cats-ssg/", env!("CARGO_PKG_VERSION"), " link checker"))
+        .build()
+        .into();
+    let limiter = RateLimiter::new(config.requests_per_second.unwrap_or(10));
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.concurrency.unwrap_or(8).max(1))
+        .build()
+        .map_err(std::io::Error::other)?;
+    let results: Vec<(String, Vec<String>, Option<ExternalProblem>)> = pool.install(|| {
+        links
+            .into_par_iter()
+            .map(|(url, pages)| {
+                limiter.wait();
+                let problem = request(&agent, &url);
+                (url, pages, problem)
+            })
+            .collect()
+    });
+
+    let mut problems = Vec::new();
+    for (url, pages, problem) in results {
+        match problem {
+            Some(problem) => problems.push(ExternalLink { url, pages, problem }),
+            None => {
+                cache.insert(url, now);
+            }
+        }
+    }
+    cache.retain(|_, checked| now.saturating_sub(*checked) < max_age);
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(cache_path, serde_json::to_string(&cache)?)?;
+    Ok(problems)
+}
+
+/// `None` when `url` answers with a success status.
+fn request(agent: &ureq::Agent, url: &str) -> Option<ExternalProblem> {
+    let response = match agent.head(url).call() {
+        Ok(response) if matches!(response.status().as_u16(), 405 | 501) => agent.get(url).call(),
+        other => other,
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => return Some(ExternalProblem::Unreachable { reason: e.to_string() }),
+    };
+    let status = response.status().as_u16();
+    match status {
+        200..=299 => None,
+        300..=399 => {
+            let location = response.headers().get("location").and_then(|v| v.to_str().ok()).unwrap_or_default();
+            Some(ExternalProblem::Redirect { status, location: location.to_string() })
+        }
+        _ => Some(ExternalProblem::Status { status }),
+    }
+}
+
+/// Spaces out the start of requests made from several threads.
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(per_second: u32) -> Self {
+        RateLimiter { interval: Duration::from_secs(1) / per_second.max(1), next: Mutex::new(Instant::now()) }
+    }
+
+    /// Block until the next request may start.
+    fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        thread::sleep(slot.saturating_duration_since(Instant::now()));
+    }
+}
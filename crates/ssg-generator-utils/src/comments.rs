@@ -0,0 +1,138 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::escape_html;
+
+/// Service hosting the comments of the posts, on GitHub Discussions or Issues.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentsProvider {
+    /// [giscus](https://giscus.app), backed by GitHub Discussions
+    #[default]
+    Giscus,
+    /// [utterances](https://utteranc.es), backed by GitHub Issues
+    Utterances,
+}
+
+impl CommentsProvider {
+    /// Script loading the comments widget.
+    pub fn script_url(self) -> &'static str {
+        match self {
+            CommentsProvider::Giscus => "https://giscus.app/client.js",
+            CommentsProvider::Utterances => "https://utteranc.es/client.js",
+        }
+    }
+}
+
+/// Comments under the posts, read from the `comments` block of `cats-ssg.json`; posts only get
+/// comments when this block is present, and a page can opt out with `comments: false` (or the home
+/// page opt in with `comments: true`).
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct CommentsConfig {
+    #[serde(default)]
+    pub provider: CommentsProvider,
+    /// GitHub repository holding the comments, as `owner/name`
+    pub repo: String,
+    /// Id of the repository, required by giscus
+    pub repo_id: Option<String>,
+    /// Discussion category of the comments, required by giscus
+    pub category: Option<String>,
+    /// Id of the discussion category, required by giscus
+    pub category_id: Option<String>,
+    /// Theme of the widget (default: following the color scheme of the reader)
+    pub theme: Option<String>,
+    /// How a page is matched with its discussion or issue, such as `pathname` (the default), `url` or
+    /// `title`
+    pub mapping: Option<String>,
+    /// Language of the giscus widget, such as `en`
+    pub lang: Option<String>,
+}
+
+/// The comments settings of a page, handed to templates as `comments`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Comments {
+    pub provider: CommentsProvider,
+    pub repo: String,
+    pub theme: String,
+    pub mapping: String,
+    /// Embed markup of the widget, which is added to the page unless its template writes it
+    pub html: String,
+}
+
+impl Comments {
+    /// The settings of `config` with the defaults filled in, and their embed markup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::comments::{Comments, CommentsConfig, CommentsProvider};
+    /// let config = CommentsConfig { provider: CommentsProvider::Utterances, repo: "ana/blog".to_string(), ..Default::default() };
+    /// let comments = Comments::new(&config);
+    /// assert_eq!(comments.theme, "preferred-color-scheme");
+    /// assert_eq!(
+    ///     comments.html,
+    ///     r#"<section class="comments"><script src="https://utteranc.es/client.js" repo="ana/blog" issue-term="pathname" theme="preferred-color-scheme" crossorigin="anonymous" async></script></section>"#
+    /// );
+    /// ```
+    pub fn new(config: &CommentsConfig) -> Self {
+        let mapping = config.mapping.clone().unwrap_or_else(|| "pathname".to_string());
+        let (theme, attributes) = match config.provider {
+            CommentsProvider::Giscus => {
+                let theme = config.theme.clone().unwrap_or_else(|| "preferred_color_scheme".to_string());
+                let attributes = [
+                    ("data-repo", Some(config.repo.as_str())),
+                    ("data-repo-id", config.repo_id.as_deref()),
+                    ("data-category", config.category.as_deref()),
+                    ("data-category-id", config.category_id.as_deref()),
+                    ("data-mapping", Some(mapping.as_str())),
+                    ("data-reactions-enabled", Some("1")),
+                    ("data-input-position", Some("bottom")),
+                    ("data-theme", Some(theme.as_str())),
+                    ("data-lang", Some(config.lang.as_deref().unwrap_or("en"))),
+                ]
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|value| format!(r#" {}="{}""#, name, escape_html(value))))
+                .collect::<String>();
+                (theme, attributes)
+            }
+            CommentsProvider::Utterances => {
+                let theme = config.theme.clone().unwrap_or_else(|| "preferred-color-scheme".to_string());
+                let attributes = [("repo", config.repo.as_str()), ("issue-term", mapping.as_str()), ("theme", theme.as_str())]
+                    .into_iter()
+                    .map(|(name, value)| format!(r#" {}="{}""#, name, escape_html(value)))
+                    .collect::<String>();
+                (theme, attributes)
+            }
+        };
+        let html = format!(
+            r#"<section class="comments"><script src="{}"{} crossorigin="anonymous" async></script></section>"#,
+            config.provider.script_url(),
+            attributes
+        );
+        Comments { provider: config.provider, repo: config.repo.clone(), theme, mapping, html }
+    }
+}
+
+/// `html` with the comments widget of `comments` added at the end of its `<main>`, or else of its
+/// `<body>`, unless it already loads the widget.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::comments::{inject_comments, Comments, CommentsConfig};
+/// let comments = Comments::new(&CommentsConfig { repo: "ana/blog".to_string(), ..Default::default() });
+/// let html = inject_comments("<body><main><p>Hi</p></main></body>", &comments);
+/// assert!(html.starts_with(r#"<body><main><p>Hi</p><section class="comments"><script src="https://giscus.app/client.js""#));
+/// assert_eq!(inject_comments(&html, &comments), html);
+/// ```
+pub fn inject_comments(html: &str, comments: &Comments) -> String {
+    if html.contains(comments.provider.script_url()) {
+        return html.to_string();
+    }
+    let end = Regex::new(r"(?i)</main\s*>").unwrap().find_iter(html).last();
+    let end = end.or_else(|| Regex::new(r"(?i)</body\s*>").unwrap().find_iter(html).last());
+    match end {
+        Some(end) => format!("{}{}{}", &html[..end.start()], comments.html, &html[end.start()..]),
+        None => format!("{}{}", html, comments.html),
+    }
+}
@@ -0,0 +1,54 @@
+use std::{collections::BTreeSet, fs, io::Write, path::Path};
+
+use flate2::{Compression, write::GzEncoder};
+use rayon::prelude::*;
+
+use crate::error::SsgError;
+
+/// Extensions of the outputs [`precompress`] writes compressed copies of.
+const TEXT_EXTENSIONS: &[&str] = &["html", "css", "js", "json", "svg", "txt", "xml"];
+
+/// Write a gzip (`.gz`) and a brotli (`.br`) copy next to every text file of `outputs`, given
+/// relative to `dist_path`, for hosts and CDNs that serve precompressed files as they are.
+///
+/// Both use the highest compression level, since they are written once and served many times.
+///
+/// Returns the paths of the copies relative to `dist_path`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::compress::precompress;
+/// let dist = std::env::temp_dir().join("precompress-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// std::fs::write(dist.join("index.html"), "<p>Hello</p>".repeat(100)).unwrap();
+/// let outputs = ["index.html".to_string(), "cat.png".to_string()].into();
+/// assert_eq!(precompress(&dist, &outputs).unwrap(), vec!["index.html.gz", "index.html.br"]);
+/// assert!(std::fs::metadata(dist.join("index.html.br")).unwrap().len() < 1200);
+/// ```
+pub fn precompress(dist_path: &Path, outputs: &BTreeSet<String>) -> Result<Vec<String>, SsgError> {
+    let written: Vec<[String; 2]> = outputs
+        .par_iter()
+        .filter(|rel| Path::new(rel).extension().and_then(|e| e.to_str()).is_some_and(|e| TEXT_EXTENSIONS.contains(&e)))
+        .map(|rel| {
+            let path = dist_path.join(rel);
+            let content = fs::read(&path).map_err(SsgError::io(&path))?;
+
+            let gz_path = dist_path.join(format!("{}.gz", rel));
+            let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+            gz.write_all(&content)
+                .and_then(|()| gz.finish())
+                .and_then(|bytes| fs::write(&gz_path, bytes))
+                .map_err(SsgError::io(&gz_path))?;
+
+            let br_path = dist_path.join(format!("{}.br", rel));
+            let mut br = Vec::new();
+            let params = brotli::enc::BrotliEncoderParams { quality: 11, ..Default::default() };
+            brotli::BrotliCompress(&mut content.as_slice(), &mut br, &params).map_err(SsgError::io(&br_path))?;
+            fs::write(&br_path, br).map_err(SsgError::io(&br_path))?;
+
+            Ok([format!("{}.gz", rel), format!("{}.br", rel)])
+        })
+        .collect::<Result<_, SsgError>>()?;
+    Ok(written.into_iter().flatten().collect())
+}
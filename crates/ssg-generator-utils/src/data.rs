@@ -0,0 +1,67 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use minijinja::Value;
+
+/// Load every `*.yml`, `*.yaml`, `*.json` and `*.toml` file directly under `dir`, keyed by file stem.
+///
+/// The result is meant to be exposed to templates as `data`, so `data/authors.yml` is available
+/// as `data.authors`. Files that fail to parse are reported and skipped; a missing `dir` yields
+/// an empty map.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::data::load_data_dir;
+/// let dir = std::env::temp_dir().join("data-doctest");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("authors.yml"), "- name: Ana\n").unwrap();
+/// std::fs::write(dir.join("site.toml"), "title = \"Blog\"\n").unwrap();
+/// std::fs::write(dir.join("links.json"), r#"{"medium": "https://medium.com"}"#).unwrap();
+/// let data = load_data_dir(&dir);
+/// assert_eq!(data["authors"].get_item_by_index(0).unwrap().get_attr("name").unwrap().as_str(), Some("Ana"));
+/// assert_eq!(data["site"].get_attr("title").unwrap().as_str(), Some("Blog"));
+/// assert_eq!(data["links"].get_attr("medium").unwrap().as_str(), Some("https://medium.com"));
+/// ```
+pub fn load_data_dir(dir: &Path) -> BTreeMap<String, Value> {
+    let mut data = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return data;
+    };
+    let mut paths: Vec<_> = entries.filter_map(Result::ok).map(|e| e.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        let (Some(stem), Some(ext)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            path.extension().and_then(|e| e.to_str()),
+        ) else {
+            continue;
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::warn!(category = "data", path = %path.display(), "Failed to read data file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let parsed = match ext {
+            "yml" | "yaml" => serde_yaml::from_str::<serde_yaml::Value>(&content)
+                .map(|v| Value::from_serialize(&v))
+                .map_err(|e| e.to_string()),
+            "json" => serde_json::from_str::<serde_json::Value>(&content)
+                .map(|v| Value::from_serialize(&v))
+                .map_err(|e| e.to_string()),
+            "toml" => toml::from_str::<toml::Value>(&content)
+                .map(|v| Value::from_serialize(&v))
+                .map_err(|e| e.to_string()),
+            _ => continue,
+        };
+        match parsed {
+            Ok(value) => {
+                data.insert(stem.to_string(), value);
+            }
+            Err(e) => tracing::warn!(category = "data", path = %path.display(), "Failed to parse data file {}: {}", path.display(), e),
+        }
+    }
+    data
+}
@@ -0,0 +1,90 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::urls::UrlResolver;
+
+/// Headers added to every path with `security_headers`.
+const SECURITY_HEADERS: &[(&str, &str)] = &[
+    ("Referrer-Policy", "strict-origin-when-cross-origin"),
+    ("X-Content-Type-Options", "nosniff"),
+    ("X-Frame-Options", "DENY"),
+];
+
+/// Files for hosts such as Netlify and Cloudflare Pages, read from the `deploy` block of
+/// `cats-ssg.json`.
+///
+/// Nothing is written when this block is absent.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeployConfig {
+    /// Write `_redirects` with a `301` from every alias of a page to the page
+    #[serde(default = "default_true")]
+    pub redirects: bool,
+    /// Headers by path pattern (relative to the site, `*` matching anything), written to `_headers`
+    #[serde(default)]
+    pub headers: BTreeMap<String, BTreeMap<String, String>>,
+    /// Also send `X-Content-Type-Options`, `X-Frame-Options` and `Referrer-Policy` for every path
+    #[serde(default)]
+    pub security_headers: bool,
+}
+
+impl Default for DeployConfig {
+    fn default() -> Self {
+        Self { redirects: true, headers: BTreeMap::new(), security_headers: false }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Write `_redirects` to `dist_path` with a permanent redirect for every `(from, to)` pair of
+/// site-absolute hrefs.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::deploy::write_redirects;
+/// let dist = std::env::temp_dir().join("redirects-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// write_redirects(&dist, &[("/blog/old/".to_string(), "/blog/new/".to_string())]).unwrap();
+/// assert_eq!(std::fs::read_to_string(dist.join("_redirects")).unwrap(), "/blog/old/ /blog/new/ 301\n");
+/// ```
+pub fn write_redirects(dist_path: &Path, redirects: &[(String, String)]) -> std::io::Result<()> {
+    let body: String = redirects.iter().map(|(from, to)| format!("{} {} 301\n", from, to)).collect();
+    fs::write(dist_path.join("_redirects"), body)
+}
+
+/// Write `_headers` to `dist_path` with the headers of `config`, the security headers first under
+/// the site root.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{deploy::{write_headers, DeployConfig}, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("headers-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// let mut config = DeployConfig::default();
+/// config.headers.insert("assets/*".to_string(), [("Cache-Control".to_string(), "max-age=31536000".to_string())].into());
+/// write_headers(&dist, &config, &UrlResolver::new("https://example.com", "/blog/")).unwrap();
+/// assert_eq!(
+///     std::fs::read_to_string(dist.join("_headers")).unwrap(),
+///     "/blog/assets/*\n  Cache-Control: max-age=31536000\n"
+/// );
+/// ```
+pub fn write_headers(dist_path: &Path, config: &DeployConfig, urls: &UrlResolver) -> std::io::Result<()> {
+    let mut body = String::new();
+    if config.security_headers {
+        body.push_str(&format!("{}*\n", urls.base_path()));
+        for (name, value) in SECURITY_HEADERS {
+            body.push_str(&format!("  {}: {}\n", name, value));
+        }
+    }
+    for (path, headers) in &config.headers {
+        body.push_str(&format!("{}\n", urls.href(path)));
+        for (name, value) in headers {
+            body.push_str(&format!("  {}: {}\n", name, value));
+        }
+    }
+    fs::write(dist_path.join("_headers"), body)
+}
@@ -0,0 +1,102 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+/// Everything that can go wrong while generating the site.
+///
+/// Each variant names the file involved: the page for page-level failures, the template for
+/// site-wide pages such as tags and the content index, and the output file for I/O.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use ssg_generator_utils::error::SsgError;
+/// let err = SsgError::Io {
+///     path: "dist/index.html".into(),
+///     source: io::Error::new(io::ErrorKind::PermissionDenied, "denied"),
+/// };
+/// assert_eq!(err.to_string(), "dist/index.html: denied");
+/// assert_eq!(err.category(), "io");
+/// ```
+#[derive(Debug, Error)]
+pub enum SsgError {
+    /// Reading or writing `path` failed
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A template, shortcode or templated page failed to render
+    #[error("template error in {}: {source}", path.display())]
+    Template {
+        path: PathBuf,
+        #[source]
+        source: minijinja::Error,
+    },
+    /// Front matter or a metadata file (see [`crate::find_meta_file`]) that cannot be parsed or does
+    /// not match [`crate::Meta`]
+    #[error("invalid metadata in {}: {source}", path.display())]
+    Meta {
+        path: PathBuf,
+        #[source]
+        source: serde_yaml::Error,
+    },
+    /// A code theme or syntax definition that could not be loaded
+    #[error("{0}")]
+    Syntax(String),
+    /// Two pages, `first` and `second`, resolve to the same output `path`
+    #[error("{} and {} are both written to {}", first.display(), second.display(), path.display())]
+    DuplicateOutput {
+        path: PathBuf,
+        first: PathBuf,
+        second: PathBuf,
+    },
+    /// Writing the sitemap to `path` failed
+    #[error("failed to write sitemap {}: {source}", path.display())]
+    Sitemap {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl SsgError {
+    /// Short name of the kind of failure, as logged in the `category` field.
+    pub fn category(&self) -> &'static str {
+        match self {
+            SsgError::Io { .. } => "io",
+            SsgError::Template { .. } => "template",
+            SsgError::Meta { .. } => "meta",
+            SsgError::Syntax(_) => "syntax",
+            SsgError::DuplicateOutput { .. } => "duplicate",
+            SsgError::Sitemap { .. } => "sitemap",
+        }
+    }
+
+    /// The file the failure is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            SsgError::Io { path, .. }
+            | SsgError::Template { path, .. }
+            | SsgError::Meta { path, .. }
+            | SsgError::DuplicateOutput { path, .. }
+            | SsgError::Sitemap { path, .. } => Some(path),
+            SsgError::Syntax(_) => None,
+        }
+    }
+
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| SsgError::Io { path, source }
+    }
+
+    pub(crate) fn template(path: impl Into<PathBuf>) -> impl FnOnce(minijinja::Error) -> Self {
+        let path = path.into();
+        move |source| SsgError::Template { path, source }
+    }
+}
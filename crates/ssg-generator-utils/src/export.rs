@@ -0,0 +1,186 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use regex::{Captures, Regex};
+
+use crate::{
+    assets::list_files,
+    check::{is_external, link_target, unescape},
+    urls::UrlResolver,
+};
+
+/// Export the site built at `dist_path`, or only its pages under the `section` directory (such as
+/// `posts`), as one self-contained HTML document for offline reading and archiving.
+///
+/// Every page becomes an `<article>` of the document, the section or site home page first and the
+/// rest by path, after a table of contents. The stylesheets of the pages are inlined into one
+/// `<style>`, and their images and the `url()`s of the stylesheets become base64 `data:` URIs;
+/// `srcset`s and `<source>`s are dropped for the inlined `src`. Links to exported pages point at
+/// their article, links to other files of the site at their absolute URL. Redirect stubs and
+/// scripts loaded from files are left out.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{export::export_single_html, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("export-doctest");
+/// std::fs::create_dir_all(dist.join("posts/cats")).unwrap();
+/// std::fs::write(dist.join("style.css"), "body { color: teal }").unwrap();
+/// std::fs::write(dist.join("posts/cats/cat.png"), [0x89, b'P', b'N', b'G']).unwrap();
+/// std::fs::write(
+///     dist.join("index.html"),
+///     r#"<html><head><title>Home</title><link rel="stylesheet" href="/style.css"></head><body><a href="/posts/cats/">Cats</a></body></html>"#,
+/// )
+/// .unwrap();
+/// std::fs::write(
+///     dist.join("posts/cats/index.html"),
+///     r#"<html><head><title>Cats</title></head><body><img src="cat.png" srcset="cat-2x.png 2x"></body></html>"#,
+/// )
+/// .unwrap();
+/// let html = export_single_html(&dist, &UrlResolver::new("https://example.com", "/"), None).unwrap();
+/// assert!(html.contains("<style>\nbody { color: teal }\n</style>"));
+/// assert!(html.contains(r##"<a href="#page-posts-cats-index-html">Cats</a>"##));
+/// assert!(html.contains(r#"<img src="data:image/png;base64,iVBORw==">"#));
+/// ```
+pub fn export_single_html(dist_path: &Path, urls: &UrlResolver, section: Option<&str>) -> io::Result<String> {
+    let files: HashSet<String> = list_files(dist_path)?
+        .iter()
+        .map(|file| file.strip_prefix(dist_path).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect();
+    let prefix = section.map(|section| format!("{}/", section.trim_matches('/'))).unwrap_or_default();
+    let home = format!("{}index.html", prefix);
+    let mut pages: Vec<&String> = files.iter().filter(|file| file.ends_with(".html") && file.starts_with(&prefix)).collect();
+    pages.sort_by_key(|&page| (*page != home, page));
+
+    let refresh_re = Regex::new(r#"(?i)<meta\s[^>]*http-equiv\s*=\s*["']?refresh"#).unwrap();
+    let title_re = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    let stylesheet_re = Regex::new(r#"(?i)<link\s[^>]*\brel\s*=\s*["']?stylesheet[^>]*>"#).unwrap();
+    let href_re = Regex::new(r#"(?i)\bhref\s*=\s*["']([^"']*)["']"#).unwrap();
+    let body_re = Regex::new(r"(?is)<body[^>]*>(.*)</body>").unwrap();
+
+    let mut articles = Vec::new();
+    for page in pages {
+        let html = fs::read_to_string(dist_path.join(page))?;
+        if refresh_re.is_match(&html) {
+            continue;
+        }
+        let title = title_re.captures(&html).map_or_else(|| page.clone(), |caps| caps[1].trim().to_string());
+        let stylesheets: Vec<String> = stylesheet_re
+            .find_iter(&html)
+            .filter_map(|link| href_re.captures(link.as_str()))
+            .map(|caps| unescape(&caps[1]))
+            .filter(|href| !is_external(href))
+            .filter_map(|href| link_target(&files, page, &href, urls.base_path()))
+            .collect();
+        let body = body_re.captures(&html).map_or(html.as_str(), |caps| caps.get(1).unwrap().as_str()).to_string();
+        articles.push((page.as_str(), title, stylesheets, body));
+    }
+    if articles.is_empty() {
+        let dir = dist_path.join(&prefix);
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("no pages to export under {}", dir.display())));
+    }
+    let ids: HashMap<&str, String> = articles.iter().map(|(page, ..)| (*page, article_id(page))).collect();
+
+    let mut css = String::new();
+    let mut inlined = HashSet::new();
+    for stylesheet in articles.iter().flat_map(|(_, _, stylesheets, _)| stylesheets) {
+        if inlined.insert(stylesheet) {
+            let content = fs::read_to_string(dist_path.join(stylesheet))?;
+            css.push_str(&inline_css_urls(&content, stylesheet, dist_path, &files, urls));
+            css.push('\n');
+        }
+    }
+
+    let script_re = Regex::new(r"(?is)<script\b[^>]*\bsrc\s*=[^>]*>\s*</script>").unwrap();
+    let source_re = Regex::new(r"(?i)<source\b[^>]*>").unwrap();
+    let srcset_re = Regex::new(r#"(?i)\s(?:srcset|sizes)\s*=\s*("[^"]*"|'[^']*')"#).unwrap();
+    let img_re = Regex::new(r#"(?i)(<img\b[^>]*?\bsrc\s*=\s*)(["'])([^"']*)["']"#).unwrap();
+    let link_re = Regex::new(r#"(?i)(<a\b[^>]*?\bhref\s*=\s*)(["'])([^"']*)["']"#).unwrap();
+
+    let title = &articles[0].1;
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<nav id=\"export-contents\">\n<ul>\n",
+        title, css
+    );
+    for (page, title, ..) in &articles {
+        out.push_str(&format!("<li><a href=\"#{}\">{}</a></li>\n", ids[page], title));
+    }
+    out.push_str("</ul>\n</nav>\n");
+    for (page, _, _, body) in &articles {
+        let body = script_re.replace_all(body, "");
+        let body = source_re.replace_all(&body, "");
+        let body = srcset_re.replace_all(&body, "");
+        let body = img_re.replace_all(&body, |caps: &Captures| {
+            let src = unescape(&caps[3]);
+            let data = (!is_external(&src))
+                .then(|| link_target(&files, page, &src, urls.base_path()))
+                .flatten()
+                .and_then(|target| data_uri(&dist_path.join(target)));
+            match data {
+                Some(data) => format!("{}{}{}{}", &caps[1], &caps[2], data, &caps[2]),
+                None => caps[0].to_string(),
+            }
+        });
+        let body = link_re.replace_all(&body, |caps: &Captures| {
+            let href = unescape(&caps[3]);
+            let target = (!is_external(&href)).then(|| link_target(&files, page, &href, urls.base_path())).flatten();
+            let href = match target {
+                Some(target) => match ids.get(target.as_str()) {
+                    // Ids of the page may be taken by other pages, so fragments lead to the article
+                    Some(id) => format!("#{}", id),
+                    None => urls.url(&target),
+                },
+                None => return caps[0].to_string(),
+            };
+            format!("{}{}{}{}", &caps[1], &caps[2], href, &caps[2])
+        });
+        out.push_str(&format!("<article id=\"{}\">\n{}\n</article>\n", ids[page], body.trim()));
+    }
+    out.push_str("</body>\n</html>\n");
+    Ok(out)
+}
+
+/// Id of the article of the page at `page`, relative to the output directory.
+fn article_id(page: &str) -> String {
+    let slug: String = page.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' }).collect();
+    format!("page-{}", slug)
+}
+
+/// `css`, the stylesheet at `stylesheet` under `dist_path`, with the files its `url()`s point at
+/// inlined as `data:` URIs.
+fn inline_css_urls(css: &str, stylesheet: &str, dist_path: &Path, files: &HashSet<String>, urls: &UrlResolver) -> String {
+    let url_re = Regex::new(r#"url\(\s*["']?([^"')]+?)["']?\s*\)"#).unwrap();
+    url_re
+        .replace_all(css, |caps: &Captures| {
+            let url = &caps[1];
+            let data = (!is_external(url) && !url.starts_with('#'))
+                .then(|| link_target(files, stylesheet, url, urls.base_path()))
+                .flatten()
+                .and_then(|target| data_uri(&dist_path.join(target)));
+            data.map_or_else(|| caps[0].to_string(), |data| format!("url(\"{}\")", data))
+        })
+        .into_owned()
+}
+
+/// The file at `path` as a base64 `data:` URI, typed after its extension.
+fn data_uri(path: &Path) -> Option<String> {
+    let mime = match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff2" => "font/woff2",
+        "woff" => "font/woff",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        _ => "application/octet-stream",
+    };
+    let bytes = fs::read(path).ok()?;
+    Some(format!("data:{};base64,{}", mime, STANDARD.encode(bytes)))
+}
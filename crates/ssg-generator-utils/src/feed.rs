@@ -0,0 +1,404 @@
+use chrono::{DateTime, FixedOffset};
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use crate::escape_html;
+
+/// Number of items written to the feed when `limit` is not configured.
+const DEFAULT_LIMIT: usize = 20;
+
+/// Podcast feed, relative to the output directory.
+pub const PODCAST_PERMALINK: &str = "podcast.xml";
+
+/// Channel-level settings for `feed.xml`, read from the `feed` block of `cats-ssg.json`.
+///
+/// With this block, the build writes an RSS 2.0 `feed.xml` of the pages without
+/// `exclude_from_index: true`, from their `date`, `description` (or summary) and authors, and a
+/// `tags/<tag>/feed.xml` of the pages of every tag, which tag pages link to (see
+/// [`crate::tags::write_tag_pages`]). Items are marked updated at the last git commit touching their
+/// source when that is later than their date, and pages with `audio` metadata get an enclosure of it.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct FeedConfig {
+    /// Channel title
+    pub title: Option<String>,
+    /// Channel description
+    pub description: Option<String>,
+    /// Author name attached to items without authors of their own
+    pub author: Option<String>,
+    /// Channel language (e.g. `en-us`)
+    pub language: Option<String>,
+    /// Maximum number of items, newest first
+    pub limit: Option<usize>,
+}
+
+/// A single post in the feed.
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+    pub title: String,
+    pub link: String,
+    pub pub_date: Option<DateTime<FixedOffset>>,
+    /// When the post was last changed, written as `<atom:updated>`
+    pub updated: Option<DateTime<FixedOffset>>,
+    /// Names of the authors of the post, written as `<dc:creator>` in place of the author of the channel
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    /// Media file of the post, such as a podcast episode
+    pub enclosure: Option<Enclosure>,
+}
+
+/// Audio file of a post, read from its `audio` metadata block.
+///
+/// Feeds attach it as an `<enclosure>` sized from the copy of a local file in the output directory, or
+/// else by `length`; enclosures of unknown size are written with a length of 0 and a warning.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Audio {
+    /// The file, as a site path, a path relative to the page (see [`audio_rel_path`]) or an absolute URL
+    pub file: String,
+    /// Running time, as `HH:MM:SS`, `MM:SS` or seconds
+    pub duration: Option<String>,
+    /// Media type (default: guessed from the extension of `file`, see [`audio_mime`])
+    pub mime: Option<String>,
+    /// Size of the file in bytes, for remote files (default: the size of the local copy)
+    pub length: Option<u64>,
+}
+
+/// Media file attached to a feed item as its `<enclosure>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enclosure {
+    /// Absolute URL of the file
+    pub url: String,
+    /// Size of the file in bytes
+    pub length: u64,
+    pub mime: String,
+    /// Running time, written as `<itunes:duration>` in the podcast
+    pub duration: Option<String>,
+}
+
+/// Media type of the audio file at `path`, from its extension; `audio/mpeg` when unknown.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::feed::audio_mime;
+/// assert_eq!(audio_mime("/episodes/01.M4A"), "audio/x-m4a");
+/// assert_eq!(audio_mime("https://cdn.example/01.mp3"), "audio/mpeg");
+/// ```
+pub fn audio_mime(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "m4a" => "audio/x-m4a",
+        "aac" => "audio/aac",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        _ => "audio/mpeg",
+    }
+}
+
+/// Where the audio `file` of the page written to `output_path` sits in the output directory, relative
+/// to it, or `None` for a remote URL.
+///
+/// Site paths are taken with their `base_path` prefix removed once, while relative paths point into
+/// the page bundle, which is copied next to the page.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::feed::audio_rel_path;
+/// let page = Path::new("episodes/01/index.html");
+/// assert_eq!(audio_rel_path("/my-blog/my-blog/01.mp3", page, "/my-blog/").as_deref(), Some("my-blog/01.mp3"));
+/// assert_eq!(audio_rel_path("/audio/01.mp3", page, "/").as_deref(), Some("audio/01.mp3"));
+/// assert_eq!(audio_rel_path("01.mp3", page, "/my-blog/").as_deref(), Some("episodes/01/01.mp3"));
+/// assert_eq!(audio_rel_path("../shared/intro.mp3", page, "/").as_deref(), Some("episodes/shared/intro.mp3"));
+/// assert_eq!(audio_rel_path("https://cdn.example/01.mp3", page, "/"), None);
+/// ```
+pub fn audio_rel_path(file: &str, output_path: &Path, base_path: &str) -> Option<String> {
+    if file.contains("://") {
+        return None;
+    }
+    let (dir, file) = match file.strip_prefix(base_path).or_else(|| file.strip_prefix('/')) {
+        Some(site_path) => (String::new(), site_path),
+        None => (output_path.parent().unwrap_or(Path::new("")).to_string_lossy().replace('\\', "/"), file),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in dir.split('/').chain(file.split('/')) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+/// Show settings of the podcast feed, read from the `podcast` block of `cats-ssg.json`; the
+/// podcast is only written when this block is present, as an iTunes-compatible `podcast.xml` of
+/// the pages with `audio` metadata (see [`write_podcast`]).
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct PodcastConfig {
+    /// Show title (default: the title of the `feed` block, or else `Podcast`)
+    pub title: Option<String>,
+    /// Show description
+    pub description: Option<String>,
+    /// Host of the show
+    pub author: Option<String>,
+    /// Email of the owner of the show, which directories use to verify it
+    pub email: Option<String>,
+    /// Artwork of the show, a square JPEG or PNG of 1400 to 3000 pixels, as a site path or an absolute URL
+    pub image: Option<String>,
+    /// Apple Podcasts category, such as `Technology`
+    pub category: Option<String>,
+    /// Show language (e.g. `en-us`)
+    pub language: Option<String>,
+    /// Mark the show as explicit
+    #[serde(default)]
+    pub explicit: bool,
+}
+
+/// Channel-level elements shared by the feeds and the podcast.
+struct Channel<'a> {
+    title: &'a str,
+    description: &'a str,
+    author: Option<&'a str>,
+    language: Option<&'a str>,
+    site_link: &'a str,
+    feed_link: &'a str,
+}
+
+/// Write an RSS 2.0 feed with the most recent `items` to `output`.
+///
+/// Items are sorted newest first (undated items go last) and truncated to `config.limit`. The
+/// `lastBuildDate` of the channel is the latest change of the items written. Items with an
+/// enclosure get an `<enclosure>` of it.
+/// `site_link` is the absolute URL of the site root used as the channel link, and `feed_link`
+/// the absolute URL the feed itself will be published at.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::feed::{write_feed, FeedConfig, FeedItem};
+/// let out = std::env::temp_dir().join("feed-doctest.xml");
+/// let items = vec![FeedItem {
+///     title: "Hello & welcome".to_string(),
+///     link: "https://example.com/hello.html".to_string(),
+///     pub_date: ssg_generator_utils::parse_date("2024-05-01"),
+///     updated: ssg_generator_utils::parse_date("2024-06-01"),
+///     authors: vec!["Ana".to_string(), "Bo".to_string()],
+///     description: None,
+///     enclosure: None,
+/// }];
+/// write_feed(&FeedConfig::default(), "https://example.com/", "https://example.com/feed.xml", &items, out.to_str().unwrap()).unwrap();
+/// let xml = std::fs::read_to_string(&out).unwrap();
+/// assert!(xml.contains("<title>Hello &amp; welcome</title>"));
+/// assert!(xml.contains("<pubDate>Wed, 1 May 2024 00:00:00 +0000</pubDate>"));
+/// assert!(xml.contains("<lastBuildDate>Sat, 1 Jun 2024 00:00:00 +0000</lastBuildDate>"));
+/// assert!(xml.contains("<atom:updated>2024-06-01T00:00:00+00:00</atom:updated>"));
+/// assert!(xml.contains("<dc:creator>Ana, Bo</dc:creator>"));
+/// ```
+pub fn write_feed(
+    config: &FeedConfig,
+    site_link: &str,
+    feed_link: &str,
+    items: &[FeedItem],
+    output: &str,
+) -> std::io::Result<()> {
+    let mut items: Vec<&FeedItem> = items.iter().collect();
+    items.sort_by_key(|item| Reverse(item.pub_date));
+    items.truncate(config.limit.unwrap_or(DEFAULT_LIMIT));
+    let channel = Channel {
+        title: config.title.as_deref().unwrap_or("Feed"),
+        description: config.description.as_deref().unwrap_or(""),
+        author: config.author.as_deref(),
+        language: config.language.as_deref(),
+        site_link,
+        feed_link,
+    };
+    write_rss(&channel, None, &items, output)
+}
+
+/// Write an iTunes-compatible podcast feed of the `items` with an enclosure to `output`, every
+/// episode newest first.
+///
+/// The channel carries the `itunes:` author, owner, artwork (`image_url`, the absolute URL of the
+/// `image` of `config`), category and explicit flag of `config`, and every episode its
+/// `<enclosure>` and `<itunes:duration>`. `feed_config` stands in for the title, description,
+/// author and language `config` lacks.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::feed::{write_podcast, Enclosure, FeedConfig, FeedItem, PodcastConfig};
+/// let out = std::env::temp_dir().join("podcast-doctest.xml");
+/// let episode = FeedItem {
+///     title: "Episode 1".to_string(),
+///     link: "https://example.com/ep1.html".to_string(),
+///     pub_date: ssg_generator_utils::parse_date("2024-05-01"),
+///     updated: None,
+///     authors: Vec::new(),
+///     description: Some("Cats".to_string()),
+///     enclosure: Some(Enclosure {
+///         url: "https://example.com/ep1.mp3".to_string(),
+///         length: 1024,
+///         mime: "audio/mpeg".to_string(),
+///         duration: Some("12:34".to_string()),
+///     }),
+/// };
+/// let post = FeedItem { title: "Post".to_string(), enclosure: None, ..episode.clone() };
+/// let config = PodcastConfig { title: Some("Cat Talk".to_string()), category: Some("Leisure".to_string()), ..Default::default() };
+/// let artwork = Some("https://example.com/cover.png");
+/// write_podcast(&config, &FeedConfig::default(), artwork, "https://example.com/", "https://example.com/podcast.xml", &[episode, post], out.to_str().unwrap()).unwrap();
+/// let xml = std::fs::read_to_string(&out).unwrap();
+/// assert!(xml.contains(r#"<itunes:image href="https://example.com/cover.png"/>"#));
+/// assert!(xml.contains(r#"<enclosure url="https://example.com/ep1.mp3" length="1024" type="audio/mpeg"/>"#));
+/// assert!(xml.contains("<itunes:duration>12:34</itunes:duration>"));
+/// assert!(!xml.contains("<title>Post</title>"));
+/// ```
+pub fn write_podcast(
+    config: &PodcastConfig,
+    feed_config: &FeedConfig,
+    image_url: Option<&str>,
+    site_link: &str,
+    feed_link: &str,
+    items: &[FeedItem],
+    output: &str,
+) -> std::io::Result<()> {
+    let mut items: Vec<&FeedItem> = items.iter().filter(|item| item.enclosure.is_some()).collect();
+    items.sort_by_key(|item| Reverse(item.pub_date));
+    let channel = Channel {
+        title: config.title.as_deref().or(feed_config.title.as_deref()).unwrap_or("Podcast"),
+        description: config.description.as_deref().or(feed_config.description.as_deref()).unwrap_or(""),
+        author: config.author.as_deref().or(feed_config.author.as_deref()),
+        language: config.language.as_deref().or(feed_config.language.as_deref()),
+        site_link,
+        feed_link,
+    };
+    write_rss(&channel, Some((config, image_url)), &items, output)
+}
+
+/// Write the RSS 2.0 document of `channel` and `items` to `output`, with the `itunes:` elements of
+/// a podcast when `podcast` is given.
+fn write_rss(channel: &Channel, podcast: Option<(&PodcastConfig, Option<&str>)>, items: &[&FeedItem], output: &str) -> std::io::Result<()> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    let mut rss = BytesStart::new("rss");
+    rss.push_attribute(("version", "2.0"));
+    rss.push_attribute(("xmlns:atom", "http://www.w3.org/2005/Atom"));
+    rss.push_attribute(("xmlns:dc", "http://purl.org/dc/elements/1.1/"));
+    if podcast.is_some() {
+        rss.push_attribute(("xmlns:itunes", "http://www.itunes.com/dtds/podcast-1.0.dtd"));
+    }
+    writer.write_event(Event::Start(rss))?;
+    writer.write_event(Event::Start(BytesStart::new("channel")))?;
+
+    write_text(&mut writer, "title", channel.title)?;
+    write_text(&mut writer, "link", channel.site_link)?;
+    write_text(&mut writer, "description", channel.description)?;
+    if let Some(language) = channel.language {
+        write_text(&mut writer, "language", language)?;
+    }
+    if let Some(date) = items.iter().filter_map(|item| item.updated.max(item.pub_date)).max() {
+        write_text(&mut writer, "lastBuildDate", &date.to_rfc2822())?;
+    }
+    let mut self_link = BytesStart::new("atom:link");
+    self_link.push_attribute(("href", channel.feed_link));
+    self_link.push_attribute(("rel", "self"));
+    self_link.push_attribute(("type", "application/rss+xml"));
+    writer.write_event(Event::Empty(self_link))?;
+    if let Some((config, image_url)) = podcast {
+        if let Some(author) = channel.author {
+            write_text(&mut writer, "itunes:author", author)?;
+        }
+        if config.author.is_some() || config.email.is_some() {
+            writer.write_event(Event::Start(BytesStart::new("itunes:owner")))?;
+            if let Some(author) = channel.author {
+                write_text(&mut writer, "itunes:name", author)?;
+            }
+            if let Some(email) = &config.email {
+                write_text(&mut writer, "itunes:email", email)?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("itunes:owner")))?;
+        }
+        if let Some(image_url) = image_url {
+            let mut image = BytesStart::new("itunes:image");
+            image.push_attribute(("href", image_url));
+            writer.write_event(Event::Empty(image))?;
+        }
+        if let Some(category) = &config.category {
+            let mut element = BytesStart::new("itunes:category");
+            element.push_attribute(("text", category.as_str()));
+            writer.write_event(Event::Empty(element))?;
+        }
+        write_text(&mut writer, "itunes:explicit", if config.explicit { "true" } else { "false" })?;
+    }
+
+    for item in items {
+        writer.write_event(Event::Start(BytesStart::new("item")))?;
+        write_text(&mut writer, "title", &item.title)?;
+        write_text(&mut writer, "link", &item.link)?;
+        write_text(&mut writer, "guid", &item.link)?;
+        if let Some(date) = item.pub_date {
+            write_text(&mut writer, "pubDate", &date.to_rfc2822())?;
+        }
+        if let Some(updated) = item.updated {
+            write_text(&mut writer, "atom:updated", &updated.to_rfc3339())?;
+        }
+        if !item.authors.is_empty() {
+            write_text(&mut writer, "dc:creator", &item.authors.join(", "))?;
+        } else if let Some(author) = channel.author {
+            write_text(&mut writer, "dc:creator", author)?;
+        }
+        if let Some(description) = &item.description {
+            write_text(&mut writer, "description", description)?;
+        }
+        if let Some(enclosure) = &item.enclosure {
+            let length = enclosure.length.to_string();
+            let mut element = BytesStart::new("enclosure");
+            element.push_attribute(("url", enclosure.url.as_str()));
+            element.push_attribute(("length", length.as_str()));
+            element.push_attribute(("type", enclosure.mime.as_str()));
+            writer.write_event(Event::Empty(element))?;
+            if let Some(duration) = enclosure.duration.as_deref().filter(|_| podcast.is_some()) {
+                write_text(&mut writer, "itunes:duration", duration)?;
+            }
+        }
+        writer.write_event(Event::End(BytesEnd::new("item")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("channel")))?;
+    writer.write_event(Event::End(BytesEnd::new("rss")))?;
+
+    let result = writer.into_inner().into_inner();
+    let mut file = File::create(output)?;
+    file.write_all(&result)
+}
+
+/// The `<link>` letting readers and browsers discover the feed titled `title` at `feed_link`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::feed::discovery_link;
+/// assert_eq!(
+///     discovery_link("Cats & dogs", "https://example.com/tags/cats/feed.xml"),
+///     r#"<link rel="alternate" type="application/rss+xml" title="Cats &amp; dogs" href="https://example.com/tags/cats/feed.xml">"#
+/// );
+/// ```
+pub fn discovery_link(title: &str, feed_link: &str) -> String {
+    format!(r#"<link rel="alternate" type="application/rss+xml" title="{}" href="{}">"#, escape_html(title), escape_html(feed_link))
+}
+
+fn write_text(writer: &mut Writer<Cursor<Vec<u8>>>, tag: &str, text: &str) -> std::io::Result<()> {
+    writer.write_event(Event::Start(BytesStart::new(tag)))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(BytesEnd::new(tag)))?;
+    Ok(())
+}
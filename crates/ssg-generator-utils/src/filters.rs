@@ -0,0 +1,104 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use minijinja::{Environment, Value};
+use pulldown_cmark::{Options, Parser as MdParser, html};
+
+use crate::{parse_date, slugify, urls::UrlResolver};
+
+/// Format used by `dateformat` when none is given.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Register the site's custom filters on `env`:
+///
+/// - `slugify`: see [`crate::slugify`].
+/// - `dateformat(format="%Y-%m-%d")`: format a date string or a [`crate::date_context`] object with
+///   a chrono `strftime` pattern; unparseable input is returned unchanged.
+/// - `truncate_words(count=30, end="…")`: keep the first `count` words.
+/// - `markdown`: render a Markdown string to HTML (no syntax highlighting).
+/// - `absolute_url`: turn a site path (with or without the base path) into a full URL (see
+///   [`UrlResolver::absolute_url`]).
+///
+/// # Examples
+///
+/// ```
+/// use minijinja::{Environment, context};
+/// use ssg_generator_utils::{filters::register_filters, urls::UrlResolver};
+/// let mut env = Environment::new();
+/// register_filters(&mut env, &UrlResolver::new("https://example.com", "/blog/"));
+/// let render = |src: &str| env.render_str(src, context! {}).unwrap();
+/// assert_eq!(render(r#"{{ "Hello World" | slugify }}"#), "hello-world");
+/// assert_eq!(render(r#"{{ "2024-05-01" | dateformat("%d %B %Y") }}"#), "01 May 2024");
+/// assert_eq!(render(r#"{{ "one two three" | truncate_words(2) }}"#), "one two…");
+/// assert_eq!(render(r#"{{ "*hi*" | markdown }}"#), "<p><em>hi</em></p>\n");
+/// assert_eq!(render(r#"{{ "/blog/posts/a.html" | absolute_url }}"#), "https://example.com/blog/posts/a.html");
+/// ```
+pub fn register_filters(env: &mut Environment, urls: &UrlResolver) {
+    env.add_filter("slugify", |value: &str| slugify(value));
+    env.add_filter("dateformat", dateformat);
+    env.add_filter("truncate_words", truncate_words);
+    env.add_filter("markdown", markdown);
+
+    let urls = urls.clone();
+    env.add_filter("absolute_url", move |path: &str| urls.absolute_url(path));
+}
+
+/// Register the `asset(path)` function on `env`, resolving the path of a static file to its href,
+/// under its fingerprinted name when `fingerprints` has one (see
+/// [`crate::assets::fingerprint_static_dir`]).
+///
+/// # Examples
+///
+/// ```
+/// use minijinja::{Environment, context};
+/// use ssg_generator_utils::{filters::register_asset_function, urls::UrlResolver};
+/// let mut env = Environment::new();
+/// let fingerprints = [("app.css".to_string(), "app.3fa9c2d1.css".to_string())].into();
+/// register_asset_function(&mut env, &UrlResolver::new("https://example.com", "/blog/"), fingerprints);
+/// assert_eq!(env.render_str(r#"{{ asset("app.css") }}"#, context! {}).unwrap(), "/blog/app.3fa9c2d1.css");
+/// assert_eq!(env.render_str(r#"{{ asset("/logo.png") }}"#, context! {}).unwrap(), "/blog/logo.png");
+/// ```
+pub fn register_asset_function(env: &mut Environment, urls: &UrlResolver, fingerprints: BTreeMap<String, String>) {
+    let urls = urls.clone();
+    env.add_function("asset", move |path: &str| {
+        let path = path.trim_start_matches('/');
+        urls.href(fingerprints.get(path).map_or(path, String::as_str))
+    });
+}
+
+fn dateformat(value: Value, format: Option<&str>) -> String {
+    let raw = value
+        .get_attr("iso")
+        .ok()
+        .filter(|iso| !iso.is_undefined())
+        .unwrap_or(value);
+    let raw = raw.as_str().map(str::to_string).unwrap_or_else(|| raw.to_string());
+    let Some(date) = parse_date(&raw) else {
+        return raw;
+    };
+    // An invalid format string makes chrono's Display fail instead of producing output
+    let mut formatted = String::new();
+    match write!(formatted, "{}", date.format(format.unwrap_or(DEFAULT_DATE_FORMAT))) {
+        Ok(()) => formatted,
+        Err(_) => raw,
+    }
+}
+
+fn truncate_words(value: &str, count: Option<usize>, end: Option<&str>) -> String {
+    let count = count.unwrap_or(30);
+    let words: Vec<&str> = value.split_whitespace().collect();
+    if words.len() <= count {
+        return value.to_string();
+    }
+    format!("{}{}", words[..count].join(" "), end.unwrap_or("…"))
+}
+
+fn markdown(value: &str) -> Value {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    let mut out = String::new();
+    html::push_html(&mut out, MdParser::new_ext(value, options));
+    Value::from_safe_string(out)
+}
@@ -0,0 +1,234 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser as MdParser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::PageOutput;
+
+/// Name of the capsule directory, next to the output directory, used when `dist` is not configured.
+const DEFAULT_DIST: &str = "dist-gemini";
+
+/// Heading of the listing [`write_index`] adds to the home page.
+const PAGES_HEADING: &str = "\n\n## Pages\n\n";
+
+/// Settings for the Gemini capsule mirroring the site, read from the `gemini` block of
+/// `cats-ssg.json`; the capsule is only written when this block is present.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct GeminiConfig {
+    /// Directory the capsule is written to (default: `dist-gemini` next to the output directory)
+    pub dist: Option<PathBuf>,
+    /// Heading of the capsule index (default: the `llm_title` of the site)
+    pub title: Option<String>,
+}
+
+impl GeminiConfig {
+    /// Directory the capsule of the site built into `dist_path` is written to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use ssg_generator_utils::gemini::GeminiConfig;
+    /// let config = GeminiConfig::default();
+    /// assert_eq!(config.dist_path(Path::new("site/dist")), Path::new("site/dist-gemini"));
+    /// ```
+    pub fn dist_path(&self, dist_path: &Path) -> PathBuf {
+        self.dist.clone().unwrap_or_else(|| dist_path.with_file_name(DEFAULT_DIST))
+    }
+}
+
+/// Capsule path of the page served on the web at the site path `permalink` (without the base
+/// path): `.gmi` instead of `.html`, and `index.gmi` in directories.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::gemini::gemini_path;
+/// assert_eq!(gemini_path("posts/cats/"), "posts/cats/index.gmi");
+/// assert_eq!(gemini_path("about.html"), "about.gmi");
+/// assert_eq!(gemini_path(""), "index.gmi");
+/// ```
+pub fn gemini_path(permalink: &str) -> String {
+    if permalink.is_empty() || permalink.ends_with('/') {
+        format!("{}index.gmi", permalink)
+    } else {
+        format!("{}.gmi", permalink.strip_suffix(".html").unwrap_or(permalink))
+    }
+}
+
+/// Gemtext of the Markdown page `md` titled `title`.
+///
+/// Gemtext has no inline links, so the links and images of each paragraph, list or quote follow it
+/// as `=>` lines, pointing at what `resolve` gives for their target. Headings deeper than `###` are
+/// flattened to it, nested lists to one level, and tables and code blocks become preformatted text.
+/// HTML is left out.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::gemini::markdown_to_gemtext;
+/// let md = "Cats nap a [lot](naps.md).\n\n- Sofa\n- Box\n\n```rust\nnap();\n```\n";
+/// let gmi = markdown_to_gemtext(md, "Cats", |dest| format!("/{}", dest.replace(".md", ".gmi")));
+/// assert_eq!(gmi, "# Cats\n\nCats nap a lot.\n=> /naps.gmi lot\n\n* Sofa\n* Box\n\n```rust\nnap();\n```\n");
+/// ```
+pub fn markdown_to_gemtext(md: &str, title: &str, resolve: impl Fn(&str) -> String) -> String {
+    let mut out = format!("# {}\n\n", title);
+    let mut text = String::new();
+    // `(url, label)` of the links of the block being written, and of the link being read
+    let mut links: Vec<(String, String)> = Vec::new();
+    let mut link: Option<(String, String)> = None;
+    let mut in_image = false;
+    let mut lists = 0;
+    let mut quotes = 0;
+    let mut in_code_block = false;
+
+    let flush_links = |out: &mut String, links: &mut Vec<(String, String)>| {
+        for (url, label) in links.drain(..) {
+            match label.trim() {
+                "" => out.push_str(&format!("=> {}\n", url)),
+                label => out.push_str(&format!("=> {} {}\n", url, label)),
+            }
+        }
+        out.push('\n');
+    };
+    for event in MdParser::new_ext(md, Options::all()) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                in_code_block = true;
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => lang.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                out.push_str(&format!("```{}\n", lang));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                out.push_str("```\n\n");
+            }
+            Event::Text(t) if in_code_block => out.push_str(&t),
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, label)) = &mut link {
+                    label.push_str(&t);
+                }
+                // The alt text of an image only labels its link
+                if !in_image {
+                    text.push_str(&t);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::Start(Tag::Link { dest_url, .. }) => link = Some((resolve(&dest_url), String::new())),
+            Event::End(TagEnd::Link) => links.extend(link.take()),
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                link = Some((resolve(&dest_url), String::new()));
+                in_image = true;
+            }
+            Event::End(TagEnd::Image) => {
+                links.extend(link.take());
+                in_image = false;
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                let hashes = match level {
+                    HeadingLevel::H1 => "#",
+                    HeadingLevel::H2 => "##",
+                    _ => "###",
+                };
+                out.push_str(&format!("{} {}\n", hashes, text.trim()));
+                text.clear();
+                flush_links(&mut out, &mut links);
+            }
+            Event::Start(Tag::BlockQuote(_)) => quotes += 1,
+            Event::End(TagEnd::BlockQuote(_)) => {
+                quotes -= 1;
+                if quotes == 0 && lists == 0 {
+                    flush_links(&mut out, &mut links);
+                }
+            }
+            Event::Start(Tag::List(_)) => {
+                line(&mut out, &mut text, "* ", quotes);
+                lists += 1;
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists -= 1;
+                if lists == 0 && quotes == 0 {
+                    flush_links(&mut out, &mut links);
+                }
+            }
+            Event::End(TagEnd::Item) => line(&mut out, &mut text, "* ", quotes),
+            Event::End(TagEnd::Paragraph) => {
+                if lists > 0 {
+                    line(&mut out, &mut text, "* ", quotes);
+                } else {
+                    line(&mut out, &mut text, "", quotes);
+                    if quotes == 0 {
+                        flush_links(&mut out, &mut links);
+                    }
+                }
+            }
+            Event::Start(Tag::Table(_)) => out.push_str("```\n"),
+            Event::End(TagEnd::TableCell) => text.push_str(" | "),
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => {
+                out.push_str(&format!("{}\n", text.trim_end_matches(" | ")));
+                text.clear();
+            }
+            Event::End(TagEnd::Table) => {
+                out.push_str("```\n");
+                flush_links(&mut out, &mut links);
+            }
+            _ => {}
+        }
+    }
+    if !links.is_empty() {
+        flush_links(&mut out, &mut links);
+    }
+    let mut out = out.trim_end().to_string();
+    out.push('\n');
+    out
+}
+
+/// Write the pending `text` to `out` as one line, after `marker` and the `>` of every open quote.
+fn line(out: &mut String, text: &mut String, marker: &str, quotes: usize) {
+    let content = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !content.is_empty() {
+        let quote = if quotes > 0 { "> " } else { "" };
+        out.push_str(&format!("{}{}{}\n", quote, marker, content));
+    }
+    text.clear();
+}
+
+/// Write the index of the capsule at `dist_path`: a gemlog-style `=> path date title` line per page
+/// of `pages` that was written to it, newest first, under `title`.
+///
+/// When the home page of the site is part of the capsule, the listing is added to the end of its
+/// `index.gmi` under a `## Pages` heading, replacing the one of a previous build; otherwise it makes
+/// up a new one. Returns the path of the index.
+pub fn write_index(dist_path: &Path, title: &str, pages: &[PageOutput]) -> io::Result<PathBuf> {
+    let index = dist_path.join("index.gmi");
+    let home = pages.iter().any(|page| page.gmi_path.as_deref() == Some(index.as_path()));
+    let mut gmi = if home {
+        let content = fs::read_to_string(&index)?;
+        // A home page reused from the page cache still ends with the listing of the last build
+        let content = match content.rsplit_once(PAGES_HEADING) {
+            Some((home, listing)) if listing.lines().all(|line| line.starts_with("=> ")) => home.to_string(),
+            _ => content,
+        };
+        format!("{}{}", content.trim_end(), PAGES_HEADING)
+    } else {
+        format!("# {}\n\n", title)
+    };
+    for page in pages {
+        let Some(rel) = page.gmi_path.as_deref().and_then(|path| path.strip_prefix(dist_path).ok()) else {
+            continue;
+        };
+        if rel == Path::new("index.gmi") {
+            continue;
+        }
+        let date = page.meta.date.as_deref().and_then(crate::parse_date);
+        let date = date.map(|date| format!("{} ", date.format("%Y-%m-%d"))).unwrap_or_default();
+        gmi.push_str(&format!("=> /{} {}{}\n", rel.to_string_lossy().replace('\\', "/"), date, page.title));
+    }
+    fs::create_dir_all(dist_path)?;
+    fs::write(&index, gmi)?;
+    Ok(index)
+}
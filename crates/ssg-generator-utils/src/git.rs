@@ -0,0 +1,195 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+
+/// When a file was first and last committed, read from the git history of its repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GitDates {
+    /// Date of the first commit adding the file, following renames
+    pub created: DateTime<FixedOffset>,
+    /// Date of the last commit touching the file
+    pub modified: DateTime<FixedOffset>,
+}
+
+/// Someone who committed to a file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GitAuthor {
+    pub name: String,
+    pub email: String,
+    /// Commits of this author touching the file
+    pub commits: usize,
+}
+
+/// What the git history says of one file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileHistory {
+    pub dates: GitDates,
+    /// The authors of the commits touching the file, in the order of their first commit, so the
+    /// author who added the file comes first.
+    ///
+    /// Authors are told apart by email, ignoring case, and named as in their latest commit; both go
+    /// through the `.mailmap` of the repository.
+    pub authors: Vec<GitAuthor>,
+}
+
+/// The git history of the files under a directory, read with a single `git log` so a build spawns
+/// git once rather than for every page.
+#[derive(Debug, Default, Clone)]
+pub struct GitHistory {
+    root: PathBuf,
+    /// By path relative to `root`
+    files: HashMap<PathBuf, FileHistory>,
+}
+
+impl GitHistory {
+    /// Read the history of every file under `dir`, following renames; empty outside a git checkout
+    /// or without git.
+    ///
+    /// Git runs in `dir`, so the content directory may be a repository of its own.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::git::GitHistory;
+    /// let dir = std::env::temp_dir().join("git-history-doctest");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("draft.md"), "# Draft").unwrap();
+    /// let history = GitHistory::load(&dir);
+    /// assert_eq!(history.file(&dir.join("draft.md")), None);
+    /// ```
+    pub fn load(dir: &Path) -> Self {
+        let root = dir.to_path_buf();
+        let current_dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let output = match Command::new("git")
+            .current_dir(current_dir)
+            .args(["-c", "core.quotePath=false", "log", "-M", "--relative", "--name-status", "--format=%x1e%cI%x1f%aN%x1f%aE", "--", "."])
+            .output()
+        {
+            Ok(output) if output.status.success() => output,
+            _ => return Self { root, files: HashMap::new() },
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // Commits come newest first; what each path of an older commit is called today, `None`
+        // once the file it named was added or deleted in a newer one
+        let mut renamed: HashMap<&str, Option<String>> = HashMap::new();
+        let mut commits: HashMap<String, Vec<_>> = HashMap::new();
+        for commit in stdout.split('\u{1e}').filter(|commit| !commit.is_empty()) {
+            let mut lines = commit.lines();
+            let mut header = lines.next().unwrap_or_default().split('\u{1f}');
+            let (Some(date), Some(name), Some(email)) = (header.next(), header.next(), header.next()) else {
+                continue;
+            };
+            let Ok(date) = DateTime::parse_from_rfc3339(date.trim()) else {
+                continue;
+            };
+            for line in lines.filter(|line| !line.is_empty()) {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let (status, old, path) = match fields.as_slice() {
+                    [status, old, path] => (*status, Some(*old), *path),
+                    [status, path] => (*status, None, *path),
+                    _ => continue,
+                };
+                let current = match renamed.get(path) {
+                    Some(current) => current.clone(),
+                    None => Some(path.to_string()),
+                };
+                if status.starts_with('D') {
+                    renamed.insert(path, None);
+                    continue;
+                }
+                if let Some(current) = &current {
+                    commits.entry(current.clone()).or_default().push((date, name, email));
+                }
+                if status.starts_with(['A', 'C']) {
+                    renamed.insert(path, None);
+                } else if let Some(old) = old {
+                    renamed.insert(path, None);
+                    renamed.insert(old, current);
+                }
+            }
+        }
+
+        let files = commits
+            .into_iter()
+            .filter_map(|(path, commits)| {
+                let modified = commits.first()?.0;
+                let created = commits.last()?.0;
+                let mut authors: Vec<GitAuthor> = Vec::new();
+                // Oldest commit first
+                for &(_, name, email) in commits.iter().rev() {
+                    match authors.iter_mut().find(|author| author.email.eq_ignore_ascii_case(email)) {
+                        Some(author) => {
+                            author.name = name.to_string();
+                            author.commits += 1;
+                        }
+                        None => authors.push(GitAuthor { name: name.to_string(), email: email.to_string(), commits: 1 }),
+                    }
+                }
+                Some((PathBuf::from(path), FileHistory { dates: GitDates { created, modified }, authors }))
+            })
+            .collect();
+        Self { root, files }
+    }
+
+    /// The history of the file at `path` (under the directory the history was loaded from), or
+    /// `None` for files never committed.
+    pub fn file(&self, path: &Path) -> Option<&FileHistory> {
+        self.files.get(path.strip_prefix(&self.root).ok()?)
+    }
+}
+
+/// "Edit this page" links to the sources of the pages in their repository on GitHub, or on GitLab
+/// when its URL says so, built from the `repo_url` and `edit_branch` of `cats-ssg.json`.
+#[derive(Serialize, Debug, Clone)]
+pub struct EditLinks {
+    repo_url: String,
+    branch: String,
+    /// Root of the git checkout holding the sources, which their paths in the repository are
+    /// relative to; without one, they are taken as given
+    root: Option<PathBuf>,
+}
+
+impl EditLinks {
+    /// Links to the sources in `branch` of the repository at `repo_url`, for the pages under
+    /// `base_path`.
+    pub fn new(repo_url: &str, branch: &str, base_path: &Path) -> Self {
+        let root = Command::new("git")
+            .current_dir(base_path)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| fs::canonicalize(String::from_utf8_lossy(&output.stdout).trim()).ok());
+        let repo_url = repo_url.trim_end_matches('/');
+        Self { repo_url: repo_url.strip_suffix(".git").unwrap_or(repo_url).to_string(), branch: branch.to_string(), root }
+    }
+
+    /// URL of the page editing `src_path` on the forge.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::git::EditLinks;
+    /// use std::path::Path;
+    /// let links = EditLinks::new("https://github.com/ana/blog.git", "main", &std::env::temp_dir());
+    /// assert_eq!(links.url(Path::new("pages/cats and dogs.md")), "https://github.com/ana/blog/edit/main/pages/cats%20and%20dogs.md");
+    /// let links = EditLinks::new("https://gitlab.com/ana/blog/", "docs", &std::env::temp_dir());
+    /// assert_eq!(links.url(Path::new("./pages/cats.md")), "https://gitlab.com/ana/blog/-/edit/docs/pages/cats.md");
+    /// ```
+    pub fn url(&self, src_path: &Path) -> String {
+        let in_repo = self.root.as_ref().and_then(|root| {
+            let src_path = fs::canonicalize(src_path).ok()?;
+            src_path.strip_prefix(root).ok().map(Path::to_path_buf)
+        });
+        let path = in_repo.as_deref().unwrap_or(src_path).to_string_lossy().replace('\\', "/").replace(' ', "%20");
+        let edit = if self.repo_url.contains("gitlab") { "-/edit" } else { "edit" };
+        format!("{}/{}/{}/{}", self.repo_url, edit, self.branch, path.trim_start_matches("./"))
+    }
+}
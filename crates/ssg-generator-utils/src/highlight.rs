@@ -0,0 +1,338 @@
+use std::{ops::RangeInclusive, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Color, Theme},
+    html::{
+        ClassStyle, ClassedHTMLGenerator, IncludeBackground, append_highlighted_html_for_styled_line,
+        css_for_theme_with_class_style, start_highlighted_html_snippet,
+    },
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// Prefix of every CSS class emitted in [`HighlightMode::Classes`], so scope names such as
+/// `keyword` or `comment` cannot clash with the site's own classes.
+pub const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "syn-" };
+
+/// Syntect theme of code blocks when none is configured.
+pub const DEFAULT_CODE_THEME: &str = "base16-ocean.dark";
+
+/// Syntax definitions bundled into the binary, regenerated with `ssg --dump`.
+const BUNDLED_SYNTAXES: &[u8] = include_bytes!("../syntaxes/syntaxes.packdump");
+
+/// Load syntax definitions from the packdump at `packdump`, or the bundled ones when no path is
+/// given or the file cannot be loaded.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::highlight::load_syntax_set;
+/// let ps = load_syntax_set(None);
+/// assert!(ps.find_syntax_by_token("rust").is_some());
+/// ```
+pub fn load_syntax_set(packdump: Option<&Path>) -> SyntaxSet {
+    if let Some(path) = packdump {
+        match syntect::dumps::from_dump_file(path) {
+            Ok(ps) => return ps,
+            Err(e) => tracing::warn!(category = "syntax", path = %path.display(), "Failed to load syntaxes from {}: {}; using the bundled ones", path.display(), e),
+        }
+    }
+    syntect::dumps::from_binary(BUNDLED_SYNTAXES)
+}
+
+/// How highlighted code blocks are styled.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightMode {
+    /// Colors are written as inline `style` attributes on every token.
+    #[default]
+    Inline,
+    /// Tokens get `syn-*` classes and the colors live in a separate stylesheet (see [`theme_css`]).
+    Classes,
+}
+
+impl FromStr for HighlightMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inline" => Ok(Self::Inline),
+            "classes" => Ok(Self::Classes),
+            other => Err(format!("unknown highlight mode `{}` (expected `inline` or `classes`)", other)),
+        }
+    }
+}
+
+/// How fenced `mermaid` blocks are rendered.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MermaidMode {
+    /// `<pre class="mermaid">` holding the raw diagram source, for client-side Mermaid.
+    Wrapper,
+    /// A plain code block, like any language in the omit list.
+    #[default]
+    Ignore,
+    /// Highlighted like any other code block, even when `mermaid` is in the omit list.
+    Highlight,
+}
+
+impl FromStr for MermaidMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrapper" => Ok(Self::Wrapper),
+            "ignore" => Ok(Self::Ignore),
+            "highlight" => Ok(Self::Highlight),
+            other => Err(format!(
+                "unknown mermaid mode `{}` (expected `wrapper`, `ignore` or `highlight`)",
+                other
+            )),
+        }
+    }
+}
+
+/// Options parsed from the info string of a fenced code block.
+///
+/// The info string is the language token followed by attributes, optionally in braces, e.g.
+/// `` ```rust title="src/main.rs" `` or `` ```rust {linenos, hl_lines=[2,5-7]} ``.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FenceInfo {
+    /// Language token, if any
+    pub lang: Option<String>,
+    /// Render a line number in front of every line
+    pub linenos: bool,
+    /// 1-based line ranges to highlight
+    pub hl_lines: Vec<RangeInclusive<usize>>,
+    /// Caption naming the file the snippet belongs to
+    pub title: Option<String>,
+}
+
+impl FenceInfo {
+    /// Parse a fence info string; unknown attributes are ignored.
+    ///
+    /// Attributes are separated by commas or whitespace; values may be quoted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::highlight::FenceInfo;
+    /// let info = FenceInfo::parse("rust {linenos, hl_lines=[2,5-7]}");
+    /// assert_eq!(info.lang.as_deref(), Some("rust"));
+    /// assert!(info.linenos);
+    /// assert_eq!(info.hl_lines, vec![2..=2, 5..=7]);
+    /// let info = FenceInfo::parse(r#"rust title="src/main rs.rs" linenos"#);
+    /// assert_eq!(info.title.as_deref(), Some("src/main rs.rs"));
+    /// assert!(info.linenos);
+    /// assert_eq!(FenceInfo::parse("").lang, None);
+    /// ```
+    pub fn parse(info: &str) -> Self {
+        let info = info.trim();
+        let lang_end = info.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(info.len());
+        let mut fence = FenceInfo {
+            lang: Some(info[..lang_end].to_string()).filter(|lang| !lang.is_empty()),
+            ..Default::default()
+        };
+
+        // Split on commas, whitespace and braces outside of quotes and `[...]`
+        let attrs = &info[lang_end..];
+        let mut parts = Vec::new();
+        let mut depth = 0;
+        let mut quote = None;
+        let mut start = 0;
+        for (i, c) in attrs.char_indices() {
+            match c {
+                '"' | '\'' if quote == Some(c) => quote = None,
+                '"' | '\'' if quote.is_none() => quote = Some(c),
+                '[' if quote.is_none() => depth += 1,
+                ']' if quote.is_none() => depth -= 1,
+                ',' | '{' | '}' if quote.is_none() && depth == 0 => {
+                    parts.push(&attrs[start..i]);
+                    start = i + 1;
+                }
+                c if c.is_whitespace() && quote.is_none() && depth == 0 => {
+                    parts.push(&attrs[start..i]);
+                    start = i + c.len_utf8();
+                }
+                _ => {}
+            }
+        }
+        parts.push(&attrs[start..]);
+
+        for part in parts {
+            let (key, value) = part.split_once('=').unwrap_or((part, ""));
+            let value = value.trim_matches(|c| matches!(c, '"' | '\''));
+            match key {
+                "linenos" => fence.linenos = true,
+                "title" => fence.title = Some(value.to_string()),
+                "hl_lines" => {
+                    let value = value.trim_matches(|c| matches!(c, '[' | ']'));
+                    for range in value.split(|c: char| c == ',' || c.is_whitespace()) {
+                        let (from, to) = range.split_once('-').unwrap_or((range, range));
+                        if let (Ok(from), Ok(to)) = (from.trim().parse(), to.trim().parse()) {
+                            fence.hl_lines.push(from..=to);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        fence
+    }
+
+    fn is_highlighted(&self, line: usize) -> bool {
+        self.hl_lines.iter().any(|range| range.contains(&line))
+    }
+}
+
+/// Highlight `code` as `syntax`, returning a complete `<pre>` block.
+///
+/// With `fence.linenos` or `fence.hl_lines`, every line is wrapped in a block-level `syn-line`
+/// span, prefixed with a `syn-lineno` span and/or marked `syn-hl`. In inline mode those spans carry
+/// the theme's gutter and line highlight colors as inline styles instead.
+///
+/// # Examples
+///
+/// ```
+/// use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
+/// use ssg_generator_utils::highlight::{highlight_code, FenceInfo, HighlightMode};
+/// let ps = SyntaxSet::load_defaults_newlines();
+/// let theme = &ThemeSet::load_defaults().themes["base16-ocean.dark"];
+/// let syntax = ps.find_syntax_by_token("rust").unwrap();
+/// let plain = FenceInfo::default();
+/// let html = highlight_code("fn main() {}\n", &ps, syntax, theme, HighlightMode::Classes, &plain);
+/// assert!(html.starts_with(r#"<pre class="syn-code">"#));
+/// assert!(html.contains(r#"class="syn-source syn-rust""#));
+/// assert!(!html.contains("style="));
+///
+/// let fence = FenceInfo::parse("rust {linenos, hl_lines=[2]}");
+/// let html = highlight_code("/* a\nb */\n", &ps, syntax, theme, HighlightMode::Classes, &fence);
+/// assert!(html.contains(r#"<span class="syn-line syn-hl"><span class="syn-lineno">2</span>"#));
+/// assert_eq!(html.matches("<span").count(), html.matches("</span>").count());
+/// ```
+pub fn highlight_code(
+    code: &str,
+    ps: &SyntaxSet,
+    syntax: &SyntaxReference,
+    theme: &Theme,
+    mode: HighlightMode,
+    fence: &FenceInfo,
+) -> String {
+    let (open, inner, close) = match mode {
+        HighlightMode::Inline => {
+            let (open, bg) = start_highlighted_html_snippet(theme);
+            let mut highlighter = HighlightLines::new(syntax, theme);
+            let mut inner = String::new();
+            for line in LinesWithEndings::from(code) {
+                let regions = highlighter.highlight_line(line, ps).unwrap();
+                append_highlighted_html_for_styled_line(&regions, IncludeBackground::IfDifferent(bg), &mut inner)
+                    .unwrap();
+            }
+            (open, inner, "</pre>")
+        }
+        HighlightMode::Classes => {
+            let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, ps, CLASS_STYLE);
+            for line in LinesWithEndings::from(code) {
+                generator.parse_html_for_line_which_includes_newline(line).unwrap();
+            }
+            (r#"<pre class="syn-code"><code>"#.to_string(), generator.finalize(), "</code></pre>")
+        }
+    };
+
+    let mut html = open;
+    if fence.linenos || !fence.hl_lines.is_empty() {
+        let lines = split_lines(&inner);
+        let width = lines.len().to_string().len();
+        for (i, line) in lines.iter().enumerate() {
+            let number = i + 1;
+            let highlighted = fence.is_highlighted(number);
+            match mode {
+                HighlightMode::Inline => {
+                    let background = theme.settings.line_highlight.filter(|_| highlighted);
+                    html.push_str(&format!(
+                        r#"<span style="display:block;{}">"#,
+                        background.map(|c| format!("background-color:{};", hex(c))).unwrap_or_default()
+                    ));
+                    if fence.linenos {
+                        let color = theme.settings.gutter_foreground.map(hex).unwrap_or_else(|| "inherit".into());
+                        html.push_str(&format!(
+                            r#"<span style="user-select:none;padding-right:1em;color:{};">{:>width$}</span>"#,
+                            color, number
+                        ));
+                    }
+                }
+                HighlightMode::Classes => {
+                    html.push_str(if highlighted { r#"<span class="syn-line syn-hl">"# } else { r#"<span class="syn-line">"# });
+                    if fence.linenos {
+                        html.push_str(&format!(r#"<span class="syn-lineno">{:>width$}</span>"#, number));
+                    }
+                }
+            }
+            html.push_str(line);
+            html.push_str("</span>");
+        }
+    } else {
+        html.push_str(&inner);
+    }
+    html.push_str(close);
+    html
+}
+
+/// Split highlighted HTML into lines (each keeping its `\n`), closing the spans still open at
+/// the end of a line and reopening them at the start of the next one.
+fn split_lines(html: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    let mut line = String::new();
+    let mut has_text = false;
+    let mut rest = html;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("</span>") {
+            open.pop();
+            line.push_str("</span>");
+            rest = &rest["</span>".len()..];
+        } else if rest.starts_with("<span") {
+            let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+            open.push(&rest[..end]);
+            line.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else {
+            line.push(c);
+            has_text = true;
+            rest = &rest[c.len_utf8()..];
+            if c == '\n' {
+                line.push_str(&"</span>".repeat(open.len()));
+                lines.push(std::mem::take(&mut line));
+                line.extend(open.iter().copied());
+                has_text = false;
+            }
+        }
+    }
+    // Closing tags after the final newline were already emitted when that line was split
+    if has_text {
+        lines.push(line);
+    }
+    lines
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+/// Stylesheet with the colors of `theme` for the classes emitted in [`HighlightMode::Classes`],
+/// including the line number gutter and highlighted lines.
+pub fn theme_css(theme: &Theme) -> String {
+    let mut css = css_for_theme_with_class_style(theme, CLASS_STYLE).unwrap();
+    css.push_str(".syn-line {\n display: block;\n}\n");
+    css.push_str(".syn-lineno {\n user-select: none;\n padding-right: 1em;\n");
+    if let Some(color) = theme.settings.gutter_foreground {
+        css.push_str(&format!(" color: {};\n", hex(color)));
+    }
+    css.push_str("}\n");
+    if let Some(color) = theme.settings.line_highlight {
+        css.push_str(&format!(".syn-hl {{\n background-color: {};\n}}\n", hex(color)));
+    }
+    css
+}
@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::urls::UrlResolver;
+
+/// Languages of a multilingual site, read from the `i18n` block of `cats-ssg.json`.
+///
+/// A page is in the language named by the first directory of its source (`pages/es/about.md`) or
+/// by the suffix of its file name (`about.es.md`), and otherwise in the default language. Pages of
+/// the default language are written at the root of the output directory and the others under a
+/// directory named after their language, so both layouts give the same output tree.
+///
+/// That directory also gets the content index, `feed.xml`, tag pages and tag feeds of the language,
+/// while those at the root only list the pages of the default language. Templates get the default
+/// language as the `default_lang` global (`en` without this block), which the built-in `base.html`
+/// falls back to for pages without a `lang`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct I18nConfig {
+    /// Codes of the languages of the site, such as `en` and `es`
+    pub languages: Vec<String>,
+    /// Language of pages naming none (default: the first of `languages`)
+    pub default_language: Option<String>,
+}
+
+impl I18nConfig {
+    /// The default language of the site.
+    pub fn default_language(&self) -> &str {
+        self.default_language.as_deref().or(self.languages.first().map(String::as_str)).unwrap_or("en")
+    }
+}
+
+/// Language of a page and where it sits in the tree of its language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLanguage {
+    pub lang: String,
+    /// Source path relative to the content root without its language directory or suffix, shared
+    /// by the translations of a page
+    pub key: PathBuf,
+}
+
+impl PageLanguage {
+    /// Directory the pages of this language are written to, relative to the output directory:
+    /// empty for the default language of `config`.
+    pub fn output_dir(&self, config: &I18nConfig) -> PathBuf {
+        if self.lang == config.default_language() { PathBuf::new() } else { PathBuf::from(&self.lang) }
+    }
+}
+
+/// The language of the page whose source is `rel_src` (relative to the content root).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::i18n::{page_language, I18nConfig};
+/// let config = I18nConfig { languages: vec!["en".to_string(), "es".to_string()], default_language: None };
+/// let page = page_language(Path::new("es/posts/cats.md"), &config);
+/// assert_eq!((page.lang.as_str(), page.key.as_path()), ("es", Path::new("posts/cats.md")));
+/// let page = page_language(Path::new("posts/cats.es.md"), &config);
+/// assert_eq!((page.lang.as_str(), page.key.as_path()), ("es", Path::new("posts/cats.md")));
+/// let page = page_language(Path::new("posts/cats.md"), &config);
+/// assert_eq!((page.lang.as_str(), page.key.as_path()), ("en", Path::new("posts/cats.md")));
+/// assert_eq!(page.output_dir(&config), Path::new(""));
+/// ```
+pub fn page_language(rel_src: &Path, config: &I18nConfig) -> PageLanguage {
+    let is_language = |code: &str| config.languages.iter().any(|lang| lang == code);
+    let mut components = rel_src.components();
+    if let Some(first) = components.next().and_then(|first| first.as_os_str().to_str())
+        && is_language(first)
+        && components.clone().next().is_some()
+    {
+        return PageLanguage { lang: first.to_string(), key: components.as_path().to_path_buf() };
+    }
+    let stem = rel_src.file_stem().unwrap_or_default().to_string_lossy();
+    if let Some((name, code)) = stem.rsplit_once('.')
+        && is_language(code)
+    {
+        let extension = rel_src.extension().unwrap_or_default().to_string_lossy();
+        return PageLanguage { lang: code.to_string(), key: rel_src.with_file_name(format!("{}.{}", name, extension)) };
+    }
+    PageLanguage { lang: config.default_language().to_string(), key: rel_src.to_path_buf() }
+}
+
+/// Another language version of a page, handed to templates in `translations`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Translation {
+    pub lang: String,
+    pub title: String,
+    pub href: String,
+}
+
+/// A language version of a page, handed to templates in `alternates` for `<link rel="alternate">`
+/// tags and listed by the sitemap.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Alternate {
+    /// Language of the version, or `x-default` for the one readers of no listed language get
+    pub hreflang: String,
+    /// Absolute URL of the version
+    pub url: String,
+}
+
+/// Every language version of the page at `href` in `lang` with `translations`, itself included and
+/// sorted by language, then an `x-default` one for its version in the default language of `config`,
+/// when it has one; empty for pages without translations.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{i18n::{alternates, I18nConfig, Translation}, urls::UrlResolver};
+/// let config = I18nConfig { languages: vec!["en".to_string(), "es".to_string()], default_language: None };
+/// let urls = UrlResolver::new("https://example.com", "/");
+/// let english = Translation { lang: "en".to_string(), title: "Cats".to_string(), href: "/cats.html".to_string() };
+/// let spanish = alternates("es", "/es/cats.html", &[english], &config, &urls);
+/// let links: Vec<_> = spanish.iter().map(|alternate| (alternate.hreflang.as_str(), alternate.url.as_str())).collect();
+/// assert_eq!(links, [
+///     ("en", "https://example.com/cats.html"),
+///     ("es", "https://example.com/es/cats.html"),
+///     ("x-default", "https://example.com/cats.html"),
+/// ]);
+/// // Pages without translations have no alternates
+/// assert!(alternates("en", "/dogs.html", &[], &config, &urls).is_empty());
+/// ```
+pub fn alternates(lang: &str, href: &str, translations: &[Translation], config: &I18nConfig, urls: &UrlResolver) -> Vec<Alternate> {
+    if translations.is_empty() {
+        return Vec::new();
+    }
+    let mut alternates: Vec<Alternate> = translations
+        .iter()
+        .map(|translation| (translation.lang.as_str(), translation.href.as_str()))
+        .chain([(lang, href)])
+        .map(|(lang, href)| Alternate { hreflang: lang.to_string(), url: urls.absolute_url(href) })
+        .collect();
+    alternates.sort_by(|a, b| a.hreflang.cmp(&b.hreflang));
+    if let Some(default) = alternates.iter().find(|alternate| alternate.hreflang == config.default_language()) {
+        alternates.push(Alternate { hreflang: "x-default".to_string(), url: default.url.clone() });
+    }
+    alternates
+}
@@ -0,0 +1,325 @@
+use std::{
+    fs,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+use image::{
+    DynamicImage, ExtendedColorType, ImageEncoder, ImageError,
+    codecs::{avif::AvifEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::escape_html;
+
+/// Widest variant produced when `max_width` is not configured.
+const DEFAULT_MAX_WIDTH: u32 = 1600;
+
+/// AVIF quality used when `quality` is not configured.
+const DEFAULT_QUALITY: u8 = 70;
+
+/// `sizes` attribute emitted with responsive images when none is configured.
+const DEFAULT_SIZES: &str = "100vw";
+
+/// Source image extensions the pipeline re-encodes.
+const RASTER_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+/// Output formats the pipeline can encode to.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    /// Lossless WebP
+    Webp,
+    /// Lossy AVIF, encoded with the configured `quality`
+    Avif,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Webp => "webp",
+            ImageFormat::Avif => "avif",
+        }
+    }
+
+    fn mime(self) -> &'static str {
+        match self {
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Avif => "image/avif",
+        }
+    }
+}
+
+/// Settings for the image optimization pipeline, read from the `images` block of `cats-ssg.json`.
+///
+/// The pipeline only runs when this block is present.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ImageConfig {
+    /// Images wider than this are scaled down, keeping their aspect ratio
+    pub max_width: Option<u32>,
+    /// Formats to encode, in order of preference
+    #[serde(default = "default_formats")]
+    pub formats: Vec<ImageFormat>,
+    /// AVIF quality from 1 to 100
+    pub quality: Option<u8>,
+    /// Responsive breakpoints; when set, every image is also resized to each of these widths
+    /// and referenced through `srcset`
+    #[serde(default)]
+    pub widths: Vec<u32>,
+    /// `sizes` attribute emitted alongside `srcset` (default: `100vw`)
+    pub sizes: Option<String>,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            formats: default_formats(),
+            quality: None,
+            widths: Vec::new(),
+            sizes: None,
+        }
+    }
+}
+
+fn default_formats() -> Vec<ImageFormat> {
+    vec![ImageFormat::Webp]
+}
+
+/// An optimized variant of a source image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    /// Encoding of the variant; `None` keeps the format of the source image
+    pub format: Option<ImageFormat>,
+    pub width: u32,
+    /// File name of the variant, placed next to the source image
+    pub file_name: String,
+}
+
+/// Compute the variants the pipeline produces for the image at `path`.
+///
+/// Returns `None` for files that are not PNG/JPEG or whose dimensions cannot be read. Variants
+/// are named `<stem>-<width>w.<ext>` so pages can reference them before they are encoded.
+///
+/// Without `widths`, one variant per format is produced at the source width capped to
+/// `max_width`. With `widths`, every breakpoint narrower than the source gets a variant per format
+/// plus one in the source format, so the `<img>` fallback can use `srcset` too.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::images::{variants, ImageConfig};
+/// let path = std::env::temp_dir().join("variants-doctest.png");
+/// image::RgbImage::new(2000, 1000).save(&path).unwrap();
+/// let names = |config: &ImageConfig| -> Vec<String> {
+///     variants(&path, config).unwrap().into_iter().map(|v| v.file_name).collect()
+/// };
+/// assert_eq!(names(&ImageConfig::default()), vec!["variants-doctest-1600w.webp"]);
+///
+/// let responsive = ImageConfig { widths: vec![640, 1280, 4000], ..Default::default() };
+/// assert_eq!(names(&responsive), vec![
+///     "variants-doctest-640w.webp", "variants-doctest-640w.png",
+///     "variants-doctest-1280w.webp", "variants-doctest-1280w.png",
+///     "variants-doctest-1600w.webp", "variants-doctest-1600w.png",
+/// ]);
+/// ```
+pub fn variants(path: &Path, config: &ImageConfig) -> Option<Vec<Variant>> {
+    let ext = path.extension()?.to_str()?;
+    if !RASTER_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) {
+        return None;
+    }
+    let (source_width, _) = image::image_dimensions(path).ok()?;
+    let largest = source_width.min(config.max_width.unwrap_or(DEFAULT_MAX_WIDTH));
+    let stem = path.file_stem()?.to_str()?;
+
+    let mut widths: Vec<u32> = config.widths.iter().copied().filter(|&w| w < largest).collect();
+    widths.push(largest);
+    widths.sort_unstable();
+    widths.dedup();
+
+    let mut formats: Vec<Option<ImageFormat>> = config.formats.iter().copied().map(Some).collect();
+    if !config.widths.is_empty() {
+        formats.push(None);
+    }
+
+    Some(
+        widths
+            .iter()
+            .flat_map(|&width| {
+                formats.iter().map(move |&format| Variant {
+                    format,
+                    width,
+                    file_name: format!("{}-{}w.{}", stem, width, format.map_or(ext, |f| f.extension())),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Encode the optimized variants of the image at `path` next to it.
+///
+/// Variants that already exist and are newer than the source are left alone, so repeated builds
+/// only pay for new or changed images. Returns the paths of the variants written.
+pub fn optimize_image(path: &Path, config: &ImageConfig) -> Result<Vec<PathBuf>, ImageError> {
+    let Some(variants) = variants(path, config) else {
+        return Ok(Vec::new());
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let source_modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    let stale: Vec<_> = variants
+        .into_iter()
+        .filter(|v| {
+            let modified = fs::metadata(dir.join(&v.file_name)).and_then(|m| m.modified()).ok();
+            !matches!((modified, source_modified), (Some(out), Some(src)) if out >= src)
+        })
+        .collect();
+    if stale.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let source = image::open(path)?;
+    let mut written = Vec::new();
+    let mut resized: Option<(u32, DynamicImage)> = None;
+    for variant in stale {
+        if resized.as_ref().is_none_or(|(width, _)| *width != variant.width) {
+            let img = if source.width() > variant.width {
+                let height = (source.height() as u64 * variant.width as u64 / source.width() as u64).max(1) as u32;
+                source.resize_exact(variant.width, height, FilterType::Lanczos3)
+            } else {
+                source.clone()
+            };
+            resized = Some((variant.width, img));
+        }
+        let img = &resized.as_ref().unwrap().1;
+        let dest = dir.join(&variant.file_name);
+        match variant.format {
+            Some(format) => encode(img, format, &dest, config)?,
+            None if is_jpeg(&dest) => DynamicImage::ImageRgb8(img.to_rgb8()).save(&dest)?,
+            None => img.save(&dest)?,
+        }
+        written.push(dest);
+    }
+    Ok(written)
+}
+
+fn encode(img: &DynamicImage, format: ImageFormat, dest: &Path, config: &ImageConfig) -> Result<(), ImageError> {
+    let rgba = img.to_rgba8();
+    let writer = BufWriter::new(fs::File::create(dest)?);
+    match format {
+        ImageFormat::Webp => {
+            WebPEncoder::new_lossless(writer).write_image(&rgba, rgba.width(), rgba.height(), ExtendedColorType::Rgba8)
+        }
+        ImageFormat::Avif => {
+            let quality = config.quality.unwrap_or(DEFAULT_QUALITY).clamp(1, 100);
+            AvifEncoder::new_with_speed_quality(writer, 8, quality).write_image(
+                &rgba,
+                rgba.width(),
+                rgba.height(),
+                ExtendedColorType::Rgba8,
+            )
+        }
+    }
+}
+
+fn is_jpeg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("jpg") || e.eq_ignore_ascii_case("jpeg"))
+}
+
+/// Render the HTML for a Markdown image.
+///
+/// `page_dir` is the directory of the Markdown source, used to resolve relative `src` values.
+/// Local images get their intrinsic `width` and `height` so browsers can reserve their space
+/// before they load; remote, absolute and data URLs, and files whose dimensions cannot be read,
+/// are rendered as a plain `<img>`. With `lazy`, the image also gets `loading="lazy"` and
+/// `decoding="async"`.
+///
+/// With an `ImageConfig`, modern formats are offered through `<source>` elements of a
+/// `<picture>`; the original `<img>` stays as the fallback and, with responsive `widths`, carries
+/// `srcset` and `sizes` for the resized copies in the source format.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::images::{img_html, ImageConfig};
+/// let dir = std::env::temp_dir().join("img-html-doctest");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// image::RgbImage::new(1000, 500).save(dir.join("cat.jpg")).unwrap();
+///
+/// let html = img_html("cat.jpg", "A cat", "", Some(&dir), None, false);
+/// assert_eq!(html, r#"<img src="cat.jpg" alt="A cat" width="1000" height="500" />"#);
+///
+/// let config = ImageConfig { widths: vec![480], ..Default::default() };
+/// let html = img_html("cat.jpg", "A cat", "", Some(&dir), Some(&config), true);
+/// assert_eq!(html, concat!(
+///     r#"<picture><source type="image/webp" srcset="cat-480w.webp 480w, cat-1000w.webp 1000w" sizes="100vw">"#,
+///     r#"<img src="cat.jpg" alt="A cat" width="1000" height="500" loading="lazy" decoding="async" "#,
+///     r#"srcset="cat-480w.jpg 480w, cat-1000w.jpg 1000w" sizes="100vw" /></picture>"#,
+/// ));
+///
+/// let html = img_html("https://example.com/cat.jpg", "", "", Some(&dir), Some(&config), true);
+/// assert_eq!(html, r#"<img src="https://example.com/cat.jpg" alt="" loading="lazy" decoding="async" />"#);
+/// ```
+pub fn img_html(
+    src: &str,
+    alt: &str,
+    title: &str,
+    page_dir: Option<&Path>,
+    config: Option<&ImageConfig>,
+    lazy: bool,
+) -> String {
+    let is_local = !(src.is_empty() || src.contains(':') || src.starts_with('/') || src.starts_with('#'));
+    let path = page_dir.filter(|_| is_local).map(|dir| dir.join(src));
+    let dimensions = path.as_deref().and_then(|p| image::image_dimensions(p).ok());
+
+    let mut img = format!(r#"<img src="{}" alt="{}""#, escape_html(src), escape_html(alt));
+    if let Some((width, height)) = dimensions {
+        img.push_str(&format!(r#" width="{}" height="{}""#, width, height));
+    }
+    if !title.is_empty() {
+        img.push_str(&format!(r#" title="{}""#, escape_html(title)));
+    }
+    if lazy {
+        img.push_str(r#" loading="lazy" decoding="async""#);
+    }
+
+    let variants = path.as_deref().zip(config).and_then(|(p, c)| Some((c, variants(p, c)?)));
+    let Some((config, variants)) = variants else {
+        img.push_str(" />");
+        return img;
+    };
+
+    let url_dir = src.rfind('/').map(|i| &src[..=i]).unwrap_or("");
+    let responsive = !config.widths.is_empty();
+    let sizes = escape_html(config.sizes.as_deref().unwrap_or(DEFAULT_SIZES));
+    let srcset = |format: Option<ImageFormat>| -> String {
+        variants
+            .iter()
+            .filter(|v| v.format == format)
+            .map(|v| {
+                let url = escape_html(&format!("{}{}", url_dir, v.file_name));
+                if responsive { format!("{} {}w", url, v.width) } else { url }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if responsive {
+        img.push_str(&format!(r#" srcset="{}" sizes="{}""#, srcset(None), sizes));
+    }
+    img.push_str(" />");
+
+    if config.formats.is_empty() {
+        return img;
+    }
+    let sources: String = config
+        .formats
+        .iter()
+        .map(|&format| {
+            let sizes_attr = if responsive { format!(r#" sizes="{}""#, sizes) } else { String::new() };
+            format!(r#"<source type="{}" srcset="{}"{}>"#, format.mime(), srcset(Some(format)), sizes_attr)
+        })
+        .collect();
+    format!("<picture>{}{}</picture>", sources, img)
+}
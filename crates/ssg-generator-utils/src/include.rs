@@ -0,0 +1,79 @@
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::{Captures, Regex};
+
+use crate::{replace_outside_code_fences, split_front_matter};
+
+/// Replace every `{{ include "path/to/file.md" }}` directive in `md` with the contents of that
+/// file, resolved relative to `content_root`.
+///
+/// Included files may include others; their front matter is dropped. Directives inside fenced
+/// code blocks are left alone, as are directives that cannot be read or would include a file
+/// that is already being included (an error naming the cycle is printed).
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::include::expand_includes;
+/// let root = std::env::temp_dir().join("include-doctest");
+/// std::fs::create_dir_all(root.join("snippets")).unwrap();
+/// std::fs::write(root.join("snippets/bio.md"), "---\ntitle: Bio\n---\nWritten by me.\n").unwrap();
+/// std::fs::write(root.join("snippets/loop.md"), "{{ include \"snippets/loop.md\" }}").unwrap();
+/// let md = "Intro\n\n{{ include \"snippets/bio.md\" }}\n{{ include \"snippets/loop.md\" }}\n";
+/// let expanded = expand_includes(md, &root);
+/// // The self-including snippet is reported and its directive left in place
+/// assert_eq!(expanded, "Intro\n\nWritten by me.\n{{ include \"snippets/loop.md\" }}\n");
+/// ```
+pub fn expand_includes(md: &str, content_root: &Path) -> String {
+    expand(md, content_root, &mut Vec::new())
+}
+
+fn expand(md: &str, content_root: &Path, stack: &mut Vec<PathBuf>) -> String {
+    if !md.contains("{{") {
+        return md.to_string();
+    }
+    let re = Regex::new(r#"\{\{\s*include\s+"([^"]+)"\s*\}\}"#).unwrap();
+    replace_outside_code_fences(md, |line| {
+        if !re.is_match(line) {
+            return Cow::Borrowed(line);
+        }
+        Cow::Owned(
+            re.replace_all(line, |caps: &Captures| {
+                include(&caps[1], content_root, stack).unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned(),
+        )
+    })
+}
+
+fn include(rel: &str, content_root: &Path, stack: &mut Vec<PathBuf>) -> Option<String> {
+    let path = content_root.join(rel);
+    let canonical = match path.canonicalize() {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::warn!(category = "include", path = %path.display(), "Failed to include {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if stack.contains(&canonical) {
+        let cycle: Vec<_> = stack.iter().chain([&canonical]).map(|p| p.display().to_string()).collect();
+        tracing::warn!(category = "include", path = %path.display(), "Include cycle detected: {}", cycle.join(" -> "));
+        return None;
+    }
+    let content = match fs::read_to_string(&canonical) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(category = "include", path = %path.display(), "Failed to include {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let (_, body) = split_front_matter(&content);
+    stack.push(canonical);
+    let expanded = expand(body, content_root, stack);
+    stack.pop();
+    Some(expanded.trim_end_matches('\n').to_string())
+}
@@ -1,36 +1,217 @@
 #![warn(unused_extern_crates)]
 use std::{
-    collections::HashSet,
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::{self},
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 use rayon::prelude::*;
-use pulldown_cmark::{Parser as MdParser, Options, html, Event, Tag, CodeBlockKind, TagEnd};
-use syntect::{parsing::SyntaxSet, highlighting::ThemeSet, html::highlighted_html_for_string};
-use serde::Deserialize;
-use minijinja::{Environment, context};
+use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
+use pulldown_cmark::{Parser as MdParser, Options, html, Event, Tag, CodeBlockKind, TagEnd, BlockQuoteKind};
+use syntect::{parsing::SyntaxSet, highlighting::ThemeSet};
+use serde::{Deserialize, Serialize};
+use minijinja::{Environment, UndefinedBehavior, Value, context};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Utc};
+use error::SsgError;
+use report::{Phase, Stats};
+use urls::UrlResolver;
 
+pub mod activitypub;
+pub mod analytics;
+pub mod api;
+pub mod assets;
+pub mod authors;
+pub mod cache;
+pub mod cards;
+pub mod check;
+pub mod comments;
+pub mod compress;
+pub mod data;
+pub mod deploy;
+pub mod error;
+pub mod export;
+pub mod feed;
+pub mod filters;
+pub mod gemini;
+pub mod git;
+pub mod highlight;
+pub mod i18n;
+pub mod images;
+pub mod include;
+pub mod links;
+pub mod llms;
+pub mod menus;
+pub mod microformats;
+pub mod nav;
+pub mod plaintext;
+pub mod prune;
+pub mod redirects;
+pub mod report;
+pub mod robots;
+pub mod search;
+pub mod sections;
+pub mod seo;
+pub mod series;
+pub mod shortcodes;
 pub mod sitemap;
+pub mod sw;
+pub mod tags;
+pub mod templates;
+pub mod toc;
+pub mod urls;
+pub mod webmanifest;
+pub mod webmention;
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct Meta {
     pub title: Option<String>,
     extends: Option<String>,
     generate_llm_txt: Option<bool>,
     omit_llm_txt_generation: Option<bool>,
     description: Option<String>,
+    /// Share image of the page, as a site path or an absolute URL (see [`seo::Seo`])
+    image: Option<String>,
+    /// Author of the page, overriding the site-wide one of [`seo::SeoConfig`]
+    author: Option<String>,
+    /// Audio of a podcast episode, attached to the feeds as an enclosure (see [`feed::PodcastConfig`])
+    audio: Option<feed::Audio>,
+    /// Authors of a page written by several, in place of `author`
+    authors: Option<Vec<String>>,
     pub llm_description: Option<String>,
+    /// `##` section of `llms.txt` listing the page (see [`llms::page_section`])
+    pub llm_section: Option<String>,
     keywords: Option<Vec<String>>,
     tags: Option<Vec<String>>,
+    /// Name of the series the page is a part of (see [`series::collect_series`])
+    series: Option<String>,
+    /// Pages sharing it are translations of each other (default: the source path without its language, see
+    /// [`i18n::page_language`])
+    translation_key: Option<String>,
     merge_tags_keywords: Option<bool>,
     page_slug: Option<String>,
-    pub llm_title: Option<String>
+    /// Permalink pattern overriding the site-wide one (see [`urls::UrlResolver::page_path`])
+    permalink: Option<String>,
+    /// Canonical URL of a page published elsewhere first (see [`Meta::canonical_url`])
+    canonical: Option<String>,
+    /// Old site paths of the page, redirected to it (see [`redirects::alias_permalink`])
+    aliases: Option<Vec<String>>,
+    pub llm_title: Option<String>,
+    date: Option<String>,
+    /// Sitemap `<lastmod>` overriding the last commit date of the page (see [`sitemap::SitemapEntry`])
+    lastmod: Option<String>,
+    /// Sitemap `<changefreq>` of the page
+    changefreq: Option<sitemap::ChangeFreq>,
+    /// Sitemap `<priority>` of the page, from 0.0 to 1.0
+    priority: Option<f32>,
+    /// Ask search engines not to index the page; it is also left out of the sitemap
+    noindex: Option<bool>,
+    /// Leave the page out of the sitemap while still rendering it
+    exclude_from_sitemap: Option<bool>,
+    /// Leave the page out of the content index and the feed while still rendering it
+    exclude_from_index: Option<bool>,
+    /// Work in progress, left out of the site unless drafts are built (see [`generate_site`])
+    draft: Option<bool>,
+    lazy_loading: Option<bool>,
+    no_syntax_highlighting: Option<bool>,
+    templated: Option<bool>,
+    /// Show the comments widget under the page (default: on every page but the home page, when comments
+    /// are configured; see [`comments::CommentsConfig`])
+    comments: Option<bool>,
+    /// Add the analytics snippet to the page (default: true, when analytics are configured; see
+    /// [`analytics::AnalyticsConfig`])
+    analytics: Option<bool>,
+    /// Metadata for every page in and below the directory of a metadata file (see [`cascaded_meta`])
+    #[serde(skip_serializing)]
+    cascade: Option<Box<Meta>>,
+    /// Any keys not known to the generator, passed through to templates as-is
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
 }
 
-/// Load metadata from a YAML file into a `Meta` struct.
+/// Names of the metadata file of a directory, in order of precedence: only the first one found is read.
+pub const META_FILES: &[&str] = &["meta.yml", "meta.yaml", "meta.toml", "meta.json"];
+
+/// The metadata file of `dir`: the first of [`META_FILES`] that exists.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::find_meta_file;
+/// let dir = std::env::temp_dir().join("find-meta-doctest");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("meta.json"), r#"{"title": "From JSON"}"#).unwrap();
+/// std::fs::write(dir.join("meta.toml"), r#"title = "From TOML""#).unwrap();
+/// assert_eq!(find_meta_file(&dir), Some(dir.join("meta.toml")));
+/// ```
+pub fn find_meta_file(dir: &Path) -> Option<PathBuf> {
+    META_FILES.iter().map(|name| dir.join(name)).find(|path| path.is_file())
+}
+
+/// Deserialize the metadata `content` of the file at `meta_path`: TOML for a `.toml` extension,
+/// JSON for `.json` and YAML otherwise.
+///
+/// TOML and JSON errors are returned as YAML errors carrying their message, so every metadata
+/// error is reported the same way (see [`SsgError::Meta`]).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::parse_meta;
+/// let meta = parse_meta(Path::new("meta.toml"), "title = \"Cats\"\ntags = [\"pets\"]").unwrap();
+/// assert_eq!(meta.title.as_deref(), Some("Cats"));
+/// assert!(parse_meta(Path::new("meta.json"), "{ not json").is_err());
+/// ```
+pub fn parse_meta(meta_path: &Path, content: &str) -> Result<Meta, serde_yaml::Error> {
+    use serde::de::Error as _;
+    match meta_path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(content).map_err(serde_yaml::Error::custom),
+        Some("json") => serde_json::from_str(content).map_err(serde_yaml::Error::custom),
+        _ => serde_yaml::from_str(content),
+    }
+}
+
+/// Reject the keys of `meta`, parsed from `content`, that are not fields of [`Meta`], such as a
+/// misspelled `titel`, naming each with the line declaring it; `first_line` is the line of the
+/// file `content` starts at (2 for front matter after its `---`).
 ///
-/// Attempts to read and deserialize YAML from `meta_path`. If the file cannot be read
-/// or the YAML fails to deserialize, returns `Meta::default()`.
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{deny_unknown_meta_fields, parse_front_matter};
+/// let yaml = "title: Cats\ntagz: [pets]";
+/// let err = deny_unknown_meta_fields(&parse_front_matter(yaml).unwrap(), yaml, 2).unwrap_err();
+/// assert_eq!(err.to_string(), "unknown field `tagz` at line 3");
+/// assert!(deny_unknown_meta_fields(&parse_front_matter("title: Cats").unwrap(), "title: Cats", 1).is_ok());
+/// ```
+pub fn deny_unknown_meta_fields(meta: &Meta, content: &str, first_line: usize) -> Result<(), serde_yaml::Error> {
+    use serde::de::Error as _;
+    let unknown: Vec<_> = meta
+        .extra
+        .keys()
+        .map(|key| {
+            let line = content.lines().position(|line| {
+                let line = line.trim_start().trim_start_matches('"');
+                line.strip_prefix(key.as_str())
+                    .is_some_and(|rest| rest.trim_start_matches('"').trim_start().starts_with([':', '=']))
+            });
+            match line {
+                Some(line) => format!("`{}` at line {}", key, line + first_line),
+                None => format!("`{}`", key),
+            }
+        })
+        .collect();
+    match unknown.len() {
+        0 => Ok(()),
+        1 => Err(serde_yaml::Error::custom(format!("unknown field {}", unknown[0]))),
+        _ => Err(serde_yaml::Error::custom(format!("unknown fields {}", unknown.join(", ")))),
+    }
+}
+
+/// Load metadata from a YAML, TOML or JSON file into a `Meta` struct (see [`parse_meta`]).
+///
+/// If the file cannot be read or fails to deserialize, returns `Meta::default()`.
 ///
 /// # Examples
 ///
@@ -41,12 +222,288 @@ pub struct Meta {
 /// ```
 pub fn load_meta(meta_path: &Path) -> Meta {
     if let Ok(content) = fs::read_to_string(meta_path) {
-        serde_yaml::from_str(&content).unwrap_or_default()
+        parse_meta(meta_path, &content).unwrap_or_default()
     } else {
         Meta::default()
     }
 }
 
+/// Load the metadata file of `dir` (see [`find_meta_file`]), or `Meta::default()` without one.
+pub fn load_dir_meta(dir: &Path) -> Meta {
+    find_meta_file(dir).map(|meta_path| load_meta(&meta_path)).unwrap_or_default()
+}
+
+impl Meta {
+    /// Combine two `Meta` values field by field, preferring the values set on `self`.
+    ///
+    /// Used to layer front matter on top of a sibling `meta.yml`: every field present in
+    /// `self` wins, and only the missing ones are taken from `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::parse_front_matter;
+    /// let front = parse_front_matter("title: From front matter\nhero: front.png").unwrap();
+    /// let file = parse_front_matter("title: From meta.yml\nllm_title: Kept\nhero: file.png").unwrap();
+    /// let meta = front.merge(file);
+    /// assert_eq!(meta.title.as_deref(), Some("From front matter"));
+    /// assert_eq!(meta.llm_title.as_deref(), Some("Kept"));
+    /// assert_eq!(meta.extra["hero"].as_str(), Some("front.png"));
+    /// ```
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            title: self.title.or(other.title),
+            extends: self.extends.or(other.extends),
+            generate_llm_txt: self.generate_llm_txt.or(other.generate_llm_txt),
+            omit_llm_txt_generation: self.omit_llm_txt_generation.or(other.omit_llm_txt_generation),
+            description: self.description.or(other.description),
+            image: self.image.or(other.image),
+            author: self.author.or(other.author),
+            authors: self.authors.or(other.authors),
+            audio: self.audio.or(other.audio),
+            llm_description: self.llm_description.or(other.llm_description),
+            llm_section: self.llm_section.or(other.llm_section),
+            keywords: self.keywords.or(other.keywords),
+            tags: self.tags.or(other.tags),
+            series: self.series.or(other.series),
+            translation_key: self.translation_key.or(other.translation_key),
+            merge_tags_keywords: self.merge_tags_keywords.or(other.merge_tags_keywords),
+            page_slug: self.page_slug.or(other.page_slug),
+            permalink: self.permalink.or(other.permalink),
+            canonical: self.canonical.or(other.canonical),
+            aliases: self.aliases.or(other.aliases),
+            llm_title: self.llm_title.or(other.llm_title),
+            date: self.date.or(other.date),
+            lastmod: self.lastmod.or(other.lastmod),
+            changefreq: self.changefreq.or(other.changefreq),
+            priority: self.priority.or(other.priority),
+            noindex: self.noindex.or(other.noindex),
+            exclude_from_sitemap: self.exclude_from_sitemap.or(other.exclude_from_sitemap),
+            exclude_from_index: self.exclude_from_index.or(other.exclude_from_index),
+            draft: self.draft.or(other.draft),
+            lazy_loading: self.lazy_loading.or(other.lazy_loading),
+            no_syntax_highlighting: self.no_syntax_highlighting.or(other.no_syntax_highlighting),
+            templated: self.templated.or(other.templated),
+            comments: self.comments.or(other.comments),
+            analytics: self.analytics.or(other.analytics),
+            cascade: self.cascade.or(other.cascade),
+            extra: {
+                let mut extra = other.extra;
+                extra.extend(self.extra);
+                extra
+            },
+        }
+    }
+
+    /// Absolute URL search engines should credit for the page published at `href`: the `canonical`
+    /// metadata for syndicated or cross-posted content, or else the URL of the page itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::{parse_front_matter, urls::UrlResolver};
+    /// let urls = UrlResolver::new("https://example.com", "/blog/");
+    /// let meta = parse_front_matter("title: Hi").unwrap();
+    /// assert_eq!(meta.canonical_url("/blog/hi.html", &urls), "https://example.com/blog/hi.html");
+    ///
+    /// let meta = parse_front_matter("canonical: https://dev.to/me/hi").unwrap();
+    /// assert_eq!(meta.canonical_url("/blog/hi.html", &urls), "https://dev.to/me/hi");
+    /// ```
+    pub fn canonical_url(&self, href: &str, urls: &UrlResolver) -> String {
+        urls.absolute_url(self.canonical.as_deref().unwrap_or(href))
+    }
+
+    /// Names of the authors of the page: its `authors` metadata, or else its `author`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::parse_front_matter;
+    /// assert_eq!(parse_front_matter("author: Ana").unwrap().author_names(), ["Ana"]);
+    /// assert_eq!(parse_front_matter("author: Ana\nauthors: [Bo, Cy]").unwrap().author_names(), ["Bo", "Cy"]);
+    /// assert!(parse_front_matter("title: Hi").unwrap().author_names().is_empty());
+    /// ```
+    pub fn author_names(&self) -> Vec<String> {
+        match &self.authors {
+            Some(authors) if !authors.is_empty() => authors.clone(),
+            _ => self.author.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Append `tags` to `keywords`, dropping duplicates while keeping the first spelling seen.
+///
+/// Duplicates are detected case-insensitively so `Rust` and `rust` only appear once.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::merge_keywords;
+/// let keywords = vec!["rust".to_string(), "ssg".to_string()];
+/// let tags = vec!["Rust".to_string(), "web".to_string()];
+/// assert_eq!(merge_keywords(&keywords, &tags), vec!["rust", "ssg", "web"]);
+/// ```
+pub fn merge_keywords(keywords: &[String], tags: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    keywords
+        .iter()
+        .chain(tags)
+        .filter(|k| seen.insert(k.to_lowercase()))
+        .cloned()
+        .collect()
+}
+
+/// Split a leading `---` delimited YAML front matter block from a Markdown document.
+///
+/// The opening `---` must be the very first line of the document and the block ends at the
+/// next line consisting only of `---` (or `...`). Returns the raw YAML and the remaining body;
+/// when there is no front matter the YAML is `None` and the body is the whole input.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::split_front_matter;
+/// let (yaml, body) = split_front_matter("---\ntitle: Hi\n---\n# Body\n");
+/// assert_eq!(yaml, Some("title: Hi\n"));
+/// assert_eq!(body, "# Body\n");
+///
+/// let (yaml, body) = split_front_matter("# No front matter\n---\n");
+/// assert_eq!(yaml, None);
+/// assert_eq!(body, "# No front matter\n---\n");
+/// ```
+pub fn split_front_matter(md: &str) -> (Option<&str>, &str) {
+    let rest = match md.strip_prefix("---\r\n").or_else(|| md.strip_prefix("---\n")) {
+        Some(rest) => rest,
+        None => return (None, md),
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+
+    (None, md)
+}
+
+/// Load the metadata of the page at `src_path` from its front matter, the metadata file of its
+/// directory (see [`find_meta_file`]) and the defaults cascaded from the directories above it up to
+/// `base_path` (see [`cascaded_meta`]).
+///
+/// Front matter takes precedence over the metadata file, and both over cascaded defaults, field by field.
+/// Invalid front matter is ignored here; [`process_md_file`] reports it.
+pub fn load_page_meta(src_path: &Path, base_path: &Path, front_matter: Option<&str>) -> Meta {
+    let front_meta = front_matter
+        .and_then(|yaml| parse_front_matter(yaml).ok())
+        .unwrap_or_default();
+    let dir_meta = src_path.parent().map(load_dir_meta).unwrap_or_default();
+    front_meta.merge(dir_meta).merge(cascaded_meta(src_path, base_path))
+}
+
+/// Directories whose metadata files can affect the page at `src_path`: its own and every one above
+/// it up to `base_path`, nearest first.
+pub(crate) fn meta_dirs<'a>(src_path: &'a Path, base_path: &'a Path) -> impl Iterator<Item = &'a Path> {
+    src_path
+        .parent()
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .take_while(move |dir| dir.starts_with(base_path))
+}
+
+/// Defaults for the page at `src_path` from the `_defaults.yml` files and the `cascade` blocks of the
+/// metadata files (see [`find_meta_file`]) in its directory and every directory above it up to `base_path`.
+///
+/// The nearest directory wins, field by field, and within a directory `_defaults.yml` wins over the
+/// `cascade` block. Files that cannot be read or parsed are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::cascaded_meta;
+/// let base = std::env::temp_dir().join("cascade-doctest");
+/// std::fs::create_dir_all(base.join("docs/api")).unwrap();
+/// std::fs::write(base.join("docs/_defaults.yml"), "extends: docs.html\ngenerate_llm_txt: false").unwrap();
+/// std::fs::write(base.join("docs/api/meta.yml"), "cascade:\n  extends: api.html").unwrap();
+/// let meta = cascaded_meta(&base.join("docs/api/intro.md"), &base);
+/// assert_eq!(serde_json::to_value(&meta).unwrap()["extends"], "api.html");
+/// assert_eq!(serde_json::to_value(&meta).unwrap()["generate_llm_txt"], false);
+/// ```
+pub fn cascaded_meta(src_path: &Path, base_path: &Path) -> Meta {
+    meta_dirs(src_path, base_path).fold(Meta::default(), |meta, dir| {
+        let cascade = load_dir_meta(dir).cascade.map(|cascade| *cascade).unwrap_or_default();
+        meta.merge(load_meta(&dir.join("_defaults.yml"))).merge(cascade)
+    })
+}
+
+/// Deserialize a YAML front matter block into a `Meta` struct.
+///
+/// Returns the YAML error so callers can report which page carries the broken block.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::parse_front_matter;
+/// let meta = parse_front_matter("title: Hello\nllm_description: A page").unwrap();
+/// assert_eq!(meta.title.as_deref(), Some("Hello"));
+/// assert!(parse_front_matter("title: [unclosed").is_err());
+/// ```
+pub fn parse_front_matter(yaml: &str) -> Result<Meta, serde_yaml::Error> {
+    if yaml.trim().is_empty() {
+        return Ok(Meta::default());
+    }
+    serde_yaml::from_str(yaml)
+}
+
+/// Parse a page date written either as RFC 3339 or as a plain `YYYY-MM-DD` day.
+///
+/// Plain days are interpreted as midnight UTC. Returns `None` when neither format matches.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::parse_date;
+/// assert_eq!(parse_date("2024-05-01").unwrap().to_rfc3339(), "2024-05-01T00:00:00+00:00");
+/// assert_eq!(parse_date("2024-05-01T10:30:00+02:00").unwrap().to_rfc3339(), "2024-05-01T10:30:00+02:00");
+/// assert!(parse_date("May 1st").is_none());
+/// ```
+pub fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value).ok().or_else(|| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .and_then(|day| day.and_hms_opt(0, 0, 0))
+            .map(|dt| dt.and_utc().fixed_offset())
+    })
+}
+
+/// Build the structured `date` object handed to templates.
+///
+/// Exposes the ISO 8601 form plus the individual parts so themes can format the date without
+/// string slicing.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{date_context, parse_date};
+/// let date = date_context(&parse_date("2024-05-01").unwrap());
+/// assert_eq!(date.get_attr("year").unwrap().to_string(), "2024");
+/// assert_eq!(date.get_attr("iso").unwrap().to_string(), "2024-05-01T00:00:00+00:00");
+/// ```
+pub fn date_context(date: &DateTime<FixedOffset>) -> Value {
+    context! {
+        iso => date.to_rfc3339(),
+        rfc2822 => date.to_rfc2822(),
+        timestamp => date.timestamp(),
+        year => date.year(),
+        month => date.month(),
+        month_name => date.format("%B").to_string(),
+        day => date.day(),
+    }
+}
+
 use regex::Regex;
 
 /// Removes all occurrences of an HTML-like tag and its contents (including the tags).
@@ -125,67 +582,276 @@ pub fn folder_name_to_title(folder: &Path) -> String {
         .unwrap_or_else(|| "Untitled".to_string())
 }
 
+/// Turn free text into a URL-safe slug: lowercase letters and digits separated by single dashes.
+///
+/// Letters and digits outside ASCII are kept too (lowercased), so tags in other scripts still
+/// produce distinct paths.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::slugify;
+/// assert_eq!(slugify("Rust & WebAssembly"), "rust-webassembly");
+/// assert_eq!(slugify("  C++ tips "), "c-tips");
+/// assert_eq!(slugify("año"), "año");
+/// ```
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+/// Run `replace` over every line of `md` that is not part of a fenced code block.
+pub(crate) fn replace_outside_code_fences<'a>(
+    md: &'a str,
+    mut replace: impl FnMut(&'a str) -> std::borrow::Cow<'a, str>,
+) -> String {
+    let mut out = String::with_capacity(md.len());
+    let mut fence: Option<&str> = None;
+    for line in md.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if open == m => fence = None,
+            _ => {}
+        }
+        if fence.is_some() || marker.is_some() {
+            out.push_str(line);
+        } else {
+            out.push_str(&replace(line));
+        }
+    }
+    out
+}
+
+/// Escape `text` for use in HTML text and double-quoted attribute values.
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Insert `snippet` at the end of the `<head>` of `html`, or in front of it when it has none.
+pub(crate) fn inject_into_head(html: &str, snippet: &str) -> String {
+    let head_end = Regex::new(r"(?i)</head\s*>").unwrap();
+    match head_end.find(html) {
+        Some(end) => format!("{}{}{}", &html[..end.start()], snippet, &html[end.start()..]),
+        None => format!("{}{}", snippet, html),
+    }
+}
+
+/// Convert Markdown to HTML, highlighting fenced code blocks with syntect.
+///
+/// The settings named below are the fields of `options` (see [`PageOptions`]); the others are ignored.
+///
+/// Code blocks whose language is in `omit_languages` (or all of them when
+/// `disable_syntax_highlighting` is set) are emitted as plain `<pre><code>`. The others are styled
+/// according to `highlight_mode` (see [`highlight::highlight_code`]), honoring the `linenos` and
+/// `hl_lines` attributes of the fence info (see [`highlight::FenceInfo`]). A `title` attribute wraps
+/// the block in `<figure class="code-block">` with the title as its `<figcaption>`. `mermaid` blocks
+/// are rendered according to `mermaid` (see [`highlight::MermaidMode`]).
+///
+/// Images are rendered through [`images::img_html`]. When `page_dir` (the directory of the
+/// Markdown source) is given, local images get their intrinsic `width`/`height` and, with an
+/// `image_config`, reference their optimized and responsive variants. With `eager_images` set,
+/// every image after the first `eager_images` ones, and every `<iframe>`, is lazy-loaded.
+///
+/// GitHub-style alerts (`> [!NOTE]`, `> [!TIP]`, `> [!IMPORTANT]`, `> [!WARNING]`, `> [!CAUTION]`)
+/// become `<div class="admonition note">` blocks opened by a `<p class="admonition-title">`.
+///
+/// Every heading gets a unique slug `id` and is returned, in document order, as the page's table
+/// of contents. A `[TOC]` paragraph is replaced with the rendered outline (see [`toc::render_toc`]).
+///
+/// With `links` and `page_dir`, relative links to `.md` files are rewritten to the href of the page
+/// built from them (see [`links::PageLinks::resolve`]); links to a Markdown file that is not a page
+/// of the site are kept and reported with a `link` warning.
+///
+/// With `stats`, the time spent highlighting is added to [`Phase::Highlight`], the rest of the
+/// conversion to [`Phase::Parse`], and warnings are counted.
+///
+/// # Examples
+///
+/// ```
+/// use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
+/// use ssg_generator_utils::markdown_to_html;
+/// let ps = SyntaxSet::load_defaults_newlines();
+/// let theme = &ThemeSet::load_defaults().themes["base16-ocean.dark"];
+/// let md = "[TOC]\n\n## Getting `cargo`\n\n## Getting `cargo`\n";
+/// let (html, toc) = markdown_to_html(md, &ps, theme, None, None, &Default::default(), None);
+/// assert_eq!(toc[0].text, "Getting cargo");
+/// assert_eq!(toc[1].slug, "getting-cargo-1");
+/// assert!(html.starts_with(r#"<nav class="toc">"#));
+/// assert!(html.contains(r#"<h2 id="getting-cargo-1">"#));
+///
+/// let (html, _) = markdown_to_html("> [!WARNING]\n> Mind the gap", &ps, theme, None, None, &Default::default(), None);
+/// assert_eq!(html, "<div class=\"admonition warning\"><p class=\"admonition-title\">Warning</p>\n<p>Mind the gap</p>\n</div>");
+/// ```
 pub fn markdown_to_html(
     md: &str,
     ps: &SyntaxSet,
     theme: &syntect::highlighting::Theme,
-    omit_languages: &HashSet<String>,
-    disable_syntax_highlighting: bool,
-) -> String {
+    page_dir: Option<&Path>,
+    links: Option<&links::PageLinks>,
+    options: &PageOptions,
+    stats: Option<&Stats>,
+) -> (String, Vec<toc::TocEntry>) {
+    let PageOptions { omit_languages, disable_syntax_highlighting, highlight_mode, mermaid, image_config, eager_images, .. } = *options;
+    let convert_start = Instant::now();
+    let mut highlight_time = Duration::ZERO;
     let mut options = Options::empty();
     options.insert(Options::ENABLE_TABLES);
     options.insert(Options::ENABLE_FOOTNOTES);
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_GFM);
 
     let parser = MdParser::new_ext(md, options);
     let mut html_output = String::new();
     let mut in_code_block = false;
-    let mut code_lang = None;
+    let mut fence = highlight::FenceInfo::default();
     let mut code_content = String::new();
-    let mut code_block_kind = None;
     let mut events = Vec::new();
+    // Image being collected: (src, title, alt text)
+    let mut image: Option<(String, String, String)> = None;
+    let mut images_seen = 0;
+    let mut toc = Vec::new();
+    let mut slugs = toc::SlugCounter::default();
+    // Heading being collected: (index of its start event, plain text)
+    let mut heading: Option<(usize, String)> = None;
 
     for event in parser {
+        if let Some((_, text)) = heading.as_mut()
+            && let Event::Text(t) | Event::Code(t) = &event
+        {
+            text.push_str(t);
+        }
+        if let Some((src, title, alt)) = image.as_mut() {
+            match event {
+                Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+                Event::End(TagEnd::Image) => {
+                    let lazy = eager_images.is_some_and(|eager| images_seen >= eager);
+                    images_seen += 1;
+                    let html = images::img_html(src, alt, title, page_dir, image_config, lazy);
+                    events.push(Event::InlineHtml(html.into()));
+                    image = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
         match event {
+            Event::Start(Tag::Image { dest_url, title, .. }) => {
+                image = Some((dest_url.to_string(), title.to_string(), String::new()));
+            }
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+                let resolved = links.zip(page_dir).and_then(|(links, dir)| Some((links.resolve(dir, &dest_url)?, dir)));
+                let dest_url = match resolved {
+                    Some((Some(href), _)) => href.into(),
+                    Some((None, dir)) => {
+                        let message = format_args!("Link to missing page {} in {}", dest_url, dir.display());
+                        match stats {
+                            Some(stats) => stats.warn("link", Some(dir), message),
+                            None => tracing::warn!(category = "link", path = %dir.display(), "{}", message),
+                        }
+                        dest_url
+                    }
+                    None => dest_url,
+                };
+                events.push(Event::Start(Tag::Link { link_type, dest_url, title, id }));
+            }
+            Event::Start(Tag::BlockQuote(Some(kind))) => {
+                let (class, title) = match kind {
+                    BlockQuoteKind::Note => ("note", "Note"),
+                    BlockQuoteKind::Tip => ("tip", "Tip"),
+                    BlockQuoteKind::Important => ("important", "Important"),
+                    BlockQuoteKind::Warning => ("warning", "Warning"),
+                    BlockQuoteKind::Caution => ("caution", "Caution"),
+                };
+                events.push(Event::Html(
+                    format!(r#"<div class="admonition {}"><p class="admonition-title">{}</p>"#, class, title).into(),
+                ));
+            }
+            Event::End(TagEnd::BlockQuote(Some(_))) => events.push(Event::Html("</div>".into())),
+            Event::Start(Tag::Heading { .. }) => {
+                heading = Some((events.len(), String::new()));
+                events.push(event);
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                if let Some((start, text)) = heading.take() {
+                    let slug = slugs.unique(&text);
+                    if let Event::Start(Tag::Heading { id, .. }) = &mut events[start] {
+                        *id = Some(slug.clone().into());
+                    }
+                    toc.push(toc::TocEntry { level: level as u8, text, slug });
+                }
+                events.push(Event::End(TagEnd::Heading(level)));
+            }
             Event::Start(Tag::CodeBlock(kind)) => {
                 in_code_block = true;
-                code_lang = match &kind {
-                    CodeBlockKind::Fenced(lang) => Some(lang.to_string()),
-                    _ => None,
+                fence = match &kind {
+                    CodeBlockKind::Fenced(info) => highlight::FenceInfo::parse(info),
+                    _ => highlight::FenceInfo::default(),
                 };
-                code_block_kind = Some(kind);
                 code_content.clear();
             }
             Event::End(TagEnd::CodeBlock) => {
                 in_code_block = false;
 
+                let is_mermaid = fence.lang.as_deref() == Some("mermaid");
                 let should_highlight = if disable_syntax_highlighting {
                     false
-                } else if let Some(lang) = &code_lang {
-                    !omit_languages.contains(lang)
+                } else if is_mermaid && mermaid == highlight::MermaidMode::Highlight {
+                    true
+                } else if let Some(lang) = &fence.lang {
+                    !omit_languages.is_some_and(|omit| omit.contains(lang))
                 } else {
                     true // Highlight if no language is specified
                 };
 
-                if should_highlight {
-                    let syntax = code_lang
+                if let Some(title) = &fence.title {
+                    events.push(Event::Html(
+                        format!(r#"<figure class="code-block"><figcaption>{}</figcaption>"#, escape_html(title)).into(),
+                    ));
+                }
+                if is_mermaid && mermaid == highlight::MermaidMode::Wrapper {
+                    events.push(Event::Html(
+                        format!(r#"<pre class="mermaid">{}</pre>"#, escape_html(&code_content)).into(),
+                    ));
+                } else if should_highlight {
+                    let syntax = fence
+                        .lang
                         .as_deref()
                         .and_then(|lang| ps.find_syntax_by_token(lang))
                         .unwrap_or_else(|| ps.find_syntax_plain_text());
-                    let mut highlighted =
-                        highlighted_html_for_string(&code_content, ps, syntax, theme).unwrap();
-                    if highlighted.ends_with('\n') {
-                        highlighted.pop();
-                    }
+                    let start = Instant::now();
+                    let highlighted =
+                        highlight::highlight_code(&code_content, ps, syntax, theme, highlight_mode, &fence);
+                    highlight_time += start.elapsed();
                     events.push(Event::Html(highlighted.into()));
                 } else {
-                    events.push(Event::Start(Tag::CodeBlock(
-                        code_block_kind.take().unwrap(),
-                    )));
+                    let kind = match fence.lang.take() {
+                        Some(lang) => CodeBlockKind::Fenced(lang.into()),
+                        None => CodeBlockKind::Indented,
+                    };
+                    events.push(Event::Start(Tag::CodeBlock(kind)));
                     events.push(Event::Text(code_content.clone().into()));
                     events.push(Event::End(TagEnd::CodeBlock));
                 }
+                if fence.title.is_some() {
+                    events.push(Event::Html("</figure>".into()));
+                }
             }
             Event::Text(text) if in_code_block => {
                 code_content.push_str(&text);
@@ -196,62 +862,169 @@ pub fn markdown_to_html(
     }
 
     html::push_html(&mut html_output, events.into_iter());
-    html_output
+    if eager_images.is_some() {
+        html_output = lazy_load_iframes(&html_output);
+    }
+    if html_output.contains(toc::TOC_MARKER) {
+        html_output = html_output.replace(toc::TOC_MARKER, &toc::render_toc(&toc));
+    }
+    if let Some(stats) = stats {
+        stats.add(Phase::Highlight, highlight_time);
+        stats.add(Phase::Parse, convert_start.elapsed().saturating_sub(highlight_time));
+    }
+    (html_output, toc)
 }
 
-/// Processes a single Markdown source file into an HTML page, optionally writes a stripped Markdown copy for LLM use, and returns metadata for site indexing.
+/// Add `loading="lazy"` to every `<iframe>` in `html` that does not set `loading` already.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::lazy_load_iframes;
+/// let html = r#"<iframe src="a"></iframe><iframe loading="eager" src="b"></iframe>"#;
+/// assert_eq!(
+///     lazy_load_iframes(html),
+///     r#"<iframe src="a" loading="lazy"></iframe><iframe loading="eager" src="b"></iframe>"#
+/// );
+/// ```
+pub fn lazy_load_iframes(html: &str) -> String {
+    let re = Regex::new(r"(?i)<iframe\b([^>]*?)(\s*/?)>").unwrap();
+    re.replace_all(html, |caps: &regex::Captures| {
+        if caps[1].to_ascii_lowercase().contains("loading=") {
+            caps[0].to_string()
+        } else {
+            format!(r#"<iframe{} loading="lazy"{}>"#, &caps[1], &caps[2])
+        }
+    })
+    .into_owned()
+}
+
+/// Render Markdown source `md` as a minijinja template with `ctx`.
 ///
-/// This function:
-/// - Reads the Markdown file at `src_path` and loads per-file metadata from a sibling `meta.yml`.
-/// - Determines the page title (from metadata, index folder name, or file stem).
-/// - Preprocesses the Markdown to remove or preserve LLM-specific tags:
-///   - `<exclude-from-llm-txt>`: kept for HTML generation but removed from any copied Markdown for LLM consumption.
-///   - `<only-in-llm-txt>`: removed (and its contents removed) before HTML generation; also removed from the final rendered HTML.
-/// - Converts the sanitized Markdown to HTML with `markdown_to_html`, renders it with the configured template (default `"base.html"`), and writes the resulting HTML under `dist_path` mirroring `base_path` (with special handling for `index.md` + `page_slug`).
-/// - Optionally writes a stripped copy of the Markdown next to the generated HTML (controlled by metadata fields `omit_llm_txt_generation`, `generate_llm_txt`, or the `generate_llm_txt_by_default` argument).
-/// - Returns None on I/O or template errors; on success returns a tuple:
-///   (title, href_for_sitemap, optional_relative_md_path_if_copied, optional_llm_description_from_meta, md_was_copied_flag).
-///
-/// Notes:
-/// - Side effects: creates directories, writes HTML files, and may write a stripped Markdown file.
-/// - Returns `None` if reading the source, creating directories, or writing output fails.
-/// - The returned `href_for_sitemap` is a path prefixed with `/my-blog/` suitable for sitemap/index entries.
+/// Globals registered on `env` (`domain`, `base_path`, ...) are available. Shortcodes
+/// (`{{< ... >}}`) are passed through verbatim instead of being parsed as expressions.
 ///
 /// # Examples
 ///
-/// ```ignore
-/// // Example (non-compiling stub): call with appropriate SyntaxSet, Theme and Minijinja Environment.
-/// let result = process_md_file(src_path, base_path, dist_path, &ps, &theme, &env, Some(true), &Default::default(), false, "https://example.com", "");
-/// if let Some((title, href, md_rel, llm_desc, copied)) = result {
-///     println!("Generated {} -> {}, md copied: {}", title, href, copied);
-/// }
 /// ```
-pub fn process_md_file(
+/// use minijinja::{Environment, context};
+/// use ssg_generator_utils::render_markdown_template;
+/// let env = Environment::new();
+/// let md = "{% for n in [1, 2] %}- item {{ n }}\n{% endfor %}{{< note >}}";
+/// let out = render_markdown_template(md, &env, context! {}).unwrap();
+/// assert_eq!(out, "- item 1\n- item 2\n{{< note >}}");
+/// ```
+pub fn render_markdown_template(md: &str, env: &Environment, ctx: Value) -> Result<String, minijinja::Error> {
+    let shortcode = Regex::new(r"\{\{<.*?>\}\}").unwrap();
+    let protected = shortcode.replace_all(md, |caps: &regex::Captures| format!("{{{{ {:?} }}}}", &caps[0]));
+    env.render_str(&protected, ctx)
+}
+
+/// A page as listed in the `site.pages` template global.
+#[derive(Serialize, Debug, Clone)]
+pub struct PageSummary {
+    pub title: String,
+    pub href: String,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+    /// Plain-text summary of the Markdown source, before includes and shortcodes (see [`summarize`])
+    pub summary: String,
+    pub series: Option<String>,
+    /// Serialized as a [`date_context`] object
+    #[serde(serialize_with = "serialize_date")]
+    pub date: Option<DateTime<FixedOffset>>,
+    /// Language of the page, on a site with several (see [`UrlResolver::with_languages`])
+    pub lang: Option<String>,
+    /// What the translations of the page share, on a site with several languages
+    #[serde(skip)]
+    pub translation_key: Option<String>,
+    /// Whether the page is a draft (see [`Meta`])
+    #[serde(skip)]
+    pub draft: bool,
+    /// Markdown source of the page
+    #[serde(skip)]
+    pub source_path: PathBuf,
+    /// Where the page is written, relative to the output directory
+    #[serde(skip)]
+    pub output_path: PathBuf,
+}
+
+fn serialize_date<S: serde::Serializer>(date: &Option<DateTime<FixedOffset>>, serializer: S) -> Result<S::Ok, S::Error> {
+    date.as_ref().map(date_context).serialize(serializer)
+}
+
+/// Read the metadata of the Markdown file at `src_path` and summarize it for `site.pages`.
+///
+/// Title, href and date are resolved exactly as [`process_md_file`] resolves them; `None` is
+/// returned when the file cannot be read.
+pub fn summarize_page(src_path: &Path, base_path: &Path, urls: &UrlResolver, git: &git::GitHistory) -> Option<PageSummary> {
+    let raw_content = fs::read_to_string(src_path).ok()?;
+    let (front_matter, body) = split_front_matter(&raw_content);
+    let (meta, date, title, output_path) = resolve_page(src_path, base_path, urls, git, front_matter);
+    let language = urls.language(src_path.strip_prefix(base_path).unwrap_or(src_path));
+    let translation_key = language
+        .as_ref()
+        .map(|language| meta.translation_key.clone().unwrap_or_else(|| language.key.to_string_lossy().replace('\\', "/")));
+    Some(PageSummary {
+        title,
+        href: urls.href(&permalink(&output_path)),
+        tags: meta.tags.unwrap_or_default(),
+        description: meta.description,
+        summary: summarize(body),
+        series: meta.series,
+        date,
+        lang: language.map(|language| language.lang),
+        translation_key,
+        draft: meta.draft.unwrap_or(false),
+        source_path: src_path.to_path_buf(),
+        output_path,
+    })
+}
+
+/// Resolve the merged metadata, date, title and output path (relative to the output directory, see
+/// [`UrlResolver::page_path`]) of the page at `src_path`.
+///
+/// The date comes from the `date` metadata field, falling back to the date of the first git commit
+/// adding the file (see [`git::GitHistory`]) and then to its modification time, and is written back
+/// into `meta.date` in RFC 3339 form.
+fn resolve_page(
     src_path: &Path,
     base_path: &Path,
-    dist_path: &Path,
-    ps: &SyntaxSet,
-    theme: &syntect::highlighting::Theme,
-    env: &Environment,
-    generate_llm_txt_by_default: Option<bool>,
-    omit_languages: &HashSet<String>,
-    disable_syntax_highlighting: bool,
-    domain: &str,
-    base_path_str: &str,
-) -> Option<(String, String, Option<String>, Option<String>, bool)> {
-    let md_content = match fs::read_to_string(src_path) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Failed to read {}: {}", src_path.display(), e);
-            return None;
-        }
-    };
+    urls: &UrlResolver,
+    git: &git::GitHistory,
+    front_matter: Option<&str>,
+) -> (Meta, Option<DateTime<FixedOffset>>, String, PathBuf) {
+    let mut meta = load_page_meta(src_path, base_path, front_matter);
+    if meta.merge_tags_keywords.unwrap_or(false) {
+        let keywords = merge_keywords(
+            meta.keywords.as_deref().unwrap_or_default(),
+            meta.tags.as_deref().unwrap_or_default(),
+        );
+        meta.keywords = Some(keywords);
+    }
 
-    let meta_path = src_path.with_file_name("meta.yml");
-    let meta = load_meta(&meta_path);
+    // Resolve the page date from meta, falling back to the first commit of the file and then to its
+    // modification time, and store it back in RFC 3339 form so every consumer sees the same value
+    let date = meta
+        .date
+        .as_deref()
+        .and_then(|raw| {
+            parse_date(raw).or_else(|| {
+                tracing::warn!(category = "meta", path = %src_path.display(), "Invalid date {:?} in {}, falling back to its git history or file mtime", raw, src_path.display());
+                None
+            })
+        })
+        .or_else(|| git.file(src_path).map(|history| history.dates.created))
+        .or_else(|| {
+            fs::metadata(src_path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(|mtime| DateTime::<Utc>::from(mtime).fixed_offset())
+        });
+    meta.date = date.map(|d| d.to_rfc3339());
 
     let title = meta.title.clone().unwrap_or_else(|| {
-        if src_path.file_name().map_or(false, |f| f == "index.md") {
+        if src_path.file_name().is_some_and(|f| f == "index.md") {
             folder_name_to_title(src_path.parent().unwrap_or_else(|| Path::new("")))
         } else {
             src_path
@@ -261,69 +1034,417 @@ pub fn process_md_file(
         }
     });
 
+    let output_path = urls.page_path(src_path.strip_prefix(base_path).unwrap(), &meta, date.as_ref());
+    (meta, date, title, output_path)
+}
+
+/// Absolute URL of the target `dest` of a link in the page whose source is `src_path`: the page
+/// of a `.md` source, or the file at that path under `base_path` as copied to the output directory.
+/// Fragments, site paths, URLs with a scheme and links to missing pages are left to the caller.
+fn absolute_link(dest: &str, src_path: &Path, base_path: &Path, links: &links::PageLinks, urls: &UrlResolver) -> Option<String> {
+    let src_dir = src_path.parent().unwrap_or(Path::new(""));
+    match links.resolve(src_dir, dest) {
+        Some(href) => href.map(|href| urls.absolute_url(&href)),
+        None if dest.starts_with(['#', '/']) || dest.contains(':') => None,
+        None => {
+            let rel_src_dir = src_dir.strip_prefix(base_path).unwrap_or(Path::new(""));
+            Some(urls.url(&permalink(&links::normalize(&rel_src_dir.join(dest)))))
+        }
+    }
+}
+
+/// `path` relative to the output directory as a permalink for [`UrlResolver`], with `/` separators.
+fn permalink(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// A page written by [`process_md_file`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PageOutput {
+    pub title: String,
+    /// Site path of the page, as used in the sitemap and the content index
+    pub href: String,
+    /// Markdown file the page was rendered from
+    pub source_path: PathBuf,
+    /// HTML file written, relative to `dist_path`
+    pub output_path: PathBuf,
+    /// Stripped Markdown copy for LLMs, relative to `dist_path`, when one was written
+    pub md_rel_path: Option<String>,
+    /// Estimated tokens of the Markdown copy for LLMs (see [`llms::estimate_tokens`])
+    #[serde(default)]
+    pub llm_tokens: Option<usize>,
+    /// Plain-text rendition of the page, relative to `dist_path`, when one was written
+    #[serde(default)]
+    pub txt_rel_path: Option<String>,
+    /// Gemtext rendition of the page in the Gemini capsule, when one was written
+    #[serde(default)]
+    pub gmi_path: Option<PathBuf>,
+    /// JSON document of the page in the content API, relative to `dist_path`, when one was written
+    #[serde(default)]
+    pub api_rel_path: Option<String>,
+    /// Share image rendered for the page, relative to `dist_path`, when one was written
+    pub card_rel_path: Option<String>,
+    /// Words of prose in the page, leaving out code blocks and markup
+    pub word_count: usize,
+    /// Plain-text summary of the page (see [`summarize`])
+    pub summary: String,
+    /// Front matter merged with the metadata file and cascaded defaults
+    pub meta: Meta,
+}
+
+/// Everything [`generate_site`] produced.
+#[derive(Debug, Default)]
+pub struct SiteOutput {
+    /// Every page rendered (or reused from the page cache), newest first
+    pub pages: Vec<PageOutput>,
+    /// How many of `pages` were reused from the page cache instead of rendered
+    pub reused_pages: usize,
+    /// Every file written under `dist_path`, relative to it
+    pub outputs: BTreeSet<String>,
+    /// Pages that failed and site-wide files (tag pages, sitemap, feed, ...) that could not be written;
+    /// the rest of the site was still generated
+    pub errors: Vec<SsgError>,
+    /// Counts and per-phase timings of the build
+    pub report: report::BuildReport,
+}
+
+/// How every page of a build is rendered; the default renders plain pages with highlighted code.
+#[derive(Serialize, Debug, Default, Clone, Copy)]
+pub struct PageOptions<'a> {
+    /// Copy the Markdown of pages not deciding it with `generate_llm_txt` or `omit_llm_txt_generation`
+    /// next to their HTML for LLMs, with front matter and absolute links (see [`llms::front_matter`] and
+    /// [`llms::normalize_markdown`]); `<exclude-from-llm-txt>` content is left out of the copy and
+    /// `<only-in-llm-txt>` content only appears there
+    pub generate_llm_txt_by_default: Option<bool>,
+    /// Token budget of those copies (see [`llms::fit_to_budget`])
+    pub llms_config: Option<&'a llms::LlmsConfig>,
+    /// Write a plain-text rendition next to every page, its links listed at the end (see
+    /// [`plaintext::markdown_to_text`])
+    pub plain_text: bool,
+    /// Write a gemtext rendition of every page to a Gemini capsule (see [`gemini::GeminiConfig`])
+    pub gemini: Option<&'a gemini::GeminiConfig>,
+    /// Write every page as JSON under `api/pages/`, listed by `api/index.json` (see [`api::PageDocument`])
+    pub content_api: bool,
+    /// Languages whose code blocks are left unhighlighted
+    #[serde(serialize_with = "serialize_sorted")]
+    pub omit_languages: Option<&'a HashSet<String>>,
+    /// Leave every code block unhighlighted; the `no_syntax_highlighting` metadata of a page overrides it
+    pub disable_syntax_highlighting: bool,
+    /// How code is colored; with [`highlight::HighlightMode::Classes`], the colors are written to
+    /// `syntax.css`, linked by the `syntax_css` global
+    pub highlight_mode: highlight::HighlightMode,
+    pub mermaid: highlight::MermaidMode,
+    /// Optimized and responsive variants of local images (see [`images::ImageConfig`])
+    pub image_config: Option<&'a images::ImageConfig>,
+    /// Images loaded eagerly at the top of a page, the rest and every iframe being lazy-loaded unless
+    /// the page sets `lazy_loading: false`
+    pub eager_images: Option<usize>,
+    /// Share data, share cards and JSON-LD of the pages (see [`seo::SeoConfig`])
+    pub seo_config: Option<&'a seo::SeoConfig>,
+    /// `h-entry` markup of the pages for IndieWeb readers (see [`microformats::Microformats`])
+    pub microformats: Option<&'a microformats::MicroformatsConfig>,
+    /// Comments widget under the posts (see [`comments::inject_comments`])
+    pub comments: Option<&'a comments::CommentsConfig>,
+    /// Snippet added to the `<head>` of every page and listing (see [`analytics::inject_analytics`])
+    pub analytics: Option<&'a analytics::AnalyticsConfig>,
+    /// `edit_url` of every page, leading to the page editing its source (see [`git::EditLinks::url`])
+    pub edit_links: Option<&'a git::EditLinks>,
+    /// Fail pages on template errors instead of falling back, and make undefined template variables errors
+    pub strict_templates: bool,
+    /// Fail pages on invalid or unknown metadata (see [`deny_unknown_meta_fields`]) instead of falling back
+    pub strict_meta: bool,
+}
+
+fn serialize_sorted<S: serde::Serializer>(set: &Option<&HashSet<String>>, serializer: S) -> Result<S::Ok, S::Error> {
+    set.map(|set| set.iter().collect::<BTreeSet<_>>()).serialize(serializer)
+}
+
+/// The options of a build that change how its pages render, serialized into the key of the page
+/// cache (see [`cache::site_key`]) so that changing any of them renders every page again.
+#[derive(Serialize)]
+struct RenderOptions<'a> {
+    domain: &'a str,
+    base_path: &'a str,
+    permalink_pattern: Option<&'a str>,
+    pretty_urls: bool,
+    i18n: Option<&'a i18n::I18nConfig>,
+    code_theme: &'a str,
+    code_theme_light: Option<&'a str>,
+    /// Fingerprinted names of the static assets, which templates link to
+    fingerprints: &'a BTreeMap<String, String>,
+    service_worker: Option<&'a sw::ServiceWorkerConfig>,
+    web_manifest: Option<&'a webmanifest::WebManifestConfig>,
+    page: PageOptions<'a>,
+}
+
+/// What the pages of a build share while being rendered by [`process_md_file`].
+#[derive(Clone, Copy)]
+pub struct PageContext<'a> {
+    pub ps: &'a SyntaxSet,
+    pub theme: &'a syntect::highlighting::Theme,
+    pub env: &'a Environment<'a>,
+    pub urls: &'a UrlResolver,
+    /// Hrefs of the pages, which Markdown links to their sources are rewritten to
+    pub links: &'a links::PageLinks,
+    /// Profiles of the authors of commits (see [`authors::load_authors`])
+    pub authors: &'a authors::Authors,
+    /// Commit dates and authors of the sources
+    pub git: &'a git::GitHistory,
+    pub stats: &'a Stats,
+    pub options: PageOptions<'a>,
+}
+
+/// Processes a single Markdown source file into an HTML page, optionally writes a stripped Markdown copy for LLM use, and returns metadata for site indexing.
+///
+/// `ps`, `theme`, `env`, `urls`, `links`, `authors` and `stats` come from `page_context`, whose options
+/// (see [`PageOptions`]) decide what else is written for the page and how strict rendering is.
+///
+/// The metadata of the page merges its front matter, the metadata file next to it (see
+/// [`find_meta_file`]) and the defaults cascaded from above (see [`cascaded_meta`]), and the page is
+/// titled by it, the name of its folder or its file stem. Its Markdown gets its includes inlined (see
+/// [`include::expand_includes`]), is rendered as a template with `templated: true` (see
+/// [`render_markdown_template`]), has its shortcodes expanded (see [`shortcodes::expand_shortcodes`])
+/// and is converted with [`markdown_to_html`]. The HTML is rendered through the template of the page
+/// (default `"base.html"`; see [`templates::DEFAULT_TEMPLATES`] for what it receives) and written under
+/// `dist_path` at the path chosen by `urls` (see [`UrlResolver::page_path`]).
+///
+/// Returns a [`PageOutput`] describing the page, whose `href` is site-absolute, or the [`SsgError`]
+/// that stopped it: I/O or, with `strict_templates` or `strict_meta`, a metadata or template error.
+/// Time spent per [`Phase`] and warnings are added to `stats`.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Example (non-compiling stub): call with appropriate SyntaxSet, Theme and Minijinja Environment.
+/// let urls = UrlResolver::new("https://example.com", "/");
+/// let options = PageOptions { generate_llm_txt_by_default: Some(true), eager_images: Some(1), ..Default::default() };
+/// let page_context = PageContext { ps: &ps, theme: &theme, env: &env, urls: &urls, links: &Default::default(), authors: &Default::default(), git: &Default::default(), stats: &Default::default(), options };
+/// let result = process_md_file(src_path, base_path, dist_path, &Default::default(), &page_context);
+/// if let Ok(page) = result {
+///     println!("Generated {} -> {}, md copied: {}", page.title, page.href, page.md_rel_path.is_some());
+/// }
+/// ```
+pub fn process_md_file(
+    src_path: &Path,
+    base_path: &Path,
+    dist_path: &Path,
+    nav: &nav::PageNav,
+    page_context: &PageContext,
+) -> Result<PageOutput, SsgError> {
+    let PageContext { ps, theme, env, urls, links, authors, git, stats, options } = *page_context;
+    let PageOptions {
+        generate_llm_txt_by_default,
+        llms_config,
+        plain_text,
+        gemini,
+        content_api,
+        seo_config,
+        microformats,
+        comments,
+        analytics,
+        edit_links,
+        strict_templates,
+        strict_meta,
+        ..
+    } = options;
+    let raw_content = stats.time(Phase::Io, || fs::read_to_string(src_path)).map_err(SsgError::io(src_path))?;
+
+    let parse_start = Instant::now();
+    let (front_matter, md_content) = split_front_matter(&raw_content);
+    let strict = |meta: Meta, content: &str, first_line: usize| {
+        if strict_meta { deny_unknown_meta_fields(&meta, content, first_line) } else { Ok(()) }
+    };
+    match front_matter.map(|yaml| parse_front_matter(yaml).and_then(|front| strict(front, yaml, 2))) {
+        Some(Err(source)) if strict_templates || strict_meta => {
+            return Err(SsgError::Meta { path: src_path.to_path_buf(), source });
+        }
+        Some(Err(e)) => stats.warn("meta", Some(src_path), format_args!("Invalid front matter in {}: {}", src_path.display(), e)),
+        _ => {}
+    }
+    if let Some(meta_path) = src_path.parent().and_then(find_meta_file)
+        && let Ok(content) = fs::read_to_string(&meta_path)
+        && let Err(source) = parse_meta(&meta_path, &content).and_then(|meta| strict(meta, &content, 1))
+    {
+        if strict_templates || strict_meta {
+            return Err(SsgError::Meta { path: meta_path, source });
+        }
+        stats.warn("meta", Some(&meta_path), format_args!("Invalid {}: {}", meta_path.display(), source));
+    }
+    let md_content = &include::expand_includes(md_content, base_path);
+    let (mut meta, date, title, output_path) = resolve_page(src_path, base_path, urls, git, front_matter);
+    let git_authors = git.file(src_path).map(|history| history.authors.as_slice()).unwrap_or_default();
+    let contributors = authors::contributors(git_authors, authors, urls);
+    let href = urls.href(&permalink(&output_path));
+    stats.add(Phase::Parse, parse_start.elapsed());
+
+    // Pages opting in with `templated: true` are rendered as templates before anything else
+    let md_content = &if meta.templated.unwrap_or(false) {
+        let ctx = context! {
+            title => &title,
+            date => date.as_ref().map(date_context),
+            meta => &meta,
+            ..Value::from_serialize(&meta.extra)
+        };
+        match stats.time(Phase::Template, || render_markdown_template(md_content, env, ctx)) {
+            Ok(rendered) => rendered,
+            Err(e) if strict_templates => return Err(SsgError::template(src_path)(e)),
+            Err(e) => {
+                stats.warn("template", Some(src_path), format_args!("Template error in {}: {}", src_path.display(), e));
+                md_content.to_string()
+            }
+        }
+    } else {
+        md_content.to_string()
+    };
+
 
+    let parse_start = Instant::now();
     // Remove <exclude-from-llm-txt> tags (but keep their content) before HTML generation
-    let md_content_no_exclude_tag = remove_tag_only(&md_content, "exclude-from-llm-txt");
+    let md_content_no_exclude_tag = remove_tag_only(md_content, "exclude-from-llm-txt");
     // Remove <only-in-llm-txt> tags AND their content before HTML generation
     let md_content_no_tags = remove_tag_and_contents(&md_content_no_exclude_tag, "only-in-llm-txt");
-    let body_html = markdown_to_html(
+    let word_count = count_words(&md_content_no_tags);
+    stats.add(Phase::Parse, parse_start.elapsed());
+    let md_content_no_tags = stats
+        .time(Phase::Template, || shortcodes::expand_shortcodes(&md_content_no_tags, env, strict_templates))
+        .map_err(SsgError::template(src_path))?;
+    let summary = stats.time(Phase::Parse, || summarize(&md_content_no_tags));
+    if meta.description.is_none() && !summary.is_empty() {
+        meta.description = Some(summary.clone());
+    }
+    let (body_html, toc) = markdown_to_html(
         &md_content_no_tags,
         ps,
         theme,
-        omit_languages,
-        disable_syntax_highlighting,
+        src_path.parent(),
+        Some(links),
+        &PageOptions {
+            disable_syntax_highlighting: meta.no_syntax_highlighting.unwrap_or(options.disable_syntax_highlighting),
+            eager_images: options.eager_images.filter(|_| meta.lazy_loading.unwrap_or(true)),
+            ..options
+        },
+        Some(stats),
     );
 
+    let home = src_path.strip_prefix(base_path).unwrap_or(src_path) == Path::new("index.md");
+    // Posts without an image of their own get a rendered card
+    let card = seo_config
+        .and_then(|config| config.cards.as_ref())
+        .filter(|_| !home && meta.image.is_none())
+        .and_then(|cards| {
+            let card = cards::card_permalink(&output_path);
+            let site_name = seo_config.and_then(|config| config.site_name.as_deref());
+            let card_path = dist_path.join(&card);
+            match stats.time(Phase::Io, || cards::write_card(&card_path, &title, site_name, cards)) {
+                Ok(()) => Some(card),
+                Err(e) => {
+                    stats.warn("image", Some(src_path), format_args!("Failed to render the card of {}: {}", src_path.display(), e));
+                    None
+                }
+            }
+        });
+    let seo = seo::Seo::new(&title, &meta, &href, home, card.as_deref(), urls, seo_config);
+    let json_ld = seo.json_ld(&meta, &nav.breadcrumbs, urls, seo_config);
+    let microformats = microformats.map(|config| {
+        let default_author = seo_config.and_then(|config| config.author.as_deref());
+        microformats::Microformats::new(&title, &seo.url, date.as_ref(), meta.author.as_deref(), default_author, config, urls)
+    });
+    let comments = comments.filter(|_| meta.comments.unwrap_or(!home)).map(comments::Comments::new);
+    let edit_url = edit_links.map(|links| links.url(src_path));
+    let lang = urls.language(src_path.strip_prefix(base_path).unwrap_or(src_path)).map(|language| language.lang);
+    let alternates = match (&lang, urls.i18n()) {
+        (Some(lang), Some(config)) => i18n::alternates(lang, &href, &nav.translations, config, urls),
+        _ => Vec::new(),
+    };
+    let page_authors: Vec<_> = meta
+        .author_names()
+        .into_iter()
+        .map(|name| context! { href => authors::author_href(&name, urls), name => name })
+        .collect();
+    let body = match microformats {
+        Some(_) => microformats::wrap_content(&body_html),
+        None => body_html.clone(),
+    };
     let template_name = meta.extends.as_deref().unwrap_or("base.html");
-    let rendered = if let Some(tmpl) = env.get_template(template_name).ok() {
-        tmpl.render(context! {
-            title => &title,
-            body => &body_html,
-            domain => domain,
-            base_path => base_path_str,
-        })
-        .unwrap_or_else(|e| {
-            eprintln!("Template render error for {}: {}", src_path.display(), e);
+    let template_start = Instant::now();
+    let rendered = match env.get_template(template_name) {
+        Ok(tmpl) => {
+            let result = tmpl.render(context! {
+                title => &title,
+                body => &body,
+                domain => urls.domain(),
+                base_path => urls.base_path(),
+                description => &meta.description,
+                keywords => &meta.keywords,
+                tags => &meta.tags,
+                page_slug => &meta.page_slug,
+                date => date.as_ref().map(date_context),
+                toc => &toc,
+                summary => &summary,
+                related => &nav.related,
+                prev => &nav.prev,
+                next => &nav.next,
+                series => &nav.series,
+                breadcrumbs => &nav.breadcrumbs,
+                menus => &nav.menus,
+                seo => &seo,
+                canonical_url => &seo.url,
+                json_ld => &json_ld,
+                microformats => &microformats,
+                comments => &comments,
+                contributors => &contributors,
+                authors => &page_authors,
+                edit_url => &edit_url,
+                lang => &lang,
+                translations => &nav.translations,
+                alternates => &alternates,
+                meta => &meta,
+                ..Value::from_serialize(&meta.extra)
+            });
+            match result {
+                Ok(html) => html,
+                Err(e) if strict_templates => return Err(SsgError::template(src_path)(e)),
+                Err(e) => {
+                    stats.warn("template", Some(src_path), format_args!("Template render error for {}: {}", src_path.display(), e));
+                    body_html.clone()
+                }
+            }
+        }
+        Err(e) if strict_templates => return Err(SsgError::template(src_path)(e)),
+        Err(_) => {
+            stats.warn("template", Some(src_path), format_args!("Template {} not found, rendering body only for {}", template_name, src_path.display()));
             body_html.clone()
-        })
-    } else {
-        eprintln!("Template {} not found, rendering body only for {}", template_name, src_path.display());
-        body_html.clone()
+        }
     };
+    stats.add(Phase::Template, template_start.elapsed());
 
-    let rel_path = src_path.strip_prefix(base_path).unwrap();
-    let mut dest_path = dist_path.join(rel_path);
+    let dest_path = dist_path.join(&output_path);
 
-    // If this is index.md and meta.page_slug is set, use it as the last directory
-    if src_path.file_name().map_or(false, |f| f == "index.md") {
-        if let Some(ref slug) = meta.page_slug {
-            // Replace the last component (parent dir) with the slug
-            if let Some(parent) = dest_path.parent().and_then(|p| p.parent()) {
-                dest_path = parent.join(slug).join("index.html");
-            } else {
-                dest_path = dist_path.join(slug).join("index.html");
-            }
-        } else {
-            dest_path.set_extension("html");
-        }
-    } else {
-        dest_path.set_extension("html");
+    // After HTML generation, remove <only-in-llm-txt> and its content from the HTML
+    let mut rendered_final = remove_tag_and_contents(&rendered, "only-in-llm-txt");
+    if meta.noindex.unwrap_or(false) {
+        rendered_final = robots::inject_noindex(&rendered_final);
     }
-
-    if let Some(parent) = dest_path.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
-            eprintln!("Failed to create directory {}: {}", parent.display(), e);
-            return None;
+    if let Some(comments) = &comments {
+        rendered_final = comments::inject_comments(&rendered_final, comments);
+    }
+    if let Some(analytics) = analytics.filter(|_| meta.analytics.unwrap_or(true)) {
+        rendered_final = analytics::inject_analytics(&rendered_final, &analytics.snippet(urls), analytics.script_url());
+    }
+    if seo_config.is_some_and(|config| config.inject) {
+        if !rendered_final.contains("og:title") {
+            rendered_final = inject_into_head(&rendered_final, &seo.to_html());
+        }
+        if !rendered_final.contains("application/ld+json") {
+            rendered_final = inject_into_head(&rendered_final, &seo::json_ld_script(&json_ld));
         }
     }
-
-
-    // After HTML generation, remove <only-in-llm-txt> and its content from the HTML
-    let rendered_final = remove_tag_and_contents(&rendered, "only-in-llm-txt");
-    if let Err(e) = fs::write(&dest_path, &rendered_final) {
-        eprintln!("Failed to write {}: {}", dest_path.display(), e);
-        return None;
+    let io_start = Instant::now();
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent).map_err(SsgError::io(parent))?;
     }
+    fs::write(&dest_path, &rendered_final).map_err(SsgError::io(&dest_path))?;
+    stats.add(Phase::Io, io_start.elapsed());
 
  
     let should_copy_md = if meta.omit_llm_txt_generation.unwrap_or(false) {
@@ -334,59 +1455,207 @@ pub fn process_md_file(
         generate_llm_txt_by_default.unwrap_or(false)
     };
 
-    let mut md_rel_path: Option<String> = None;
-    let mut md_copied = false;
-    if should_copy_md {
-        if let Some(parent) = dest_path.parent() {
-            let md_filename = src_path.file_name().unwrap();
-            let md_dest = parent.join(md_filename);
-            // Write the stripped md content (with <exclude-from-llm-txt> tag and its content removed, and <only-in-llm-txt> tag only removed)
-            let md_content_no_exclude = remove_tag_and_contents(&md_content, "exclude-from-llm-txt");
-            let md_content_no_only_tag = remove_tag_only(&md_content_no_exclude, "only-in-llm-txt");
-            if let Err(e) = fs::write(&md_dest, &md_content_no_only_tag) {
-                eprintln!("Failed to write stripped markdown file to {}: {}", md_dest.display(), e);
-            } else {
-                // Compute relative path from dist_path
-                if let Ok(rel_md) = md_dest.strip_prefix(dist_path) {
-                    md_rel_path = Some(rel_md.to_string_lossy().replace('\\', "/"));
-                    md_copied = true;
+    let txt_rel_path = if plain_text {
+        let rel = permalink(&output_path.with_extension("txt"));
+        let text = stats.time(Phase::Parse, || {
+            plaintext::markdown_to_text(&md_content_no_tags, &title, |dest| {
+                absolute_link(dest, src_path, base_path, links, urls).unwrap_or_else(|| urls.absolute_url(dest))
+            })
+        });
+        let txt_dest = dist_path.join(&rel);
+        stats.time(Phase::Io, || fs::write(&txt_dest, text)).map_err(SsgError::io(&txt_dest))?;
+        Some(rel)
+    } else {
+        None
+    };
+
+    let gmi_path = if let Some(gemini) = gemini {
+        let gmi_dest = gemini.dist_path(dist_path).join(gemini::gemini_path(&permalink(&output_path)));
+        let src_dir = src_path.parent().unwrap_or(Path::new(""));
+        let gmi = stats.time(Phase::Parse, || {
+            gemini::markdown_to_gemtext(&md_content_no_tags, &title, |dest| match links.resolve(src_dir, dest) {
+                // Other pages are mirrored in the capsule; Gemini has no fragments
+                Some(Some(href)) => {
+                    let path = href.split(['?', '#']).next().unwrap_or_default();
+                    let path = path.strip_prefix(urls.base_path()).unwrap_or(path);
+                    format!("/{}", gemini::gemini_path(path.trim_start_matches('/')))
                 }
-            }
+                _ => absolute_link(dest, src_path, base_path, links, urls).unwrap_or_else(|| urls.absolute_url(dest)),
+            })
+        });
+        stats
+            .time(Phase::Io, || {
+                if let Some(parent) = gmi_dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&gmi_dest, gmi)
+            })
+            .map_err(SsgError::io(&gmi_dest))?;
+        Some(gmi_dest)
+    } else {
+        None
+    };
+
+    let api_rel_path = if content_api {
+        let slug = api::page_slug(&output_path);
+        let document = api::PageDocument {
+            slug: &slug,
+            title: &title,
+            href: &href,
+            url: urls.absolute_url(&href),
+            date: meta.date.as_deref(),
+            summary: &summary,
+            word_count,
+            html: &body_html,
+            text: stats.time(Phase::Parse, || search::plain_text(&md_content_no_tags)),
+            meta: &meta,
+        };
+        let rel = stats.time(Phase::Io, || api::write_page(dist_path, &document));
+        Some(rel.map_err(SsgError::io(dist_path.join(api::page_permalink(&slug))))?)
+    } else {
+        None
+    };
+
+    let mut md_rel_path: Option<String> = None;
+    let mut llm_tokens: Option<usize> = None;
+    if should_copy_md && let Some(parent) = dest_path.parent() {
+        let md_filename = src_path.file_name().unwrap();
+        let md_dest = parent.join(md_filename);
+        // Write the stripped md content (with <exclude-from-llm-txt> tag and its content removed, and <only-in-llm-txt> tag only removed)
+        let md_content_no_exclude = remove_tag_and_contents(md_content, "exclude-from-llm-txt");
+        let md_content_no_only_tag = remove_tag_only(&md_content_no_exclude, "only-in-llm-txt");
+        let normalized = llms::normalize_markdown(&md_content_no_only_tag, |dest| {
+            absolute_link(dest, src_path, base_path, links, urls)
+        });
+        let md_copy = match llms_config.and_then(|config| config.max_page_tokens) {
+            Some(max_tokens) => llms::fit_to_budget(&normalized, max_tokens, meta.llm_description.as_deref()),
+            None => normalized.as_str().into(),
+        };
+        if md_copy.len() < normalized.len() {
+            tracing::debug!("Shortened the Markdown copy of {} to fit the token budget", src_path.display());
+        }
+        llm_tokens = Some(llms::estimate_tokens(&md_copy));
+        let md_copy = format!(
+            "{}{}",
+            llms::front_matter(&title, &meta.canonical_url(&href, urls), meta.date.as_deref()),
+            md_copy
+        );
+        stats.time(Phase::Io, || fs::write(&md_dest, &md_copy)).map_err(SsgError::io(&md_dest))?;
+        // Compute relative path from dist_path
+        if let Ok(rel_md) = md_dest.strip_prefix(dist_path) {
+            md_rel_path = Some(permalink(rel_md));
         }
     }
 
-    // Compute href for sitemap/index
-    let href = if src_path.file_name().map_or(false, |f| f == "index.md") {
-        if let Some(ref slug) = meta.page_slug {
-            format!("{}/{}/index.html", base_path_str, slug)
-        } else {
-            format!(
-                "{}/{}",
-                base_path_str,
-                rel_path.with_extension("html").to_string_lossy().replace('\\', "/")
-            )
+    Ok(PageOutput {
+        title,
+        href,
+        source_path: src_path.to_path_buf(),
+        output_path,
+        md_rel_path,
+        llm_tokens,
+        txt_rel_path,
+        gmi_path,
+        api_rel_path,
+        card_rel_path: card,
+        word_count,
+        summary,
+        meta,
+    })
+}
+
+/// Marker ending the summary of a page written by hand.
+const MORE_MARKER: &str = "<!--more-->";
+
+/// Words kept in a summary taken from the first paragraph.
+const SUMMARY_WORDS: usize = 50;
+
+/// Plain-text summary of the Markdown `md`: everything before a `<!--more-->` marker, or else its
+/// first paragraph cut to 50 words. Code blocks and HTML are left out.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::summarize;
+/// assert_eq!(summarize("# Title\n\nFirst *paragraph*.\n\nSecond one."), "First paragraph.");
+/// assert_eq!(summarize("Intro.\n\nStill `intro`.\n\n<!--more-->\n\nRest."), "Intro. Still intro.");
+/// assert!(summarize(&"word ".repeat(80)).ends_with("word…"));
+/// ```
+pub fn summarize(md: &str) -> String {
+    let (md, explicit) = match md.find(MORE_MARKER) {
+        Some(end) => (&md[..end], true),
+        None => (md, false),
+    };
+    let mut text = String::new();
+    let mut in_code_block = false;
+    let mut in_paragraph = false;
+    for event in MdParser::new_ext(md, Options::all()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::Paragraph) => in_paragraph = true,
+            Event::End(TagEnd::Paragraph) => {
+                in_paragraph = false;
+                if !explicit && !text.trim().is_empty() {
+                    break;
+                }
+                text.push(' ');
+            }
+            Event::End(TagEnd::Heading(_)) if explicit => text.push(' '),
+            Event::Text(t) | Event::Code(t) if !in_code_block && (explicit || in_paragraph) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
         }
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if !explicit && words.len() > SUMMARY_WORDS {
+        format!("{}…", words[..SUMMARY_WORDS].join(" "))
     } else {
-        format!(
-            "{}/{}",
-            base_path_str,
-            rel_path.with_extension("html").to_string_lossy().replace('\\', "/")
-        )
-    };
+        words.join(" ")
+    }
+}
+
+/// Count the words of prose in `md`, skipping code blocks, inline code and HTML.
+fn count_words(md: &str) -> usize {
+    let mut in_code_block = false;
+    MdParser::new_ext(md, Options::all())
+        .filter_map(|event| match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                None
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                None
+            }
+            Event::Text(text) if !in_code_block => Some(text.split_whitespace().count()),
+            _ => None,
+        })
+        .sum()
+}
 
-    Some((title, href, md_rel_path, meta.llm_description.clone(), md_copied))
+/// The language versions of `page` for the sitemap, from the translations in its `nav` (see
+/// [`i18n::alternates`]).
+fn page_alternates(page: &PageOutput, nav: Option<&nav::PageNav>, base_path: &Path, urls: &UrlResolver) -> Vec<i18n::Alternate> {
+    let language = urls.language(page.source_path.strip_prefix(base_path).unwrap_or(&page.source_path));
+    match (language, urls.i18n(), nav) {
+        (Some(language), Some(config), Some(nav)) => i18n::alternates(&language.lang, &page.href, &nav.translations, config, urls),
+        _ => Vec::new(),
+    }
 }
 
-/// Create a "content-index" page under `dist_path` using the template at `content_index_path`.
+/// Create a "content-index" page under `dist_path` using the `"content-index.html"` template.
 ///
-/// Reads the template file, registers it in the provided Minijinja `env` as `"content-index.html"`,
-/// renders it with `entries` mapped to `{ pages: [{ title, href }, ...], title: "Index Content" }`,
-/// and writes the result to `<dist_path>/content-index/index.html`.
+/// When `content_index_path` is given, that file is read and registered in the provided Minijinja `env` as
+/// `"content-index.html"`; otherwise the template comes from the environment's loader (see
+/// [`templates::loader`] for the built-in fallback). The template is rendered with `entries` mapped to `{ pages: [{ title, href, summary }, ...], title: "Index Content" }`
+/// and the same pages grouped by directory as `tree` (see [`sections::content_tree`]), and writes the result to
+/// `<dist_path>/content-index/index.html`.
 ///
-/// `entries` must be a slice of `(title, href)` pairs; any leading "/my-blog/" prefix in `href` is
-/// stripped before rendering so links in the index are relative to the site root.
+/// `entries` must be a slice of `(title, href, summary)` triples, with hrefs as built by [`UrlResolver::href`]; they are passed
+/// to the template unchanged.
 ///
-/// Errors from file I/O or template rendering are propagated via the `Result`.
+/// Errors from file I/O or template rendering are returned as [`SsgError::Io`] and [`SsgError::Template`].
 ///
 /// # Examples
 ///
@@ -396,165 +1665,667 @@ pub fn process_md_file(
 /// # // Assume `create_index_page` is available in this crate.
 /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let dist = Path::new("dist");
-/// let content_index_template = Path::new("templates/content-index.html");
+/// let content_index_template = Some(Path::new("templates/content-index.html"));
 /// let mut env = Environment::new();
 ///
 /// let entries = vec![
-///     ("First Page".to_string(), "/my-blog/first/index.html".to_string()),
-///     ("Second Page".to_string(), "second.html".to_string()),
+///     ("First Page".to_string(), "/my-blog/first/index.html".to_string(), "The first page.".to_string()),
+///     ("Second Page".to_string(), "/my-blog/second.html".to_string(), String::new()),
 /// ];
 ///
-/// use ssg_generator_utils::create_index_page;
-/// create_index_page(dist, &entries, &mut env, content_index_template, "/my-blog")?;
+/// use ssg_generator_utils::{create_index_page, sections::ContentTree};
+/// create_index_page(dist, &entries, &ContentTree::default(), &mut env, content_index_template)?;
 /// # Ok(()) }
 /// ```
 pub fn create_index_page(
     dist_path: &Path,
-    entries: &[(String, String)],
+    entries: &[(String, String, String)],
+    tree: &sections::ContentTree,
     env: &mut Environment,
-    content_index_path: &Path,
-    base_path_str: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let index_template_str = fs::read_to_string(content_index_path)?;
-
-    env.add_template_owned("content-index.html", index_template_str)?;
+    content_index_path: Option<&Path>,
+) -> Result<(), SsgError> {
+    if let Some(content_index_path) = content_index_path {
+        let index_template_str = fs::read_to_string(content_index_path).map_err(SsgError::io(content_index_path))?;
+        env.add_template_owned("content-index.html", index_template_str)
+            .map_err(SsgError::template(content_index_path))?;
+    }
 
     let items: Vec<_> = entries
         .iter()
-        .map(|(title, href)| {
-            let href = href.strip_prefix(base_path_str).unwrap_or(href).to_string();
-            context! { href => href, title => title.clone() }
-        })
+        .map(|(title, href, summary)| context! { href => href, title => title, summary => summary })
         .collect();
 
-    let rendered = env.get_template("content-index.html")?.render(context! {
-        pages => items,
-        title => "Index Content",
-    })?;
+    let rendered = env
+        .get_template("content-index.html")
+        .and_then(|tmpl| {
+            tmpl.render(context! {
+                pages => items,
+                tree => tree,
+                title => "Index Content",
+                description => (),
+                keywords => Vec::<String>::new(),
+            })
+        })
+        .map_err(SsgError::template("content-index.html"))?;
 
     let index_dir = dist_path.join("content-index");
-    fs::create_dir_all(&index_dir)?;
+    fs::create_dir_all(&index_dir).map_err(SsgError::io(&index_dir))?;
     let index_path = index_dir.join("index.html");
-    fs::write(index_path, rendered)?;
+    fs::write(&index_path, rendered).map_err(SsgError::io(&index_path))?;
 
     Ok(())
 }
 
+/// Settings of a build of the whole site by [`generate_site`]; the default builds a site at the
+/// root of its domain with the templates of `templates` or the built-in ones.
+#[derive(Default, Clone, Copy)]
+pub struct SiteOptions<'a> {
+    pub domain: &'a str,
+    /// Path the site is served under, such as `/blog/` (see [`UrlResolver::new`])
+    pub base_path: &'a str,
+    /// Where pages are written, such as `/:year/:slug/`, instead of mirroring `base_path` (see
+    /// [`UrlResolver::page_path`])
+    pub permalink_pattern: Option<&'a str>,
+    /// Write pages as `<slug>/index.html` and link to them without `index.html`
+    pub pretty_urls: bool,
+    /// Languages of the site, each but the default one written under a directory of its own (see
+    /// [`i18n::I18nConfig`])
+    pub i18n: Option<&'a i18n::I18nConfig>,
+    /// Directory of the templates (default: `templates`), falling back to the built-in ones
+    pub templates_path: Option<&'a Path>,
+    /// Template of `content-index/index.html`, listing every page without `exclude_from_index: true`
+    /// flat and by directory (default: `content-index.html`)
+    pub content_index_path: Option<&'a Path>,
+    /// Header of `llms.txt`, which lists the pages copied for LLMs in sections (see [`llms::order_sections`])
+    pub llms_title: Option<&'a str>,
+    pub llms_description: Option<&'a str>,
+    /// Also write `llms-full.txt`, the Markdown of those pages in the same order, each under a
+    /// `# source: <url>` header and within the `max_total_tokens` of `llms_config`
+    pub llms_full: bool,
+    /// Syntect theme of code blocks (default: [`highlight::DEFAULT_CODE_THEME`]); an unknown name is an error
+    pub code_theme: Option<&'a str>,
+    /// Theme of `syntax-light.css`, for templates switching on `prefers-color-scheme`; only written with
+    /// [`highlight::HighlightMode::Classes`], and linked by the `syntax_css_light` global
+    pub code_theme_light: Option<&'a str>,
+    /// Packdump of the syntax definitions (default: the one bundled into the binary, see
+    /// [`highlight::load_syntax_set`])
+    pub syntaxes_path: Option<&'a Path>,
+    /// Directory of the files templates read as `data` (see [`data::load_data_dir`]), of `menus.yml` and
+    /// of the `authors.yml` profiles of commit authors and author pages (see [`authors::load_authors`])
+    pub data_path: Option<&'a Path>,
+    /// Build the pages with `draft: true` too
+    pub drafts: bool,
+    /// Write `feed.xml` and the feeds of the tags (see [`feed::FeedConfig`])
+    pub feed_config: Option<&'a feed::FeedConfig>,
+    /// Write `podcast.xml` (see [`feed::PodcastConfig`])
+    pub podcast: Option<&'a feed::PodcastConfig>,
+    /// Rules of `robots.txt`, which is written unless the static directory provides one (see
+    /// [`robots::write_robots`])
+    pub robots_config: Option<&'a robots::RobotsConfig>,
+    /// Write `_redirects` and `_headers` for the host (see [`deploy::DeployConfig`])
+    pub deploy_config: Option<&'a deploy::DeployConfig>,
+    /// Pages sharing the most tags with a page given to its template as `related` (default:
+    /// [`nav::DEFAULT_RELATED_POSTS`])
+    pub related_posts: Option<usize>,
+    /// Order and pagination of the listings of directories without an `index.md` (see
+    /// [`sections::write_section_pages`])
+    pub section_config: Option<&'a sections::SectionConfig>,
+    /// Menus of the site (default: those of `menus.yml` in `data_path`, see [`menus::resolve_menus`])
+    pub menus: Option<&'a menus::Menus>,
+    /// Write a `sw.js` caching the site for offline visits, registered by the `sw_register` global (see
+    /// [`sw::write_service_worker`])
+    pub service_worker: Option<&'a sw::ServiceWorkerConfig>,
+    /// Render icons from a logo next to a `manifest.webmanifest`, linked by the `manifest_tags` global (see
+    /// [`webmanifest::head_tags`])
+    pub web_manifest: Option<&'a webmanifest::WebManifestConfig>,
+    /// Write a chunked full-text search index and its `search.js` client to `search/` (see
+    /// [`search::write_search_index`])
+    pub search_index: bool,
+    /// Write an ActivityPub actor, outbox and WebFinger response (see [`activitypub::write_activitypub`])
+    pub activitypub: Option<&'a activitypub::ActivityPubConfig>,
+    /// Template of the tag pages (default: `tag.html`, see [`tags::write_tag_pages`])
+    pub tag_template: Option<&'a str>,
+    /// Directory copied into the root of the output directory (see [`assets::copy_static_dir`])
+    pub static_path: Option<&'a Path>,
+    /// Also copy the stylesheets, scripts, images and fonts of `static_path` under a name carrying a hash
+    /// of their content (see [`assets::fingerprint_static_dir`])
+    pub fingerprint_assets: bool,
+    /// Where the outputs of the build are recorded for pruning the next one (see [`prune::record_outputs`]);
+    /// builds where some page or step failed leave it untouched
+    pub manifest_path: Option<&'a Path>,
+    /// Delete the files recorded by the previous build that this one no longer wrote
+    pub prune: bool,
+    /// Write gzip and brotli copies of every text output (see [`compress::precompress`])
+    pub precompress: bool,
+    /// Where rendered pages are cached for the next build, which reuses the pages whose inputs are
+    /// unchanged (see [`cache::page_key`])
+    pub page_cache_path: Option<&'a Path>,
+    /// URLs per sitemap file before it is split (see [`sitemap::write_sitemaps`])
+    pub sitemap_chunk_size: Option<usize>,
+    /// Compiles the site stylesheet (with Tailwind, say) once every page is written, returning its path
+    /// relative to `dist_path`; it is counted among the outputs, so it is precached, precompressed and
+    /// pruned like them
+    pub stylesheet: Option<&'a dyn Fn() -> std::io::Result<String>>,
+    /// How every page is rendered
+    pub page: PageOptions<'a>,
+}
+
 /// Generate a static site from a list of Markdown files, write supporting artifacts, and return metadata.
 ///
-/// Processes the provided Markdown files (in parallel) to produce HTML pages under `dist_path` using
-/// templates from `templates_path` and syntax definitions from `syntaxes_path`. Side effects:
-/// - Writes generated HTML files (and optional stripped Markdown copies) into `dist_path`.
-/// - Writes `sitemap.xml` to `dist_path`.
-/// - Creates a content index page at `{dist_path}/content-index/index.html` using `content_index_path`.
-/// - Writes `llms.txt` to `dist_path` listing pages whose Markdown was copied for LLM consumption.
+/// Renders the pages in parallel with [`process_md_file`], then writes what is built from all of them:
+/// the content index (see [`create_index_page`]), the sitemap (see [`sitemap::SitemapEntry`]) and
+/// `robots.txt`, `llms.txt`, the feeds, the tag, series, author and section pages, redirects for the
+/// `aliases` of pages (see [`redirects::write_redirect`]) and copies of the other files under `base_path`.
+/// Every setting of `options` (see [`SiteOptions`] and [`PageOptions`]) documents what it adds.
 ///
-/// Behavior notes:
-/// - Syntax highlighting is loaded from `syntaxes_path/syntaxes.packdump` and a default dark theme is used.
-/// - Template loader is rooted at `templates_path`; missing templates fall back to body HTML for that page.
-/// - The `generate_llm_txt_by_default` flag determines the default behavior for copying stripped Markdown files:
-///   meta flags on a per-file basis (generate_llm_txt, omit_llm_txt_generation) override this default.
-/// - `llms_title` and `llms_description`, if provided, are used as the header in `llms.txt`.
+/// Pages with `draft: true` are left out unless `drafts` is set, and the rest are listed newest first,
+/// by `date` metadata, falling back to the first git commit of their source and then to its modification
+/// time. Templates come from `templates_path`, falling back to the built-in ones (see
+/// [`templates::loader`]), and get the globals listed in [`templates::DEFAULT_TEMPLATES`]. Progress is
+/// logged with `tracing`, with a progress bar on stderr while pages render at the INFO level.
 ///
 /// Returns:
-/// - Ok((entries, md_paths)) where:
-///   - `entries` is a Vec of (title, href, md_rel_path, llm_description) for all processed pages (md_rel_path and llm_description may be None).
-///   - `md_paths` is a Vec of relative paths (strings) of Markdown files that were copied for LLM use.
-/// - Err(...) if an early fatal error occurs (e.g., failing to read syntax data or other IO/parsing errors during initialization).
+/// - Ok([`SiteOutput`]) with every page written, newest first, every output file, the pages or site-wide files
+///   that failed in [`SiteOutput::errors`] (callers decide whether those fail the build), and a
+///   [`report::BuildReport`] of the build.
+/// - Err(...) if an early fatal error occurs (an unknown code theme, failing to write the syntax stylesheets, or two
+///   pages resolving to the same output path).
 ///
 /// # Examples
 ///
 /// ```
 /// use std::path::PathBuf;
-/// use ssg_generator_utils::generate_site;
+/// use ssg_generator_utils::{generate_site, PageOptions, SiteOptions};
 /// // Call with no markdown files; this will initialize and produce empty outputs in the temp dir.
 /// let md_files: Vec<PathBuf> = Vec::new();
-/// let base = std::env::temp_dir();
+/// let base = std::env::temp_dir().join("generate-site-doctest");
+/// std::fs::create_dir_all(&base).unwrap();
 /// let dist = std::env::temp_dir();
 /// let templates = std::env::temp_dir();
 /// let content_index = std::env::temp_dir().join("content-index.html");
 /// std::fs::write(&content_index, "<html>{{ title }}</html>").unwrap();
-/// let res = generate_site(
-///     md_files,
-///     &base,
-///     &dist,
-///     "https://example.com",
-///     "",
-///     &templates,
-///     &content_index,
-///     Some(false),
-///     None,
-///     None,
-///     &Default::default(),
-///     false,
-/// );
+/// let options = SiteOptions {
+///     domain: "https://example.com",
+///     templates_path: Some(&templates),
+///     content_index_path: Some(&content_index),
+///     page: PageOptions { generate_llm_txt_by_default: Some(false), eager_images: Some(1), ..Default::default() },
+///     ..Default::default()
+/// };
+/// let res = generate_site(md_files, &base, &dist, &options);
 /// assert!(res.is_ok());
 /// ```
-pub fn generate_site(
-    md_files: Vec<PathBuf>,
-    base_path: &Path,
-    dist_path: &Path,
-    domain: &str,
-    base_path_str: &str,
-    templates_path: &Path,
-    content_index_path: &Path,
-    generate_llm_txt_by_default: Option<bool>,
-    llms_title: Option<&str>,
-    llms_description: Option<&str>,
-    omit_languages: &HashSet<String>,
-    disable_syntax_highlighting: bool,
-) -> Result<(Vec<(String, String, Option<String>, Option<String>)>, Vec<String>), Box<dyn std::error::Error>> {
-    let ps: SyntaxSet = syntect::dumps::from_binary(include_bytes!(
-        "../syntaxes/syntaxes.packdump"
-    ));
+pub fn generate_site(md_files: Vec<PathBuf>, base_path: &Path, dist_path: &Path, options: &SiteOptions) -> Result<SiteOutput, SsgError> {
+    let SiteOptions {
+        domain,
+        base_path: base_path_str,
+        permalink_pattern,
+        pretty_urls,
+        i18n,
+        templates_path,
+        content_index_path,
+        llms_title,
+        llms_description,
+        llms_full,
+        code_theme,
+        code_theme_light,
+        syntaxes_path,
+        data_path,
+        drafts,
+        feed_config,
+        podcast,
+        robots_config,
+        deploy_config,
+        related_posts,
+        section_config,
+        menus,
+        service_worker,
+        web_manifest,
+        search_index,
+        activitypub,
+        tag_template,
+        static_path,
+        fingerprint_assets,
+        manifest_path,
+        prune,
+        precompress,
+        page_cache_path,
+        sitemap_chunk_size,
+        stylesheet,
+        page: page_options,
+    } = *options;
+    let PageOptions { llms_config, gemini, content_api, highlight_mode, image_config, analytics, strict_templates, .. } = page_options;
+    let templates_path = templates_path.unwrap_or(Path::new("templates"));
+    let code_theme = code_theme.unwrap_or(highlight::DEFAULT_CODE_THEME);
+    let tag_template = tag_template.unwrap_or("tag.html");
+    let start = Instant::now();
+    let progress = if tracing::enabled!(tracing::Level::INFO) {
+        ProgressBar::new(md_files.len() as u64)
+    } else {
+        ProgressBar::hidden()
+    };
+    progress.set_style(ProgressStyle::with_template("{bar:40} {pos}/{len} pages").unwrap());
+    let stats = Stats::with_progress(progress.clone());
+    let ps = highlight::load_syntax_set(syntaxes_path);
 
     let ts = ThemeSet::load_defaults();
-    let theme = &ts.themes["base16-ocean.dark"];
+    let find_theme = |name: &str| {
+        ts.themes
+            .get(name)
+            .ok_or_else(|| SsgError::Syntax(format!("Unknown code theme `{}`", name)))
+    };
+    let theme = find_theme(code_theme)?;
 
     let mut env = Environment::new();
-    env.set_loader(minijinja::path_loader(templates_path));
-    env.add_global("domain", domain);
-    env.add_global("base_path", base_path_str);
-
-    let domain = domain.trim_end_matches('/');
-    let sitemap_urls: Vec<String> = md_files.iter().map(|p| {
-        let rel = p.strip_prefix(base_path).unwrap();
-        let url = rel.with_extension("html").to_string_lossy().replace('\\', "/");
-        format!("{}{}{}", domain, base_path_str, url)
-    }).collect();
+    env.set_loader(templates::loader(templates_path));
+    if strict_templates {
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+    }
+    let urls = UrlResolver::new(domain, base_path_str)
+        .with_permalink(permalink_pattern)
+        .with_pretty_urls(pretty_urls)
+        .with_languages(i18n);
+    env.add_global("domain", urls.domain());
+    env.add_global("base_path", urls.base_path());
+    env.add_global("default_lang", urls.i18n().map_or("en", i18n::I18nConfig::default_language));
+    filters::register_filters(&mut env, &urls);
+    env.add_global("data", Value::from(data_path.map(data::load_data_dir).unwrap_or_default()));
+    let file_menus = match (menus, data_path) {
+        (None, Some(data_path)) => menus::load_menus(data_path).unwrap_or_else(|e| {
+            stats.warn("data", Some(data_path), format_args!("Failed to read the menus of {}: {}", data_path.display(), e));
+            None
+        }),
+        _ => None,
+    };
+    let menus = menus.or(file_menus.as_ref()).map(|menus| menus::resolve_menus(menus, &urls)).unwrap_or_default();
+    env.add_global("menus", Value::from_serialize(&menus));
+    let authors = data_path
+        .and_then(|data_path| {
+            authors::load_authors(data_path).unwrap_or_else(|e| {
+                stats.warn("data", Some(data_path), format_args!("Failed to read the authors of {}: {}", data_path.display(), e));
+                None
+            })
+        })
+        .unwrap_or_default();
+    // Every file written below, relative to `dist_path`; builds with errors are never pruned
+    let mut outputs: BTreeSet<String> = BTreeSet::new();
+    let mut errors: Vec<SsgError> = Vec::new();
+    if service_worker.is_some() {
+        env.add_global("sw_register", sw::register_snippet(&urls));
+    }
+    if let Some(web_manifest) = web_manifest {
+        env.add_global("manifest_tags", webmanifest::head_tags(web_manifest, &urls));
+    }
+    let mut fingerprints = BTreeMap::new();
+    if let Some(static_path) = static_path
+        && fingerprint_assets
+    {
+        match assets::fingerprint_static_dir(static_path) {
+            Ok(fingerprinted) => fingerprints = fingerprinted,
+            Err(e) => errors.push(SsgError::io(static_path)(e)),
+        }
+    }
+    filters::register_asset_function(&mut env, &urls, fingerprints.clone());
+    if highlight_mode == highlight::HighlightMode::Classes {
+        fs::create_dir_all(dist_path).map_err(SsgError::io(dist_path))?;
+        let syntax_css = dist_path.join("syntax.css");
+        fs::write(&syntax_css, highlight::theme_css(theme)).map_err(SsgError::io(&syntax_css))?;
+        outputs.insert("syntax.css".to_string());
+        env.add_global("syntax_css", urls.href("syntax.css"));
+        if let Some(light) = code_theme_light {
+            let syntax_light_css = dist_path.join("syntax-light.css");
+            fs::write(&syntax_light_css, highlight::theme_css(find_theme(light)?)).map_err(SsgError::io(&syntax_light_css))?;
+            outputs.insert("syntax-light.css".to_string());
+            env.add_global("syntax_css_light", urls.href("syntax-light.css"));
+        }
+    } else if code_theme_light.is_some() {
+        stats.warn("config", None, format_args!("A light code theme needs the `classes` highlight mode; using `{}` only", code_theme));
+    }
 
-    let sitemap_refs: Vec<&str> = sitemap_urls.iter().map(|s| s.as_str()).collect();
-    let sitemap_path = dist_path.join("sitemap.xml");
+    let git = stats.time(Phase::Io, || git::GitHistory::load(base_path));
+    let mut pages: Vec<PageSummary> = md_files
+        .par_iter()
+        .filter_map(|file| summarize_page(file, base_path, &urls, &git))
+        .collect();
+    // Drafts are left out as if their sources did not exist
+    let md_files: Vec<PathBuf> = if drafts {
+        md_files
+    } else {
+        let draft_sources: HashSet<PathBuf> = pages.iter().filter(|page| page.draft).map(|page| page.source_path.clone()).collect();
+        pages.retain(|page| !page.draft);
+        md_files.into_iter().filter(|file| !draft_sources.contains(file)).collect()
+    };
+    progress.set_length(md_files.len() as u64);
+    // Pages are written in parallel, so two of them sharing an output would race
+    let mut sources: BTreeMap<&Path, &Path> = BTreeMap::new();
+    for page in &pages {
+        if let Some(first) = sources.insert(&page.output_path, &page.source_path) {
+            return Err(SsgError::DuplicateOutput {
+                path: dist_path.join(&page.output_path),
+                first: first.to_path_buf(),
+                second: page.source_path.clone(),
+            });
+        }
+    }
+    pages.sort_by_key(|page| Reverse(page.date));
+    let mut links = links::PageLinks::default();
+    for page in &pages {
+        links.insert(&page.source_path, page.href.clone());
+    }
+    let site_key = page_cache_path.map(|_| {
+        let render_options = RenderOptions {
+            domain,
+            base_path: base_path_str,
+            permalink_pattern,
+            pretty_urls,
+            i18n,
+            code_theme,
+            code_theme_light,
+            fingerprints: &fingerprints,
+            service_worker,
+            web_manifest,
+            page: page_options,
+        };
+        let options = serde_json::to_string(&render_options).unwrap_or_default();
+        let site = serde_json::to_string(&pages).unwrap_or_default();
+        cache::site_key(&options, templates_path, data_path, syntaxes_path, &site)
+    });
+    let mut site_nav = nav::site_nav(&pages, base_path, related_posts.unwrap_or(nav::DEFAULT_RELATED_POSTS), &urls);
+    for page in &pages {
+        if let Some(page_nav) = site_nav.get_mut(&page.source_path) {
+            page_nav.menus = menus::mark_active(&menus, &page.href);
+        }
+    }
+    let no_nav = nav::PageNav { menus: menus.clone(), ..Default::default() };
+    let previous_cache = page_cache_path.map(cache::PageCache::load).unwrap_or_default();
+    env.add_global("site", context! {
+        domain => urls.domain(),
+        base_path => urls.base_path(),
+        build_time => date_context(&Utc::now().fixed_offset()),
+        pages => pages,
+    });
 
-    let results: Vec<_> = md_files
+    let page_context = PageContext { ps: &ps, theme, env: &env, urls: &urls, links: &links, authors: &authors, git: &git, stats: &stats, options: page_options };
+    let (rendered, page_errors): (Vec<_>, Vec<_>) = md_files
         .par_iter()
-        .filter_map(|file| {
-            process_md_file(
-                file,
-                base_path,
-                dist_path,
-                &ps,
-                theme,
-                &env,
-                generate_llm_txt_by_default,
-                omit_languages,
-                disable_syntax_highlighting,
-                domain,
-                base_path_str,
-            )
+        .progress_with(progress.clone())
+        .map(|file| {
+            let rel_src = file.strip_prefix(base_path).unwrap();
+            let page_nav = site_nav.get(file).unwrap_or(&no_nav);
+            let key = site_key.and_then(|site_key| cache::page_key(site_key, file, base_path, page_nav, git.file(file)));
+            if let Some(key) = key
+                && let Some(page) = previous_cache.get(rel_src, key, dist_path)
+            {
+                tracing::debug!("Reused {}", file.display());
+                return Ok((rel_src, key, page, true));
+            }
+            let page = process_md_file(file, base_path, dist_path, page_nav, &page_context)?;
+            tracing::debug!("Rendered {} to {}", file.display(), page.output_path.display());
+            Ok((rel_src, key.unwrap_or_default(), page, false))
         })
+        .partition_map(|result| match result {
+            Ok(page) => rayon::iter::Either::Left(page),
+            Err(e) => rayon::iter::Either::Right(e),
+        });
+    progress.finish_and_clear();
+    errors.extend(page_errors);
+    let reused_pages = rendered.iter().filter(|(_, _, _, reused)| *reused).count();
+    if let Some(page_cache_path) = page_cache_path {
+        tracing::info!("Reused {} unchanged pages.", reused_pages);
+        let mut page_cache = cache::PageCache::default();
+        for (rel_src, key, page, _) in &rendered {
+            page_cache.insert(rel_src, *key, page.clone());
+        }
+        if let Err(e) = page_cache.save(page_cache_path) {
+            stats.warn("io", Some(page_cache_path), format_args!("Failed to write {}: {}", page_cache_path.display(), e));
+        }
+    }
+    let mut results: Vec<PageOutput> = rendered.into_iter().map(|(_, _, page, _)| page).collect();
+    // Newest first for the content index, llms.txt and everything else built from `results`
+    results.sort_by_key(|page| Reverse(page.meta.date.as_deref().and_then(parse_date)));
+    // The directory the language of a page gets its content index and feed in, empty for the default
+    // language or a site of one, and the source of the page in the tree of its language
+    let language_dir = |page: &PageOutput| match (urls.language(page.source_path.strip_prefix(base_path).unwrap_or(&page.source_path)), urls.i18n()) {
+        (Some(language), Some(config)) => (language.output_dir(config).to_string_lossy().into_owned(), base_path.join(language.key)),
+        _ => (String::new(), page.source_path.clone()),
+    };
+    let mut indexed: BTreeMap<String, Vec<(PathBuf, nav::PageRef)>> = BTreeMap::from([(String::new(), Vec::new())]);
+    for page in results.iter().filter(|page| !page.meta.exclude_from_index.unwrap_or(false)) {
+        let (dir, source) = language_dir(page);
+        let page_ref = nav::PageRef { title: page.title.clone(), href: page.href.clone(), summary: page.summary.clone() };
+        indexed.entry(dir).or_default().push((source, page_ref));
+    }
+    // Sources outside a git checkout or never committed have no entry
+    let git_modified: HashMap<&Path, DateTime<FixedOffset>> = results
+        .par_iter()
+        .filter_map(|page| Some((page.source_path.as_path(), git.file(&page.source_path)?.dates.modified)))
         .collect();
-    let entries: Vec<_> = results.iter().map(|(title, href, _, _, _)| (title.clone(), href.clone())).collect();
-    let md_paths: Vec<String> = results.iter().filter_map(|(_, _, md, _, md_copied)| if *md_copied { md.clone() } else { None }).collect();
+    let mut sitemap_entries: Vec<sitemap::SitemapEntry> = results
+        .par_iter()
+        .filter(|page| !page.meta.noindex.unwrap_or(false) && !page.meta.exclude_from_sitemap.unwrap_or(false))
+        .map(|page| sitemap::SitemapEntry {
+            // The same href every link to the page uses
+            loc: urls.absolute_url(&page.href),
+            lastmod: page
+                .meta
+                .lastmod
+                .as_deref()
+                .and_then(parse_date)
+                .or_else(|| git_modified.get(page.source_path.as_path()).copied())
+                .or_else(|| page.meta.date.as_deref().and_then(parse_date)),
+            changefreq: page.meta.changefreq,
+            priority: page.meta.priority,
+            alternates: page_alternates(page, site_nav.get(&page.source_path), base_path, &urls),
+        })
+        .collect();
+    for page in &results {
+        outputs.insert(permalink(&page.output_path));
+        outputs.extend(page.md_rel_path.clone());
+        outputs.extend(page.txt_rel_path.clone());
+        outputs.extend(page.api_rel_path.clone());
+        outputs.extend(page.card_rel_path.clone());
+    }
 
-    println!("Processed all markdown files.");
-    if let Err(e) = sitemap::write_sitemap(&sitemap_refs, sitemap_path.to_string_lossy().as_ref()) {
-        eprintln!("Failed to write sitemap: {}", e);
+    tracing::info!("Processed all markdown files.");
+
+    let page_paths: HashMap<PathBuf, PathBuf> = results
+        .iter()
+        .filter_map(|page| Some((page.source_path.strip_prefix(base_path).ok()?.to_path_buf(), page.output_path.clone())))
+        .collect();
+    match stats.time(Phase::Io, || assets::copy_content_assets(base_path, dist_path, &page_paths)) {
+        Ok(copied) => {
+            tracing::info!("Copied {} content assets.", copied.len());
+            if let Some(image_config) = image_config {
+                for rel in &copied {
+                    let dir = Path::new(rel).parent().unwrap_or(Path::new(""));
+                    let variants = images::variants(&dist_path.join(rel), image_config).unwrap_or_default();
+                    outputs.extend(variants.iter().map(|v| dir.join(&v.file_name).to_string_lossy().replace('\\', "/")));
+                }
+                let optimized: usize = copied
+                    .par_iter()
+                    .map(|rel| {
+                        let path = dist_path.join(rel);
+                        stats.time(Phase::Io, || images::optimize_image(&path, image_config)).map_or_else(
+                            |e| {
+                                stats.warn("image", Some(&path), format_args!("Failed to optimize {}: {}", path.display(), e));
+                                0
+                            },
+                            |written| written.len(),
+                        )
+                    })
+                    .sum();
+                tracing::info!("Encoded {} optimized image variants.", optimized);
+            }
+            outputs.extend(copied);
+        }
+        Err(e) => errors.push(SsgError::io(base_path)(e)),
     }
-    if let Err(e) = create_index_page(dist_path, &entries, &mut env, content_index_path, base_path_str) {
-        eprintln!("Failed to create index page: {}", e);
-    } else {
-        println!("Index page generated at {}/content-index/index.html", dist_path.display());
+    if let Some(static_path) = static_path {
+        match stats.time(Phase::Io, || assets::copy_static_dir(static_path, dist_path, &fingerprints)) {
+            Ok(copied) => {
+                tracing::info!("Copied {} static files from {}.", copied.len(), static_path.display());
+                outputs.extend(copied);
+            }
+            Err(e) => errors.push(SsgError::io(static_path)(e)),
+        }
+    }
+
+    // Pages rendered from templates alone, which get the analytics snippet once written
+    let mut listings: Vec<String> = Vec::new();
+    for page in &results {
+        for tag in page.meta.tags.iter().flatten().filter(|tag| slugify(tag).is_empty()) {
+            stats.warn("tag", Some(&page.source_path), format_args!("Tag {:?} of {} has no letter or digit to name its page after; skipping it", tag, page.source_path.display()));
+        }
+    }
+    // Every language gets the tag pages of its own pages, in its directory
+    let mut language_tags: BTreeMap<String, Vec<(String, String, Vec<String>)>> = BTreeMap::new();
+    for page in &results {
+        let tags = (page.title.clone(), page.href.clone(), page.meta.tags.clone().unwrap_or_default());
+        language_tags.entry(language_dir(page).0).or_default().push(tags);
+    }
+    let tagged: BTreeMap<String, BTreeMap<String, tags::TagPages>> =
+        language_tags.into_iter().map(|(dir, pages)| (dir, tags::collect_tags(pages))).filter(|(_, tags)| !tags.is_empty()).collect();
+    for (dir, tagged) in &tagged {
+        match stats.time(Phase::Template, || tags::write_tag_pages(dist_path, dir, tagged, &env, tag_template, &urls, feed_config.is_some())) {
+            Ok(written) => {
+                sitemap_entries.extend(written.iter().map(|rel| sitemap::SitemapEntry { loc: urls.url(rel), ..Default::default() }));
+                tracing::info!("Tag pages generated at {}", dist_path.join(dir).join("tags").display());
+                listings.extend(written.iter().cloned());
+                outputs.extend(written);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    let series = series::collect_series(&pages);
+    if !series.is_empty() {
+        match stats.time(Phase::Template, || series::write_series_pages(dist_path, &series, &env)) {
+            Ok(written) => {
+                sitemap_entries.extend(written.iter().map(|rel| sitemap::SitemapEntry { loc: urls.url(rel), ..Default::default() }));
+                tracing::info!("Series pages generated at {}/series/", dist_path.display());
+                listings.extend(written.iter().cloned());
+                outputs.extend(written);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    let by_author = authors::collect_authors(&results);
+    if !by_author.is_empty() {
+        match stats.time(Phase::Template, || authors::write_author_pages(dist_path, &by_author, &authors, &env, &urls)) {
+            Ok(written) => {
+                sitemap_entries.extend(written.iter().map(|rel| sitemap::SitemapEntry { loc: urls.url(rel), ..Default::default() }));
+                tracing::info!("Author pages generated at {}/authors/", dist_path.display());
+                listings.extend(written.iter().cloned());
+                outputs.extend(written);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    match stats.time(Phase::Template, || sections::write_section_pages(dist_path, base_path, &pages, &env, &urls, section_config, &outputs)) {
+        Ok(written) => {
+            sitemap_entries.extend(written.iter().map(|rel| sitemap::SitemapEntry { loc: urls.url(rel), ..Default::default() }));
+            listings.extend(written.iter().cloned());
+            outputs.extend(written);
+        }
+        Err(e) => errors.push(e),
+    }
+
+    // Pages, tag, series, author, section pages and static files keep their paths over any alias
+    let mut redirects: Vec<(String, String)> = Vec::new();
+    for page in &results {
+        for alias in page.meta.aliases.iter().flatten() {
+            let alias_permalink = redirects::alias_permalink(alias);
+            if outputs.contains(&alias_permalink) {
+                stats.warn("redirect", Some(&page.source_path), format_args!("Alias {} of {} is already taken; not redirecting it", alias, page.source_path.display()));
+                continue;
+            }
+            let target = urls.absolute_url(&page.href);
+            let canonical = page.meta.canonical_url(&page.href, &urls);
+            match stats.time(Phase::Io, || redirects::write_redirect(dist_path, &alias_permalink, &target, &canonical)) {
+                Ok(()) => {
+                    redirects.push((urls.href(&alias_permalink), page.href.clone()));
+                    outputs.insert(alias_permalink);
+                }
+                Err(e) => errors.push(SsgError::io(dist_path.join(&alias_permalink))(e)),
+            }
+        }
+    }
+    // `_redirects` and `_headers` from the static directory win
+    // The Fediverse only accepts the ActivityPub documents with their own content types
+    let deploy_config = deploy_config.map(|deploy_config| {
+        let mut deploy_config = deploy_config.clone();
+        for (path, name, value) in activitypub.map_or(&[][..], |_| activitypub::HEADERS) {
+            let headers = deploy_config.headers.entry(path.to_string()).or_default();
+            headers.entry(name.to_string()).or_insert_with(|| value.to_string());
+        }
+        deploy_config
+    });
+    if let Some(deploy_config) = &deploy_config {
+        if deploy_config.redirects && !outputs.contains("_redirects") {
+            match stats.time(Phase::Io, || deploy::write_redirects(dist_path, &redirects)) {
+                Ok(()) => {
+                    outputs.insert("_redirects".to_string());
+                }
+                Err(e) => errors.push(SsgError::io(dist_path.join("_redirects"))(e)),
+            }
+        }
+        if (deploy_config.security_headers || !deploy_config.headers.is_empty()) && !outputs.contains("_headers") {
+            match stats.time(Phase::Io, || deploy::write_headers(dist_path, deploy_config, &urls)) {
+                Ok(()) => {
+                    outputs.insert("_headers".to_string());
+                }
+                Err(e) => errors.push(SsgError::io(dist_path.join("_headers"))(e)),
+            }
+        }
+    }
+
+    let sitemap = match stats.time(Phase::Io, || sitemap::write_sitemaps(&sitemap_entries, dist_path, &urls, sitemap_chunk_size)) {
+        Ok(written) => {
+            let sitemap = written.last().cloned().unwrap_or_default();
+            outputs.extend(written);
+            Some(sitemap)
+        }
+        Err(source) => {
+            errors.push(SsgError::Sitemap { path: dist_path.join("sitemap.xml"), source });
+            None
+        }
+    };
+    // A robots.txt from the static directory wins
+    if let Some(sitemap) = sitemap
+        && !outputs.contains("robots.txt")
+    {
+        let robots_path = dist_path.join("robots.txt");
+        match stats.time(Phase::Io, || robots::write_robots(dist_path, robots_config, &urls, &sitemap)) {
+            Ok(()) => {
+                outputs.insert("robots.txt".to_string());
+            }
+            Err(e) => errors.push(SsgError::io(robots_path)(e)),
+        }
+    }
+    for (dir, pages) in &indexed {
+        let entries: Vec<_> = pages.iter().map(|(_, r)| (r.title.clone(), r.href.clone(), r.summary.clone())).collect();
+        let tree = sections::content_tree(pages.iter().map(|(source, r)| (source.as_path(), r)), base_path);
+        let index_dist = dist_path.join(dir);
+        if let Err(e) = stats.time(Phase::Template, || create_index_page(&index_dist, &entries, &tree, &mut env, content_index_path)) {
+            errors.push(e);
+        } else {
+            tracing::info!("Index page generated at {}/content-index/index.html", index_dist.display());
+            let rel = Path::new(dir).join("content-index/index.html").to_string_lossy().replace('\\', "/");
+            outputs.insert(rel.clone());
+            listings.push(rel);
+        }
+    }
+    if let Some(analytics) = analytics {
+        let snippet = analytics.snippet(&urls);
+        for rel in &listings {
+            let path = dist_path.join(rel);
+            let injected = stats.time(Phase::Io, || -> std::io::Result<()> {
+                let html = fs::read_to_string(&path)?;
+                fs::write(&path, analytics::inject_analytics(&html, &snippet, analytics.script_url()))
+            });
+            if let Err(e) = injected {
+                errors.push(SsgError::io(&path)(e));
+            }
+        }
     }
 
     use std::fmt::Write as _;
@@ -565,29 +2336,323 @@ pub fn generate_site(
     if !llms_description.trim().is_empty() {
         writeln!(llms_tx, "{}\n", llms_description.trim()).ok();
     }
-    writeln!(llms_tx, "## Contents\n").ok();
-    for (title, _href, md, llm_description, md_copied) in &results {
-
-        if !md_copied { continue; }
-        // Remove any leading "/my-blog" or similar base path from href before joining with domain
- 
-        if let Some(md_path) = md {
+    let mut llms_sections: BTreeMap<String, Vec<(&PageOutput, &String)>> = BTreeMap::new();
+    for page in &results {
+        if let Some(md_path) = &page.md_rel_path {
+            let section = llms::page_section(&page.source_path, base_path, page.meta.llm_section.as_deref());
+            llms_sections.entry(section).or_default().push((page, md_path));
+        }
+    }
+    let section_order = llms_config.map(|config| config.section_order.as_slice()).unwrap_or_default();
+    for section in llms::order_sections(llms_sections.keys().map(String::as_str), section_order) {
+        writeln!(llms_tx, "## {}\n", section).ok();
+        for (page, md_path) in &llms_sections[section] {
             writeln!(llms_tx, "- [{}]({}){}",
-                title,
-                format!("{}{}{}", domain, base_path_str, md_path),
-                match llm_description {
+                page.title,
+                urls.url(md_path),
+                match &page.meta.llm_description {
                     Some(desc) if !desc.trim().is_empty() => format!(": {}", desc.trim()),
                     _ => String::new(),
                 }
             ).ok();
         }
+        writeln!(llms_tx).ok();
     }
     let llms_tx_path = dist_path.join("llms.txt");
-    if let Err(e) = std::fs::write(&llms_tx_path, llms_tx) {
-        eprintln!("Failed to write llms.tx: {}", e);
+    if let Err(e) = stats.time(Phase::Io, || std::fs::write(&llms_tx_path, llms_tx)) {
+        errors.push(SsgError::io(&llms_tx_path)(e));
     } else {
-        println!("llms.tx generated at {}", llms_tx_path.display());
+        tracing::info!("llms.tx generated at {}", llms_tx_path.display());
+        outputs.insert("llms.txt".to_string());
     }
-    // Remove md_copied from results in return value for compatibility
-    Ok((results.into_iter().map(|(a,b,c,d,_e)| (a,b,c,d)).collect(), md_paths))
+    if llms_full {
+        let llms_full_path = dist_path.join("llms-full.txt");
+        let written = stats.time(Phase::Io, || -> std::io::Result<usize> {
+            let mut llms_full = format!("# {}\n\n", llms_title);
+            if !llms_description.trim().is_empty() {
+                llms_full.push_str(&format!("{}\n\n", llms_description.trim()));
+            }
+            let mut budget = llms_config.and_then(|config| config.max_total_tokens).unwrap_or(usize::MAX);
+            let mut shortened = 0;
+            for page in &results {
+                if let Some(md_path) = &page.md_rel_path {
+                    let md = fs::read_to_string(dist_path.join(md_path))?;
+                    // The source header stands in for the front matter of the copy
+                    let mut body = split_front_matter(&md).1.trim();
+                    let mut tokens = llms::estimate_tokens(body);
+                    if tokens > budget {
+                        // Past the budget, a page is only described, if it can be at all
+                        shortened += 1;
+                        body = page.meta.llm_description.as_deref().unwrap_or("").trim();
+                        tokens = llms::estimate_tokens(body);
+                        if body.is_empty() || tokens > budget {
+                            continue;
+                        }
+                    }
+                    budget -= tokens;
+                    llms_full.push_str(&format!("# source: {}\n\n{}\n\n", urls.url(md_path), body));
+                }
+            }
+            fs::write(&llms_full_path, llms_full).map(|()| shortened)
+        });
+        match written {
+            Ok(shortened) => {
+                if shortened > 0 {
+                    stats.warn("llms", Some(&llms_full_path), format_args!(
+                        "{} pages over the token budget of {} were described or left out",
+                        shortened,
+                        llms_full_path.display()
+                    ));
+                }
+                tracing::info!("llms-full.txt generated at {}", llms_full_path.display());
+                outputs.insert("llms-full.txt".to_string());
+            }
+            Err(e) => errors.push(SsgError::io(&llms_full_path)(e)),
+        }
+    }
+
+    if feed_config.is_some() || podcast.is_some() {
+        let site_link = urls.url("");
+        let items: Vec<(&PageOutput, feed::FeedItem)> = results
+            .iter()
+            .filter(|page| !page.meta.exclude_from_index.unwrap_or(false))
+            .map(|page| {
+                let pub_date = page.meta.date.as_deref().and_then(parse_date);
+                // Local audio files are measured in their copy under `dist_path`; remote ones are not fetched,
+                // so their size comes from the `length` of the audio block
+                let enclosure = page.meta.audio.as_ref().map(|audio| {
+                    let rel = feed::audio_rel_path(&audio.file, &page.output_path, urls.base_path());
+                    let measured = rel.as_ref().and_then(|rel| {
+                        let path = dist_path.join(rel);
+                        fs::metadata(&path)
+                            .inspect_err(|e| {
+                                stats.warn("feed", Some(&page.source_path), format_args!("Failed to measure the audio {} of {}: {}", path.display(), page.source_path.display(), e));
+                            })
+                            .ok()
+                            .map(|metadata| metadata.len())
+                    });
+                    let length = measured.or(audio.length).unwrap_or_else(|| {
+                        stats.warn("feed", Some(&page.source_path), format_args!("Unknown length of the audio {} of {}, set `length` in its audio block", audio.file, page.source_path.display()));
+                        0
+                    });
+                    feed::Enclosure {
+                        url: rel.map_or_else(|| audio.file.clone(), |rel| urls.url(&rel)),
+                        length,
+                        mime: audio.mime.clone().unwrap_or_else(|| feed::audio_mime(&audio.file).to_string()),
+                        duration: audio.duration.clone(),
+                    }
+                });
+                let item = feed::FeedItem {
+                    title: page.title.clone(),
+                    link: urls.url(&permalink(&page.output_path)),
+                    pub_date,
+                    authors: page.meta.author_names(),
+                    updated: git_modified.get(page.source_path.as_path()).copied().filter(|&modified| Some(modified) > pub_date),
+                    description: page.meta.description.clone(),
+                    enclosure,
+                };
+                (page, item)
+            })
+            .collect();
+        let all_items: Vec<feed::FeedItem> = items.iter().map(|(_, item)| item.clone()).collect();
+        if let Some(feed_config) = feed_config {
+            let mut language_items: BTreeMap<String, Vec<&(&PageOutput, feed::FeedItem)>> = BTreeMap::from([(String::new(), Vec::new())]);
+            for pair in &items {
+                language_items.entry(language_dir(pair.0).0).or_default().push(pair);
+            }
+            for (dir, language_pairs) in &language_items {
+                let language_items: Vec<feed::FeedItem> = language_pairs.iter().map(|(_, item)| item.clone()).collect();
+                let rel = Path::new(dir).join("feed.xml").to_string_lossy().replace('\\', "/");
+                let feed_path = dist_path.join(&rel);
+                let language_config = match (dir.as_str(), urls.i18n()) {
+                    ("", None) => feed_config.clone(),
+                    ("", Some(config)) => feed::FeedConfig {
+                        language: feed_config.language.clone().or_else(|| Some(config.default_language().to_string())),
+                        ..feed_config.clone()
+                    },
+                    (lang, _) => feed::FeedConfig {
+                        title: Some(format!("{} ({})", feed_config.title.as_deref().unwrap_or("Feed"), lang)),
+                        language: Some(lang.to_string()),
+                        ..feed_config.clone()
+                    },
+                };
+                let language_link = if dir.is_empty() { site_link.clone() } else { urls.url(&format!("{}/", dir)) };
+                let written = stats.time(Phase::Io, || {
+                    fs::create_dir_all(feed_path.parent().unwrap())?;
+                    feed::write_feed(&language_config, &language_link, &urls.url(&rel), &language_items, feed_path.to_string_lossy().as_ref())
+                });
+                if let Err(e) = written {
+                    errors.push(SsgError::io(&feed_path)(e));
+                } else {
+                    tracing::info!("Feed generated at {}", feed_path.display());
+                    outputs.insert(rel);
+                }
+                for (slug, tags::TagPages { name: tag, .. }) in tagged.get(dir).into_iter().flatten() {
+                    let tag_items: Vec<feed::FeedItem> = language_pairs
+                        .iter()
+                        .filter(|(page, _)| page.meta.tags.iter().flatten().any(|page_tag| slugify(page_tag) == *slug))
+                        .map(|(_, item)| item.clone())
+                        .collect();
+                    let title = format!("{}: {}", language_config.title.as_deref().unwrap_or("Feed"), tag);
+                    let tag_config = feed::FeedConfig { title: Some(title), ..language_config.clone() };
+                    let rel = tags::tag_feed_permalink(dir, tag);
+                    let tag_feed_path = dist_path.join(&rel);
+                    let written = stats.time(Phase::Io, || {
+                        fs::create_dir_all(tag_feed_path.parent().unwrap())?;
+                        feed::write_feed(&tag_config, &urls.url(rel.trim_end_matches("feed.xml")), &urls.url(&rel), &tag_items, tag_feed_path.to_string_lossy().as_ref())
+                    });
+                    match written {
+                        Ok(()) => {
+                            outputs.insert(rel);
+                        }
+                        Err(e) => errors.push(SsgError::io(&tag_feed_path)(e)),
+                    }
+                }
+            }
+        }
+        if let Some(podcast) = podcast {
+            let podcast_path = dist_path.join(feed::PODCAST_PERMALINK);
+            let image_url = podcast.image.as_deref().map(|image| urls.absolute_url(image));
+            let written = stats.time(Phase::Io, || {
+                feed::write_podcast(
+                    podcast,
+                    feed_config.unwrap_or(&Default::default()),
+                    image_url.as_deref(),
+                    &site_link,
+                    &urls.url(feed::PODCAST_PERMALINK),
+                    &all_items,
+                    podcast_path.to_string_lossy().as_ref(),
+                )
+            });
+            if let Err(e) = written {
+                errors.push(SsgError::io(&podcast_path)(e));
+            } else {
+                tracing::info!("Podcast feed generated at {}", podcast_path.display());
+                outputs.insert(feed::PODCAST_PERMALINK.to_string());
+            }
+        }
+    }
+
+    if search_index {
+        match stats.time(Phase::Io, || search::write_search_index(dist_path, &results)) {
+            Ok(written) => {
+                tracing::info!("Search index written to {}.", dist_path.join(search::SEARCH_DIR).display());
+                outputs.extend(written);
+            }
+            Err(e) => errors.push(SsgError::io(dist_path.join(search::SEARCH_DIR))(e)),
+        }
+    }
+
+    if let Some(activitypub) = activitypub {
+        match stats.time(Phase::Io, || activitypub::write_activitypub(dist_path, activitypub, &results, &urls)) {
+            Ok(written) => {
+                tracing::info!("ActivityPub actor written to {}.", dist_path.join(activitypub::AP_DIR).display());
+                outputs.extend(written);
+            }
+            Err(e) => errors.push(SsgError::io(dist_path.join(activitypub::AP_DIR))(e)),
+        }
+    }
+
+    if content_api {
+        match stats.time(Phase::Io, || api::write_index(dist_path, &results, &urls)) {
+            Ok(rel) => {
+                tracing::info!("Content API index written to {}.", dist_path.join(&rel).display());
+                outputs.insert(rel);
+            }
+            Err(e) => errors.push(SsgError::io(dist_path.join(api::API_DIR).join("index.json"))(e)),
+        }
+    }
+
+    if let Some(gemini) = gemini {
+        let title = gemini.title.as_deref().unwrap_or(llms_title);
+        match stats.time(Phase::Io, || gemini::write_index(&gemini.dist_path(dist_path), title, &results)) {
+            Ok(index) => tracing::info!("Gemini capsule index written to {}.", index.display()),
+            Err(e) => errors.push(SsgError::io(gemini.dist_path(dist_path).join("index.gmi"))(e)),
+        }
+    }
+
+    if let Some(web_manifest) = web_manifest {
+        match stats.time(Phase::Io, || webmanifest::write_web_manifest(dist_path, web_manifest, &urls)) {
+            Ok(written) => outputs.extend(written),
+            Err(e) => stats.warn(
+                "image",
+                Some(&web_manifest.logo),
+                format_args!("Failed to render the icons of {}: {}", web_manifest.logo.display(), e),
+            ),
+        }
+    }
+
+    if let Some(stylesheet) = stylesheet {
+        match stats.time(Phase::Io, stylesheet) {
+            Ok(rel) => {
+                tracing::info!("Stylesheet compiled to {}.", dist_path.join(&rel).display());
+                outputs.insert(rel);
+            }
+            Err(e) => errors.push(SsgError::io(dist_path)(e)),
+        }
+    }
+
+    if let Some(service_worker) = service_worker {
+        match stats.time(Phase::Io, || sw::write_service_worker(dist_path, &outputs, service_worker, &urls)) {
+            Ok(precached) => {
+                tracing::info!("Service worker written with {} precached files.", precached.len());
+                outputs.insert(sw::SW_PERMALINK.to_string());
+            }
+            Err(e) => errors.push(SsgError::io(dist_path.join(sw::SW_PERMALINK))(e)),
+        }
+    }
+
+    if precompress {
+        match stats.time(Phase::Io, || compress::precompress(dist_path, &outputs)) {
+            Ok(written) => {
+                tracing::info!("Wrote {} precompressed files.", written.len());
+                outputs.extend(written);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if let Some(manifest_path) = manifest_path {
+        if !errors.is_empty() {
+            tracing::warn!(category = "io", path = %manifest_path.display(), "Some outputs failed; leaving {} and stale files as they are", manifest_path.display());
+        } else {
+            match prune::record_outputs(dist_path, manifest_path, &outputs, prune) {
+                Ok(removed) if prune => tracing::info!("Pruned {} stale files from {}.", removed.len(), dist_path.display()),
+                Ok(_) => {}
+                Err(e) => stats.warn("io", Some(manifest_path), format_args!("Failed to update {}: {}", manifest_path.display(), e)),
+            }
+            // The capsule lives in a directory of its own, recorded in a manifest of its own
+            if let Some(gemini) = gemini {
+                let capsule_path = gemini.dist_path(dist_path);
+                let capsule_manifest = manifest_path.with_extension("gemini.txt");
+                let capsule_outputs: BTreeSet<String> = results
+                    .iter()
+                    .filter_map(|page| page.gmi_path.as_deref()?.strip_prefix(&capsule_path).ok().map(permalink))
+                    .chain(["index.gmi".to_string()])
+                    .collect();
+                match prune::record_outputs(&capsule_path, &capsule_manifest, &capsule_outputs, prune) {
+                    Ok(removed) if prune => tracing::info!("Pruned {} stale files from {}.", removed.len(), capsule_path.display()),
+                    Ok(_) => {}
+                    Err(e) => stats.warn("io", Some(&capsule_manifest), format_args!("Failed to update {}: {}", capsule_manifest.display(), e)),
+                }
+            }
+        }
+    }
+
+    let report = report::BuildReport {
+        pages_rendered: results.len() - reused_pages,
+        pages_skipped: reused_pages,
+        warnings: stats.warnings(),
+        errors: errors.len(),
+        total: start.elapsed(),
+        parse: stats.get(Phase::Parse),
+        highlight: stats.get(Phase::Highlight),
+        template: stats.get(Phase::Template),
+        io: stats.get(Phase::Io),
+        llm_tokens: results
+            .iter()
+            .filter_map(|page| Some((page.md_rel_path.clone()?, page.llm_tokens?)))
+            .collect(),
+    };
+    Ok(SiteOutput { pages: results, reused_pages, outputs, errors, report })
 }
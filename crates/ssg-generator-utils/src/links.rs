@@ -0,0 +1,63 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// Hrefs of the pages of the site by Markdown source, used to rewrite links between pages.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::links::PageLinks;
+/// let mut links = PageLinks::default();
+/// links.insert(Path::new("pages/other-post/index.md"), "/blog/other/".to_string());
+/// let page_dir = Path::new("pages/this-post");
+/// assert_eq!(links.resolve(page_dir, "../other-post/index.md#setup"), Some(Some("/blog/other/#setup".to_string())));
+/// assert_eq!(links.resolve(page_dir, "../missing.md"), Some(None));
+/// assert_eq!(links.resolve(page_dir, "https://example.com/readme.md"), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PageLinks {
+    hrefs: HashMap<PathBuf, String>,
+}
+
+impl PageLinks {
+    /// Record `href` as the URL of the page whose source is `source`.
+    pub fn insert(&mut self, source: &Path, href: String) {
+        self.hrefs.insert(normalize(source), href);
+    }
+
+    /// Resolve `dest`, a link found in a page whose source lives in `page_dir`.
+    ///
+    /// Returns `None` when `dest` is not a relative link to a `.md` file, `Some(None)` when it is
+    /// but no page has that source, and otherwise the href of the page followed by the query
+    /// and fragment of `dest`.
+    pub fn resolve(&self, page_dir: &Path, dest: &str) -> Option<Option<String>> {
+        if dest.contains("://") || dest.starts_with('/') || dest.starts_with("mailto:") {
+            return None;
+        }
+        let split = dest.find(['?', '#']).unwrap_or(dest.len());
+        let (path, suffix) = dest.split_at(split);
+        if !path.ends_with(".md") {
+            return None;
+        }
+        let href = self.hrefs.get(&normalize(&page_dir.join(path)));
+        Some(href.map(|href| format!("{}{}", href, suffix)))
+    }
+}
+
+/// `path` with `.` and `..` components resolved without touching the filesystem.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
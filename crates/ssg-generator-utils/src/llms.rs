@@ -0,0 +1,225 @@
+use std::{borrow::Cow, ops::Range, path::Path};
+use pulldown_cmark::{Event, LinkType, Options, Parser as MdParser, Tag};
+use serde::{Deserialize, Serialize};
+
+use crate::folder_name_to_title;
+
+/// Bytes counted as one token by [`estimate_tokens`].
+const BYTES_PER_TOKEN: usize = 4;
+
+/// `llms.txt` section of the pages at the root of the content directory without an `llm_section`.
+pub const DEFAULT_SECTION: &str = "Contents";
+
+/// `llms.txt` section the llms.txt convention lets readers skip when short of context; always last.
+pub const OPTIONAL_SECTION: &str = "Optional";
+
+/// Size limits of the Markdown handed to LLMs and layout of `llms.txt`, read from the `llms` block of
+/// `cats-ssg.json`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct LlmsConfig {
+    /// Approximate tokens allowed in the Markdown copy of a page (see [`fit_to_budget`])
+    pub max_page_tokens: Option<usize>,
+    /// Approximate tokens allowed in `llms-full.txt`; pages past it are only given by their
+    /// `llm_description`, or left out without one
+    pub max_total_tokens: Option<usize>,
+    /// Sections of `llms.txt` listed first, in this order; the rest follow alphabetically (see
+    /// [`order_sections`])
+    #[serde(default)]
+    pub section_order: Vec<String>,
+}
+
+/// `llms.txt` section of the page at `src_path`: its `llm_section` metadata, or else the title of its
+/// top-level directory under `base_path` (see [`folder_name_to_title`]), or [`DEFAULT_SECTION`] for
+/// pages at the root.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use ssg_generator_utils::llms::page_section;
+/// let base = Path::new("pages");
+/// assert_eq!(page_section(Path::new("pages/tech/rust/async.md"), base, None), "Tech");
+/// assert_eq!(page_section(Path::new("pages/about.md"), base, None), "Contents");
+/// assert_eq!(page_section(Path::new("pages/tech/old.md"), base, Some("Optional")), "Optional");
+/// ```
+pub fn page_section(src_path: &Path, base_path: &Path, llm_section: Option<&str>) -> String {
+    if let Some(section) = llm_section.map(str::trim).filter(|section| !section.is_empty()) {
+        return section.to_string();
+    }
+    let mut components = src_path.strip_prefix(base_path).unwrap_or(src_path).components();
+    match (components.next(), components.next()) {
+        (Some(dir), Some(_)) => folder_name_to_title(Path::new(dir.as_os_str())),
+        _ => DEFAULT_SECTION.to_string(),
+    }
+}
+
+/// `sections` in the order `llms.txt` lists them: those of `order` first, in that order, then
+/// [`DEFAULT_SECTION`], then the rest alphabetically, and [`OPTIONAL_SECTION`] last whatever `order`
+/// says.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::llms::order_sections;
+/// let sections = ["Optional", "Tech", "Contents", "Art", "Life"];
+/// assert_eq!(order_sections(sections, &["Life".to_string()]), vec!["Life", "Contents", "Art", "Tech", "Optional"]);
+/// ```
+pub fn order_sections<'a>(sections: impl IntoIterator<Item = &'a str>, order: &[String]) -> Vec<&'a str> {
+    let mut sections: Vec<&str> = sections.into_iter().collect();
+    sections.sort_by_key(|&section| {
+        (
+            section == OPTIONAL_SECTION,
+            order.iter().position(|name| name == section).unwrap_or(order.len()),
+            section != DEFAULT_SECTION,
+            section,
+        )
+    });
+    sections.dedup();
+    sections
+}
+
+/// `md` with its HTML comments removed and the targets of its links and images, including link
+/// reference definitions, replaced by what `resolve` returns for them (when it returns anything).
+///
+/// Comments and links inside code are left alone.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::llms::normalize_markdown;
+/// let md = "<!-- draft -->\nSee [the post](other.md#top) and ![a cat](cat.png).\n\n[ref]: ../about.md\n\n`<!-- code -->`\n";
+/// let resolve = |dest: &str| Some(format!("https://example.com/{}", dest.trim_start_matches("../")));
+/// assert_eq!(
+///     normalize_markdown(md, resolve),
+///     "See [the post](https://example.com/other.md#top) and ![a cat](https://example.com/cat.png).\n\n[ref]: https://example.com/about.md\n\n`<!-- code -->`\n"
+/// );
+/// ```
+pub fn normalize_markdown(md: &str, resolve: impl Fn(&str) -> Option<String>) -> String {
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let resolve_in = |span: Range<usize>, dest: &str, edits: &mut Vec<_>| {
+        // The target is written last in the span, but may be wrapped in `<>` or followed by a title
+        if let Some(start) = md[span.clone()].rfind(dest).map(|i| span.start + i)
+            && let Some(resolved) = resolve(dest)
+        {
+            edits.push((start..start + dest.len(), resolved));
+        }
+    };
+    let parser = MdParser::new_ext(md, Options::all());
+    for (_, def) in parser.reference_definitions().iter() {
+        resolve_in(def.span.clone(), &def.dest, &mut edits);
+    }
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link { link_type: LinkType::Inline, dest_url, .. } | Tag::Image { link_type: LinkType::Inline, dest_url, .. }) => {
+                // Past the text, which may hold links of its own
+                let text_end = md[range.clone()].rfind("](").map_or(range.start, |i| range.start + i);
+                resolve_in(text_end..range.end, &dest_url, &mut edits);
+            }
+            Event::Start(Tag::HtmlBlock) if is_comment(&md[range.clone()]) => {
+                // With the line break ending it, so no blank line is left behind
+                let end = md[range.end..].strip_prefix('\n').map_or(range.end, |_| range.end + 1);
+                edits.push((range.start..end, String::new()));
+            }
+            Event::InlineHtml(html) if is_comment(&html) => edits.push((range, String::new())),
+            _ => {}
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut out = String::with_capacity(md.len());
+    let mut last = 0;
+    for (range, replacement) in edits {
+        // An edit inside a removed comment has nothing left to apply to
+        if range.start < last {
+            continue;
+        }
+        out.push_str(&md[last..range.start]);
+        out.push_str(&replacement);
+        last = range.end;
+    }
+    out.push_str(&md[last..]);
+    out
+}
+
+fn is_comment(html: &str) -> bool {
+    let html = html.trim();
+    html.starts_with("<!--") && html.ends_with("-->")
+}
+
+/// YAML front matter block making the Markdown copy of a page self-describing: its `title`, canonical
+/// `url` and `date`, when it has one.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::llms::front_matter;
+/// assert_eq!(
+///     front_matter("Cats: a study", "https://example.com/cats/", Some("2024-05-01")),
+///     "---\ntitle: 'Cats: a study'\nurl: https://example.com/cats/\ndate: 2024-05-01\n---\n\n"
+/// );
+/// ```
+pub fn front_matter(title: &str, url: &str, date: Option<&str>) -> String {
+    #[derive(Serialize)]
+    struct FrontMatter<'a> {
+        title: &'a str,
+        url: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        date: Option<&'a str>,
+    }
+
+    let yaml = serde_yaml::to_string(&FrontMatter { title, url, date }).unwrap_or_default();
+    format!("---\n{}---\n\n", yaml)
+}
+
+/// Rough count of the tokens of `text` for LLM tokenizers: one per 4 bytes.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::llms::estimate_tokens;
+/// assert_eq!(estimate_tokens("Hello, cats!"), 3);
+/// assert_eq!(estimate_tokens(""), 0);
+/// ```
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(BYTES_PER_TOKEN)
+}
+
+/// `md` cut down to about `max_tokens` (see [`estimate_tokens`]).
+///
+/// Markdown over the budget is cut before the last heading that keeps it under; when even its first
+/// section is over, it is replaced by `fallback` (the `llm_description` of the page) when there is
+/// one, or else cut at the last whitespace within the budget.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::llms::fit_to_budget;
+/// let md = "# Cats\n\nCats nap.\n\n## Sleep\n\nA lot, all day long.\n";
+/// assert_eq!(fit_to_budget(md, 100, None), md);
+/// assert_eq!(fit_to_budget(md, 6, None), "# Cats\n\nCats nap.\n");
+/// assert_eq!(fit_to_budget(md, 2, Some("About cats")), "About cats");
+/// assert_eq!(fit_to_budget(md, 2, None), "# Cats");
+/// ```
+pub fn fit_to_budget<'a>(md: &'a str, max_tokens: usize, fallback: Option<&'a str>) -> Cow<'a, str> {
+    if estimate_tokens(md) <= max_tokens {
+        return Cow::Borrowed(md);
+    }
+    let max_bytes = max_tokens * BYTES_PER_TOKEN;
+    let heading = MdParser::new_ext(md, Options::all())
+        .into_offset_iter()
+        .filter_map(|(event, range)| matches!(event, Event::Start(Tag::Heading { .. })).then_some(range.start))
+        .filter(|&start| start > 0 && md[..start].trim_end().len() < max_bytes)
+        .last();
+    if let Some(start) = heading {
+        return Cow::Owned(format!("{}\n", md[..start].trim_end()));
+    }
+    if let Some(fallback) = fallback.filter(|fallback| !fallback.trim().is_empty()) {
+        return Cow::Borrowed(fallback.trim());
+    }
+    let mut end = max_bytes.min(md.len());
+    while !md.is_char_boundary(end) {
+        end -= 1;
+    }
+    let cut = md[..end].rfind(char::is_whitespace).unwrap_or(end);
+    Cow::Borrowed(md[..cut].trim_end())
+}
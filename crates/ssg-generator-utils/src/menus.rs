@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::urls::UrlResolver;
+
+/// Named menus, such as `main` and `footer`, read from the `menus` block of `cats-ssg.json` or
+/// else from `menus.yml` in the data directory, and handed to templates as `menus`.
+pub type Menus = BTreeMap<String, Vec<MenuItem>>;
+
+/// An entry of a menu.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct MenuItem {
+    pub name: String,
+    /// A site path, with or without the base path, or an absolute URL; a site-absolute href once
+    /// resolved (see [`resolve_menus`])
+    pub url: String,
+    /// Items are listed from the lowest weight to the highest, then by name
+    #[serde(default)]
+    pub weight: i32,
+    /// Nested items, ordered by the same rules
+    #[serde(default)]
+    pub children: Vec<MenuItem>,
+    /// Set for URLs with a scheme, such as `https:` or `mailto:`
+    #[serde(default, skip_deserializing)]
+    pub external: bool,
+    /// Set on the item linking to the page being rendered and on every item above it (see
+    /// [`mark_active`])
+    #[serde(default, skip_deserializing)]
+    pub active: bool,
+}
+
+/// Read `menus.yml` (or `menus.yaml`) from `data_path`; `Ok(None)` when there is no such file.
+pub fn load_menus(data_path: &Path) -> Result<Option<Menus>, String> {
+    for name in ["menus.yml", "menus.yaml"] {
+        let path = data_path.join(name);
+        if path.is_file() {
+            let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            return serde_yaml::from_str(&content).map(Some).map_err(|e| e.to_string());
+        }
+    }
+    Ok(None)
+}
+
+/// Sort every menu of `menus` by weight and turn its URLs into hrefs of the site, leaving external
+/// URLs unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{menus::{resolve_menus, MenuItem, Menus}, urls::UrlResolver};
+/// let item = |name: &str, url: &str, weight: i32| MenuItem { name: name.into(), url: url.into(), weight, ..Default::default() };
+/// let menus = Menus::from([("main".to_string(), vec![item("Blog", "/blog/", 2), item("About", "about/", 1), item("GitHub", "https://github.com", 3)])]);
+/// let menus = resolve_menus(&menus, &UrlResolver::new("https://example.com", "/site/"));
+/// let hrefs: Vec<_> = menus["main"].iter().map(|item| item.url.as_str()).collect();
+/// assert_eq!(hrefs, vec!["/site/about/", "/site/blog/", "https://github.com"]);
+/// assert!(menus["main"][2].external);
+/// ```
+pub fn resolve_menus(menus: &Menus, urls: &UrlResolver) -> Menus {
+    fn resolve(items: &[MenuItem], urls: &UrlResolver) -> Vec<MenuItem> {
+        let mut items: Vec<_> = items
+            .iter()
+            .map(|item| {
+                let external = item.url.contains(':');
+                let url = if external {
+                    item.url.clone()
+                } else {
+                    // Through the full URL so a path already carrying the base path keeps it once
+                    urls.absolute_url(&item.url).trim_start_matches(urls.domain()).to_string()
+                };
+                MenuItem { url, external, children: resolve(&item.children, urls), ..item.clone() }
+            })
+            .collect();
+        items.sort_by(|a, b| (a.weight, &a.name).cmp(&(b.weight, &b.name)));
+        items
+    }
+
+    menus.iter().map(|(name, items)| (name.clone(), resolve(items, urls))).collect()
+}
+
+/// Copy of resolved `menus` with the items leading to the page at `href` marked `active`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::menus::{mark_active, MenuItem, Menus};
+/// let item = |name: &str, url: &str, children: Vec<MenuItem>| MenuItem { name: name.into(), url: url.into(), children, ..Default::default() };
+/// let menus = Menus::from([("main".to_string(), vec![item("Blog", "/blog/", vec![item("Rust", "/blog/rust/", vec![])]), item("About", "/about/", vec![])])]);
+/// let menus = mark_active(&menus, "/blog/rust/");
+/// assert!(menus["main"][0].active && menus["main"][0].children[0].active);
+/// assert!(!menus["main"][1].active);
+/// ```
+pub fn mark_active(menus: &Menus, href: &str) -> Menus {
+    fn mark(items: &[MenuItem], href: &str) -> Vec<MenuItem> {
+        items
+            .iter()
+            .map(|item| {
+                let children = mark(&item.children, href);
+                let active = item.url == href || children.iter().any(|child| child.active);
+                MenuItem { children, active, ..item.clone() }
+            })
+            .collect()
+    }
+
+    menus.iter().map(|(name, items)| (name.clone(), mark(items, href))).collect()
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::{escape_html, urls::UrlResolver};
+
+/// Author of the site for IndieWeb readers and webmention receivers, read from the `microformats`
+/// block of `cats-ssg.json`; pages only get microformats markup when this block is present.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct MicroformatsConfig {
+    /// Name in the `h-card` of pages without an `author` of their own (default: the `author` of
+    /// the `seo` block)
+    pub author: Option<String>,
+    /// Home page of the author, as a site path or an absolute URL (default: the site)
+    pub url: Option<String>,
+    /// Photo of the author, as a site path or an absolute URL
+    pub photo: Option<String>,
+}
+
+/// The microformats2 markup of a page, handed to templates as `microformats`; the body they get is
+/// then wrapped in an `e-content` element.
+///
+/// Templates put these in an element with the `h-entry` class, as the built-in `base.html` does.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Microformats {
+    /// `p-name` element of the title
+    pub name: String,
+    /// `u-url` link to the canonical URL of the page
+    pub url: String,
+    /// `dt-published` element of the date of the page
+    pub published: Option<String>,
+    /// `p-author h-card` element of the author of the page
+    pub author: Option<String>,
+}
+
+impl Microformats {
+    /// Markup of the page titled `title` at `canonical_url`, published on `date` by `author` (or
+    /// else the author of `config`, or else `default_author`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::{microformats::{Microformats, MicroformatsConfig}, parse_date, urls::UrlResolver};
+    /// let urls = UrlResolver::new("https://example.com", "/");
+    /// let date = parse_date("2024-05-01").unwrap();
+    /// let mf = Microformats::new("Cats", "https://example.com/cats/", Some(&date), Some("Ana"), None, &MicroformatsConfig::default(), &urls);
+    /// assert_eq!(mf.name, r#"<span class="p-name">Cats</span>"#);
+    /// assert_eq!(mf.published.unwrap(), r#"<time class="dt-published" datetime="2024-05-01T00:00:00+00:00">2024-05-01</time>"#);
+    /// assert_eq!(mf.author.unwrap(), r#"<a class="p-author h-card" href="https://example.com/">Ana</a>"#);
+    /// ```
+    pub fn new(
+        title: &str,
+        canonical_url: &str,
+        date: Option<&DateTime<FixedOffset>>,
+        author: Option<&str>,
+        default_author: Option<&str>,
+        config: &MicroformatsConfig,
+        urls: &UrlResolver,
+    ) -> Self {
+        let author = author.or(config.author.as_deref()).or(default_author).map(|name| {
+            let url = config.url.as_deref().map_or_else(|| urls.url(""), |url| urls.absolute_url(url));
+            let photo = config
+                .photo
+                .as_deref()
+                .map(|photo| format!(r#"<img class="u-photo" src="{}" alt=""> "#, escape_html(&urls.absolute_url(photo))))
+                .unwrap_or_default();
+            format!(r#"<a class="p-author h-card" href="{}">{}{}</a>"#, escape_html(&url), photo, escape_html(name))
+        });
+        Self {
+            name: format!(r#"<span class="p-name">{}</span>"#, escape_html(title)),
+            url: format!(r#"<a class="u-url" href="{0}">{0}</a>"#, escape_html(canonical_url)),
+            published: date.map(|date| {
+                format!(r#"<time class="dt-published" datetime="{}">{}</time>"#, date.to_rfc3339(), date.format("%Y-%m-%d"))
+            }),
+            author,
+        }
+    }
+}
+
+/// `body_html` wrapped in the `e-content` element of an `h-entry`.
+pub fn wrap_content(body_html: &str) -> String {
+    format!("<div class=\"e-content\">\n{}\n</div>", body_html.trim())
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{PageSummary, folder_name_to_title, i18n::Translation, menus::Menus, series, urls::UrlResolver};
+
+/// Related posts listed per page when `related_posts` is not configured.
+pub const DEFAULT_RELATED_POSTS: usize = 5;
+
+/// Another page of the site, as handed to templates.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PageRef {
+    pub title: String,
+    pub href: String,
+    pub summary: String,
+}
+
+impl From<&PageSummary> for PageRef {
+    fn from(page: &PageSummary) -> Self {
+        PageRef { title: page.title.clone(), href: page.href.clone(), summary: page.summary.clone() }
+    }
+}
+
+/// What a page links to among the other pages of the site, known only once every page is.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct PageNav {
+    /// Pages sharing the most tags with this one, then the newest, handed to templates as `related`
+    pub related: Vec<PageRef>,
+    /// The page published before this one in the same section, handed to templates as `prev`
+    pub prev: Option<PageRef>,
+    /// The page published after this one in the same section, handed to templates as `next`
+    pub next: Option<PageRef>,
+    /// The series the page is part of, handed to templates as `series`
+    pub series: Option<SeriesNav>,
+    /// The home page and every section above the page, outermost first, handed to templates as
+    /// `breadcrumbs`
+    pub breadcrumbs: Vec<Breadcrumb>,
+    /// The menus of the site with the items leading to the page marked active, handed to templates
+    /// as `menus` (see [`crate::menus::mark_active`])
+    pub menus: Menus,
+    /// The other language versions of the page, by language, handed to templates as `translations`
+    pub translations: Vec<Translation>,
+}
+
+/// A step of the breadcrumb trail of a page.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Breadcrumb {
+    pub title: String,
+    pub href: String,
+}
+
+/// A series as seen from one of its parts.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SeriesNav {
+    pub name: String,
+    /// Landing page of the series (see [`series::write_series_pages`])
+    pub href: String,
+    /// Every part, from the oldest to the newest (see [`series::collect_series`])
+    pub parts: Vec<PageRef>,
+    /// Position of the page in `parts`, from 0
+    pub index: usize,
+}
+
+/// Directory of the section holding the page at `src_path`: the directory of a post written as
+/// `<section>/<post>/index.md`, or else the directory of the file.
+pub(crate) fn section_dir(src_path: &Path) -> &Path {
+    let dir = src_path.parent().unwrap_or(Path::new(""));
+    if src_path.file_name().is_some_and(|f| f == "index.md") {
+        dir.parent().unwrap_or(Path::new(""))
+    } else {
+        dir
+    }
+}
+
+/// Build the [`PageNav`] of every page of `pages` (newest first, as in `site.pages`), keyed by
+/// source path.
+///
+/// Up to `related_posts` pages sharing at least one tag (compared case-insensitively) are listed as
+/// related, those sharing more tags first and, among equals, the newest first.
+///
+/// `prev` and `next` link the pages of each section (see `section_dir`) from the oldest to the
+/// newest; undated pages follow their source order. Pages declaring a `series` get its parts.
+///
+/// The breadcrumbs of a page lead from the home page through every directory between `base_path`
+/// and its section, each titled and linked by its `index.md` when there is one, or else by the
+/// directory name and its section page (see [`crate::sections::write_section_pages`]).
+///
+/// On a multilingual site, the translations of a page are the pages of other languages sharing its
+/// `translation_key`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{PageSummary, nav::site_nav};
+/// let page = |title: &str, tags: &[&str]| PageSummary {
+///     title: title.to_string(),
+///     href: format!("/{}.html", title),
+///     tags: tags.iter().map(|t| t.to_string()).collect(),
+///     description: None,
+///     summary: String::new(),
+///     series: None,
+///     date: None,
+///     lang: None,
+///     translation_key: None,
+///     draft: false,
+///     source_path: format!("{}.md", title).into(),
+///     output_path: format!("{}.html", title).into(),
+/// };
+/// let pages = vec![page("a", &["rust", "web"]), page("b", &["Rust"]), page("c", &["web", "rust"]), page("d", &[])];
+/// let nav = site_nav(&pages, "".as_ref(), 5, &ssg_generator_utils::urls::UrlResolver::new("https://example.com", "/"));
+/// let related: Vec<_> = nav[std::path::Path::new("a.md")].related.iter().map(|p| p.title.as_str()).collect();
+/// assert_eq!(related, vec!["c", "b"]);
+/// assert!(nav[std::path::Path::new("d.md")].related.is_empty());
+/// // Undated pages of a section follow their source order
+/// assert_eq!(nav[std::path::Path::new("b.md")].prev.as_ref().unwrap().title, "a");
+/// assert_eq!(nav[std::path::Path::new("b.md")].next.as_ref().unwrap().title, "c");
+/// assert!(nav[std::path::Path::new("a.md")].prev.is_none());
+/// assert_eq!(nav[std::path::Path::new("a.md")].breadcrumbs[0].title, "Home");
+/// ```
+pub fn site_nav(pages: &[PageSummary], base_path: &Path, related_posts: usize, urls: &UrlResolver) -> BTreeMap<PathBuf, PageNav> {
+    let tags: Vec<HashSet<String>> = pages
+        .iter()
+        .map(|page| page.tags.iter().map(|tag| tag.to_lowercase()).collect())
+        .collect();
+    let mut sections: BTreeMap<&Path, Vec<usize>> = BTreeMap::new();
+    for (i, page) in pages.iter().enumerate() {
+        sections.entry(section_dir(&page.source_path)).or_default().push(i);
+    }
+    let all_series = series::collect_series(pages);
+    let mut neighbors: BTreeMap<usize, (Option<usize>, Option<usize>)> = BTreeMap::new();
+    for members in sections.values_mut() {
+        members.sort_by_key(|&i| (pages[i].date, &pages[i].source_path));
+        for (position, &i) in members.iter().enumerate() {
+            let prev = position.checked_sub(1).map(|p| members[p]);
+            neighbors.insert(i, (prev, members.get(position + 1).copied()));
+        }
+    }
+
+    pages
+        .iter()
+        .enumerate()
+        .map(|(i, page)| {
+            let mut related: Vec<(usize, usize)> = pages
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(j, _)| (j, tags[i].intersection(&tags[j]).count()))
+                .filter(|(_, shared)| *shared > 0)
+                .collect();
+            // Stable, so pages sharing as many tags stay newest first
+            related.sort_by_key(|(_, shared)| std::cmp::Reverse(*shared));
+            let related = related.into_iter().take(related_posts).map(|(j, _)| PageRef::from(&pages[j])).collect();
+            let (prev, next) = neighbors[&i];
+            let series = page.series.as_ref().map(|name| {
+                let parts = all_series[name].clone();
+                SeriesNav {
+                    name: name.clone(),
+                    href: series::series_href(name, urls),
+                    index: parts.iter().position(|part| part.href == page.href).unwrap_or_default(),
+                    parts,
+                }
+            });
+            (page.source_path.clone(), PageNav {
+                related,
+                prev: prev.map(|j| PageRef::from(&pages[j])),
+                next: next.map(|j| PageRef::from(&pages[j])),
+                series,
+                breadcrumbs: breadcrumbs(&page.source_path, pages, base_path, urls),
+                translations: translations(page, pages),
+                // Filled in by `generate_site`, which knows the menus
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// The other language versions of `page` among `pages`, sorted by language.
+fn translations(page: &PageSummary, pages: &[PageSummary]) -> Vec<Translation> {
+    let Some(key) = &page.translation_key else {
+        return Vec::new();
+    };
+    let mut translations: Vec<Translation> = pages
+        .iter()
+        .filter(|other| other.translation_key.as_ref() == Some(key) && other.lang != page.lang)
+        .filter_map(|other| {
+            let lang = other.lang.clone()?;
+            Some(Translation { lang, title: other.title.clone(), href: other.href.clone() })
+        })
+        .collect();
+    translations.sort_by(|a, b| a.lang.cmp(&b.lang));
+    translations
+}
+
+/// The breadcrumb trail of the page at `src_path` (see [`site_nav`]); empty for pages outside
+/// `base_path`.
+fn breadcrumbs(src_path: &Path, pages: &[PageSummary], base_path: &Path, urls: &UrlResolver) -> Vec<Breadcrumb> {
+    let Ok(section) = section_dir(src_path).strip_prefix(base_path) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<&Path> = section.ancestors().collect();
+    dirs.reverse();
+    dirs.into_iter()
+        .map(|dir| {
+            let index = base_path.join(dir).join("index.md");
+            match pages.iter().find(|page| page.source_path == index) {
+                Some(page) => Breadcrumb { title: page.title.clone(), href: page.href.clone() },
+                None if dir.as_os_str().is_empty() => Breadcrumb { title: "Home".to_string(), href: urls.href("") },
+                None => Breadcrumb {
+                    title: folder_name_to_title(dir),
+                    href: urls.href(&format!("{}/", dir.to_string_lossy().replace('\\', "/"))),
+                },
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,169 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser as MdParser, Tag, TagEnd};
+
+/// Column plain-text paragraphs are wrapped at.
+const WIDTH: usize = 72;
+
+/// Readable plain-text rendition of the Markdown page `md` titled `title`, for `curl` and other
+/// lightweight clients.
+///
+/// Paragraphs are wrapped at 72 columns, `#` and `##` headings underlined, lists and quotes kept
+/// with their markers and code blocks indented. Links and images are numbered like footnotes and
+/// listed at the end with the URL `resolve` gives for their target. HTML is left out.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::plaintext::markdown_to_text;
+/// let md = "## Naps\n\nCats **nap** a [lot](naps.md).\n\n- Sofa\n- Box\n\n```\nsleep(16h)\n```\n";
+/// let text = markdown_to_text(md, "Cats", |dest| format!("https://example.com/{}", dest));
+/// assert_eq!(text, "Cats\n====\n\nNaps\n----\n\nCats nap a lot [1].\n\n- Sofa\n- Box\n\n    sleep(16h)\n\nLinks:\n[1] https://example.com/naps.md\n");
+/// ```
+pub fn markdown_to_text(md: &str, title: &str, resolve: impl Fn(&str) -> String) -> String {
+    let mut out = String::new();
+    underline(&mut out, title, '=');
+    out.push('\n');
+
+    let mut text = String::new();
+    let mut links: Vec<String> = Vec::new();
+    let mut targets: Vec<String> = Vec::new();
+    // Counter of every open list, `None` for bulleted ones
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut marker = String::new();
+    let mut quotes = 0;
+    let mut in_code_block = false;
+    let indent = |lists: &[Option<u64>], quotes: usize| {
+        format!("{}{}", "> ".repeat(quotes), "  ".repeat(lists.len().saturating_sub(1)))
+    };
+
+    for event in MdParser::new_ext(md, Options::all()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                out.push('\n');
+            }
+            Event::Text(t) if in_code_block => {
+                let prefix = format!("{}    ", indent(&lists, quotes));
+                for line in t.lines() {
+                    out.push_str(format!("{}{}", prefix, line).trim_end());
+                    out.push('\n');
+                }
+            }
+            Event::Text(t) => text.push_str(&t),
+            Event::Code(t) => text.push_str(&format!("`{}`", t)),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::TaskListMarker(done) => text.push_str(if done { "[x] " } else { "[ ] " }),
+            Event::Start(Tag::Link { dest_url, .. }) => targets.push(resolve(&dest_url)),
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                targets.push(resolve(&dest_url));
+                text.push_str("[image: ");
+            }
+            Event::End(end @ (TagEnd::Link | TagEnd::Image)) => {
+                if end == TagEnd::Image {
+                    text.push(']');
+                }
+                links.extend(targets.pop());
+                text.push_str(&format!(" [{}]", links.len()));
+            }
+            Event::Start(Tag::BlockQuote(_)) => quotes += 1,
+            Event::End(TagEnd::BlockQuote(_)) => quotes -= 1,
+            Event::Start(Tag::List(start)) => {
+                // The text of the item holding a nested list comes before it
+                flush(&mut out, &mut text, &mut marker, &indent(&lists, quotes));
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    out.push('\n');
+                }
+            }
+            Event::Start(Tag::Item) => {
+                flush(&mut out, &mut text, &mut marker, &indent(&lists, quotes));
+                marker = match lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+            }
+            Event::End(TagEnd::Item) => flush(&mut out, &mut text, &mut marker, &indent(&lists, quotes)),
+            Event::End(TagEnd::Paragraph) => {
+                flush(&mut out, &mut text, &mut marker, &indent(&lists, quotes));
+                if lists.is_empty() {
+                    out.push('\n');
+                }
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                let heading = std::mem::take(&mut text);
+                match level {
+                    HeadingLevel::H1 => underline(&mut out, heading.trim(), '='),
+                    HeadingLevel::H2 => underline(&mut out, heading.trim(), '-'),
+                    _ => out.push_str(&format!("{}\n", heading.trim())),
+                }
+                out.push('\n');
+            }
+            Event::End(TagEnd::TableCell) => text.push_str("  "),
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => {
+                out.push_str(text.trim_end());
+                out.push('\n');
+                text.clear();
+            }
+            Event::End(TagEnd::Table) => out.push('\n'),
+            Event::Rule => out.push_str("----\n\n"),
+            _ => {}
+        }
+    }
+    flush(&mut out, &mut text, &mut marker, "");
+
+    if !links.is_empty() {
+        out.push_str("Links:\n");
+        for (i, link) in links.iter().enumerate() {
+            out.push_str(&format!("[{}] {}\n", i + 1, link));
+        }
+    }
+    let mut out = out.trim_end().to_string();
+    out.push('\n');
+    out
+}
+
+/// Write `text` underlined with `c` to `out`.
+fn underline(out: &mut String, text: &str, c: char) {
+    out.push_str(&format!("{}\n{}\n", text, c.to_string().repeat(text.chars().count())));
+}
+
+/// Wrap the pending `text` to `out`, its first line after `marker` and every line after `prefix`.
+fn flush(out: &mut String, text: &mut String, marker: &mut String, prefix: &str) {
+    let rest = format!("{}{}", prefix, " ".repeat(marker.len()));
+    let mut line = format!("{}{}", prefix, marker);
+    let mut empty = true;
+    for (i, hard_line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push_str(line.trim_end());
+            out.push('\n');
+            line = rest.clone();
+            empty = true;
+        }
+        for word in hard_line.split_whitespace() {
+            if !empty && line.chars().count() + 1 + word.chars().count() > WIDTH {
+                out.push_str(line.trim_end());
+                out.push('\n');
+                line = rest.clone();
+                empty = true;
+            }
+            if !empty {
+                line.push(' ');
+            }
+            line.push_str(word);
+            empty = false;
+        }
+    }
+    if !text.trim().is_empty() {
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    text.clear();
+    marker.clear();
+}
@@ -0,0 +1,64 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::Path,
+};
+
+/// Record `outputs` (paths relative to `dist_path`) in `manifest_path`, deleting first the files
+/// listed by the previous manifest that are no longer among them when `prune` is set.
+///
+/// Only files the generator wrote in an earlier build are ever removed, so anything else living in
+/// `dist_path` (bundled CSS and JS, `candidates.txt`, ...) is left alone. Directories emptied by the
+/// removal are deleted too. Returns the removed paths, relative to `dist_path`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeSet;
+/// use ssg_generator_utils::prune::record_outputs;
+/// let root = std::env::temp_dir().join("prune-doctest");
+/// let dist = root.join("dist");
+/// let manifest = root.join("outputs.txt");
+/// std::fs::create_dir_all(dist.join("old-post")).unwrap();
+/// std::fs::write(dist.join("old-post/index.html"), "old").unwrap();
+/// std::fs::write(dist.join("main.css"), "body {}").unwrap();
+/// let first: BTreeSet<String> = ["old-post/index.html".to_string()].into();
+/// record_outputs(&dist, &manifest, &first, false).unwrap();
+///
+/// let removed = record_outputs(&dist, &manifest, &BTreeSet::new(), true).unwrap();
+/// assert_eq!(removed, ["old-post/index.html"]);
+/// assert!(!dist.join("old-post").exists());
+/// assert!(dist.join("main.css").exists());
+/// ```
+pub fn record_outputs(
+    dist_path: &Path,
+    manifest_path: &Path,
+    outputs: &BTreeSet<String>,
+    prune: bool,
+) -> std::io::Result<Vec<String>> {
+    let mut removed = Vec::new();
+    if prune {
+        let previous = fs::read_to_string(manifest_path).unwrap_or_default();
+        for rel in previous.lines().filter(|rel| !rel.is_empty() && !outputs.contains(*rel)) {
+            let path = dist_path.join(rel);
+            match fs::remove_file(&path) {
+                Ok(()) => removed.push(rel.to_string()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+            // Stops at the first directory that still has content
+            for dir in path.ancestors().skip(1).take_while(|dir| *dir != dist_path) {
+                if fs::remove_dir(dir).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(parent) = manifest_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let manifest: Vec<&str> = outputs.iter().map(String::as_str).collect();
+    fs::write(manifest_path, manifest.join("\n"))?;
+    Ok(removed)
+}
@@ -0,0 +1,60 @@
+use std::{fs, path::Path};
+
+use crate::escape_html;
+
+/// Permalink of the stub written for `alias`, a site path such as `/old-url/` or
+/// `/2023/old-slug.html`: directories and paths without an extension get an `index.html`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::redirects::alias_permalink;
+/// assert_eq!(alias_permalink("/old-url/"), "old-url/index.html");
+/// assert_eq!(alias_permalink("/old-url"), "old-url/index.html");
+/// assert_eq!(alias_permalink("2023/old-slug.html"), "2023/old-slug.html");
+/// ```
+pub fn alias_permalink(alias: &str) -> String {
+    let path = alias.trim_start_matches('/');
+    let file_name = path.rsplit('/').next().unwrap_or("");
+    if file_name.is_empty() {
+        format!("{}index.html", path)
+    } else if !file_name.contains('.') {
+        format!("{}/index.html", path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// HTML of a stub sending visitors to `target` right away and pointing search engines at
+/// `canonical`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::redirects::redirect_html;
+/// let html = redirect_html("https://example.com/new/", "https://example.com/new/");
+/// assert!(html.contains(r#"<meta http-equiv="refresh" content="0; url=https://example.com/new/">"#));
+/// assert!(html.contains(r#"<link rel="canonical" href="https://example.com/new/">"#));
+/// ```
+pub fn redirect_html(target: &str, canonical: &str) -> String {
+    let target = escape_html(target);
+    format!(
+        concat!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Redirecting…</title>\n",
+            "<link rel=\"canonical\" href=\"{canonical}\">\n<meta name=\"robots\" content=\"noindex\">\n",
+            "<meta http-equiv=\"refresh\" content=\"0; url={target}\">\n</head>\n",
+            "<body><p>This page has moved to <a href=\"{target}\">{target}</a>.</p></body>\n</html>\n"
+        ),
+        target = target,
+        canonical = escape_html(canonical),
+    )
+}
+
+/// Write the stub of [`redirect_html`] to `permalink` under `dist_path`.
+pub fn write_redirect(dist_path: &Path, permalink: &str, target: &str, canonical: &str) -> std::io::Result<()> {
+    let dest = dist_path.join(permalink);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(dest, redirect_html(target, canonical))
+}
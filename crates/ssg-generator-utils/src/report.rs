@@ -0,0 +1,142 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::Path,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use indicatif::ProgressBar;
+use serde::{Serialize, Serializer};
+
+/// The parts of a build that [`Stats`] times separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Front matter, includes and Markdown parsing
+    Parse,
+    /// Syntax highlighting of code blocks
+    Highlight,
+    /// Templated pages, shortcodes and page, tag and index templates
+    Template,
+    /// Reading sources and writing outputs
+    Io,
+}
+
+/// Time spent per [`Phase`] and warnings emitted during a build, shared by the pages rendered in parallel.
+///
+/// Durations are summed over every page, so with several threads they can add up to more than the
+/// wall-clock time of the build.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::report::{Phase, Stats};
+/// let stats = Stats::default();
+/// let sum = stats.time(Phase::Parse, || 1 + 1);
+/// stats.warn("template", None, format_args!("something looks off"));
+/// assert_eq!(sum, 2);
+/// assert_eq!(stats.warnings(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct Stats {
+    // Nanoseconds, indexed like `Phase`
+    phases: [AtomicU64; 4],
+    warnings: AtomicUsize,
+    // Cleared while warnings are logged so they don't tear it
+    progress: Option<ProgressBar>,
+}
+
+impl Stats {
+    pub(crate) fn with_progress(progress: ProgressBar) -> Self {
+        Stats { progress: Some(progress), ..Default::default() }
+    }
+
+    /// Run `f`, adding the time it takes to `phase`.
+    pub fn time<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.add(phase, start.elapsed());
+        result
+    }
+
+    /// Add `duration` to `phase`.
+    pub fn add(&self, phase: Phase, duration: Duration) {
+        self.phases[phase as usize].fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Total time spent in `phase` so far.
+    pub fn get(&self, phase: Phase) -> Duration {
+        Duration::from_nanos(self.phases[phase as usize].load(Ordering::Relaxed))
+    }
+
+    /// Log `message` at the warning level with its `category` and the `path` it is about, and count it.
+    pub fn warn(&self, category: &str, path: Option<&Path>, message: fmt::Arguments) {
+        let log = || tracing::warn!(category, path = path.map(|p| display(p.display())), "{}", message);
+        match &self.progress {
+            Some(progress) => progress.suspend(log),
+            None => log(),
+        }
+        self.warnings.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of warnings emitted so far.
+    pub fn warnings(&self) -> usize {
+        self.warnings.load(Ordering::Relaxed)
+    }
+}
+
+/// Summary of a build, as returned in [`crate::SiteOutput::report`] and written to `build-report.json`.
+///
+/// Durations are serialized in milliseconds.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct BuildReport {
+    /// Pages rendered by this build
+    pub pages_rendered: usize,
+    /// Unchanged pages reused from the page cache instead of being rendered
+    pub pages_skipped: usize,
+    pub warnings: usize,
+    pub errors: usize,
+    #[serde(rename = "total_ms", serialize_with = "serialize_millis")]
+    pub total: Duration,
+    #[serde(rename = "parse_ms", serialize_with = "serialize_millis")]
+    pub parse: Duration,
+    #[serde(rename = "highlight_ms", serialize_with = "serialize_millis")]
+    pub highlight: Duration,
+    #[serde(rename = "template_ms", serialize_with = "serialize_millis")]
+    pub template: Duration,
+    #[serde(rename = "io_ms", serialize_with = "serialize_millis")]
+    pub io: Duration,
+    /// Estimated tokens of every Markdown copy for LLMs, by path relative to the output directory (see
+    /// [`crate::llms::estimate_tokens`])
+    pub llm_tokens: BTreeMap<String, usize>,
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+impl fmt::Display for BuildReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} pages rendered, {} skipped, {} warning(s), {} error(s) in {:.2?}",
+            self.pages_rendered, self.pages_skipped, self.warnings, self.errors, self.total
+        )?;
+        write!(
+            f,
+            "  parse {:.2?}, highlight {:.2?}, template {:.2?}, io {:.2?}",
+            self.parse, self.highlight, self.template, self.io
+        )?;
+        if let Some((largest, tokens)) = self.llm_tokens.iter().max_by_key(|(_, tokens)| **tokens) {
+            write!(
+                f,
+                "\n  llms ~{} tokens in {} pages, largest {} (~{})",
+                self.llm_tokens.values().sum::<usize>(),
+                self.llm_tokens.len(),
+                largest,
+                tokens
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{inject_into_head, urls::UrlResolver};
+
+/// Rules for `robots.txt`, read from the `robots` block of `cats-ssg.json`.
+///
+/// Paths are relative to the site, like permalinks, and get the base path prepended.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct RobotsConfig {
+    /// Paths crawlers may visit even inside a disallowed one
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Paths crawlers should stay out of
+    #[serde(default)]
+    pub disallow: Vec<String>,
+}
+
+/// Write `robots.txt` to `dist_path` with the rules of `config` for every user agent, followed by
+/// the URL of the `sitemap` (a permalink such as `sitemap.xml`).
+///
+/// Without any rule, everything is allowed.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{robots::{write_robots, RobotsConfig}, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("robots-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// let config = RobotsConfig { disallow: vec!["drafts/".to_string()], ..Default::default() };
+/// write_robots(&dist, Some(&config), &UrlResolver::new("https://example.com", "/blog/"), "sitemap.xml").unwrap();
+/// assert_eq!(
+///     std::fs::read_to_string(dist.join("robots.txt")).unwrap(),
+///     "User-agent: *\nDisallow: /blog/drafts/\n\nSitemap: https://example.com/blog/sitemap.xml\n"
+/// );
+/// ```
+pub fn write_robots(
+    dist_path: &Path,
+    config: Option<&RobotsConfig>,
+    urls: &UrlResolver,
+    sitemap: &str,
+) -> std::io::Result<()> {
+    let mut robots = String::from("User-agent: *\n");
+    let config = config.cloned().unwrap_or_default();
+    for path in &config.allow {
+        robots.push_str(&format!("Allow: {}\n", urls.href(path)));
+    }
+    for path in &config.disallow {
+        robots.push_str(&format!("Disallow: {}\n", urls.href(path)));
+    }
+    if config.allow.is_empty() && config.disallow.is_empty() {
+        robots.push_str("Disallow:\n");
+    }
+    robots.push_str(&format!("\nSitemap: {}\n", urls.url(sitemap)));
+    fs::write(dist_path.join("robots.txt"), robots)
+}
+
+/// Add `<meta name="robots" content="noindex">` to the `<head>` of `html`, or in front of it when
+/// it has none.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::robots::inject_noindex;
+/// assert_eq!(
+///     inject_noindex("<html><head><title>Hi</title></head></html>"),
+///     r#"<html><head><title>Hi</title><meta name="robots" content="noindex"></head></html>"#
+/// );
+/// assert_eq!(inject_noindex("<p>Hi</p>"), r#"<meta name="robots" content="noindex"><p>Hi</p>"#);
+/// ```
+pub fn inject_noindex(html: &str) -> String {
+    inject_into_head(html, r#"<meta name="robots" content="noindex">"#)
+}
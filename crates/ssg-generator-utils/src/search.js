@@ -0,0 +1,53 @@
+// Client for the search index written next to this file: `search(query)` resolves to the pages
+// matching every word of `query`, best first, each loading its fragment with `data()`.
+const root = new URL(".", import.meta.url);
+const chunks = new Map();
+let entry;
+
+const load = (path) =>
+  fetch(new URL(path, root)).then((response) => {
+    if (!response.ok) throw new Error(`Failed to load ${path}: ${response.status}`);
+    return response.json();
+  });
+
+// Must stay in sync with `search::stem`
+export function stem(word) {
+  let w = word.toLowerCase();
+  if ([...w].length <= 3) return w;
+  if (w.endsWith("ies")) w = w.slice(0, -3) + "y";
+  else if (/(sses|ches|shes|xes|zes)$/.test(w)) w = w.slice(0, -2);
+  else if (w.endsWith("s") && !/(ss|us|is)$/.test(w)) w = w.slice(0, -1);
+  for (const suffix of ["ing", "ed"]) {
+    if (!w.endsWith(suffix)) continue;
+    const base = w.slice(0, -suffix.length);
+    if ([...base].length < 3 || !/[aeiouy]/.test(base)) continue;
+    w = /([^aeioulsz])\1$/u.test(base) ? base.slice(0, -1) : base;
+    break;
+  }
+  return w;
+}
+
+export async function search(query) {
+  entry ??= await load("entry.json");
+  const terms = [...new Set(query.split(/[^\p{Alphabetic}\p{N}]+/u).filter(Boolean).map(stem))];
+  let scores = null;
+  for (const [i, term] of terms.entries()) {
+    const prefix = [...term].slice(0, entry.prefix_chars).join("");
+    if (!entry.chunks.includes(prefix)) return [];
+    if (!chunks.has(prefix)) chunks.set(prefix, load(`index/${encodeURIComponent(prefix)}.json`));
+    const chunk = await chunks.get(prefix);
+    // The last word may still be being typed, so it also matches longer words
+    const last = i === terms.length - 1;
+    const matches = new Map();
+    for (const [word, pages] of Object.entries(chunk)) {
+      if (word !== term && !(last && word.startsWith(term))) continue;
+      for (const [page, positions] of pages) matches.set(page, (matches.get(page) ?? 0) + positions.length);
+    }
+    scores = scores
+      ? new Map([...scores].filter(([page]) => matches.has(page)).map(([page, score]) => [page, score + matches.get(page)]))
+      : matches;
+  }
+  return [...(scores ?? [])]
+    .sort((a, b) => b[1] - a[1])
+    .map(([id, score]) => ({ id, score, data: () => load(`fragment/${id}.json`) }));
+}
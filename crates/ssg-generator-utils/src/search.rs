@@ -0,0 +1,185 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::Path,
+};
+use pulldown_cmark::{Event, Options, Parser as MdParser, Tag, TagEnd};
+use serde::Serialize;
+
+use crate::{PageOutput, remove_tag_and_contents, split_front_matter};
+
+/// Directory of the search index, relative to the output directory.
+pub const SEARCH_DIR: &str = "search";
+
+/// Characters of a word that pick the index chunk it is stored in.
+const PREFIX_CHARS: usize = 2;
+
+/// `search/index/<prefix>.json`: the pages, and the positions in them, of every stem with the prefix.
+type Chunk = BTreeMap<String, Vec<(usize, Vec<usize>)>>;
+
+/// `search/entry.json`: what a client loads first to know which chunks exist.
+#[derive(Serialize)]
+struct Entry {
+    version: u32,
+    pages: usize,
+    prefix_chars: usize,
+    chunks: Vec<String>,
+}
+
+/// `search/fragment/<id>.json`: what a client shows for a result.
+#[derive(Serialize)]
+struct Fragment<'a> {
+    url: &'a str,
+    title: &'a str,
+    excerpt: &'a str,
+    content: String,
+    word_count: usize,
+}
+
+/// Reduce `word` to a stem shared by its plural and `-ing`/`-ed` forms, lowercased.
+///
+/// A light English stemmer, mirrored by the `stem` of `search.js` so queries are stemmed the way the
+/// index was.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::search::stem;
+/// assert_eq!(stem("Cats"), "cat");
+/// assert_eq!(stem("stories"), "story");
+/// assert_eq!(stem("running"), "run");
+/// assert_eq!(stem("compiled"), "compil");
+/// assert_eq!(stem("class"), "class");
+/// ```
+pub fn stem(word: &str) -> String {
+    let mut w = word.to_lowercase();
+    if w.chars().count() <= 3 {
+        return w;
+    }
+    if let Some(base) = w.strip_suffix("ies") {
+        w = format!("{}y", base);
+    } else if ["sses", "ches", "shes", "xes", "zes"].iter().any(|suffix| w.ends_with(suffix)) {
+        w.truncate(w.len() - 2);
+    } else if w.ends_with('s') && !["ss", "us", "is"].iter().any(|suffix| w.ends_with(suffix)) {
+        w.pop();
+    }
+    for suffix in ["ing", "ed"] {
+        let Some(base) = w.strip_suffix(suffix) else {
+            continue;
+        };
+        if base.chars().count() < 3 || !base.contains(['a', 'e', 'i', 'o', 'u', 'y']) {
+            continue;
+        }
+        let mut base = base.to_string();
+        // running -> run, but not falling -> fal
+        let mut last = base.chars().rev();
+        if let (Some(a), Some(b)) = (last.next(), last.next())
+            && a == b
+            && !"aeioulsz".contains(a)
+        {
+            base.pop();
+        }
+        w = base;
+        break;
+    }
+    w
+}
+
+/// Stems of the words of `text`, in order, splitting on anything that is not a letter or a digit.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(stem)
+}
+
+/// Searchable text of the Markdown page `md`: its prose and inline code without markup, front matter,
+/// code blocks or `<only-in-llm-txt>` content.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::search::plain_text;
+/// let md = "---\ntitle: Hi\n---\n# Cats\n\nSome *cats* nap.\n\n```rust\nlet x = 1;\n```\n";
+/// assert_eq!(plain_text(md), "Cats Some cats nap.");
+/// ```
+pub fn plain_text(md: &str) -> String {
+    let (_, body) = split_front_matter(md);
+    let body = remove_tag_and_contents(body, "only-in-llm-txt");
+    let mut text = String::new();
+    let mut in_code_block = false;
+    for event in MdParser::new_ext(&body, Options::all()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(t) | Event::Code(t) if !in_code_block => text.push_str(&t),
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Write a chunked full-text search index of `pages` under `dist_path/search`, in the spirit of
+/// Pagefind's: clients load a small `entry.json`, then only the `index/<prefix>.json` chunks holding
+/// the stems of the words searched for, then the `fragment/<id>.json` of the results they show.
+///
+/// Every chunk maps the stems (see [`stem`]) starting with its prefix to `[page id, [positions]]`
+/// pairs, positions counting the words of the title followed by the text of the page (see
+/// [`plain_text`]). A fragment holds the `url`, `title`, `excerpt` (the summary), `content` and
+/// `word_count` of a page. Pages with `noindex` or `exclude_from_index` are left out.
+///
+/// `search/search.js` is written along with the index; its `search(query)` resolves to the pages
+/// matching every word of the query, the last one as a prefix.
+///
+/// Returns the output paths relative to `dist_path`.
+pub fn write_search_index(dist_path: &Path, pages: &[PageOutput]) -> io::Result<Vec<String>> {
+    let dir = dist_path.join(SEARCH_DIR);
+    for sub in ["index", "fragment"] {
+        // Chunks and fragments of pages since removed must not linger
+        let sub = dir.join(sub);
+        if sub.exists() {
+            fs::remove_dir_all(&sub)?;
+        }
+        fs::create_dir_all(&sub)?;
+    }
+    let mut written = Vec::new();
+    let mut write = |rel: String, content: String| -> io::Result<()> {
+        fs::write(dir.join(&rel), content)?;
+        written.push(format!("{}/{}", SEARCH_DIR, rel));
+        Ok(())
+    };
+
+    let mut chunks: BTreeMap<String, Chunk> = BTreeMap::new();
+    let pages = pages
+        .iter()
+        .filter(|page| !page.meta.noindex.unwrap_or(false) && !page.meta.exclude_from_index.unwrap_or(false));
+    let mut count = 0;
+    for (id, page) in pages.enumerate() {
+        let content = fs::read_to_string(&page.source_path).map(|md| plain_text(&md))?;
+        let mut positions: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (position, word) in tokenize(&page.title).chain(tokenize(&content)).enumerate() {
+            positions.entry(word).or_default().push(position);
+        }
+        for (word, positions) in positions {
+            let prefix: String = word.chars().take(PREFIX_CHARS).collect();
+            chunks.entry(prefix).or_default().entry(word).or_default().push((id, positions));
+        }
+        let fragment = Fragment {
+            url: &page.href,
+            title: &page.title,
+            excerpt: &page.summary,
+            content,
+            word_count: page.word_count,
+        };
+        write(format!("fragment/{}.json", id), serde_json::to_string(&fragment).unwrap_or_default())?;
+        count += 1;
+    }
+
+    for (prefix, chunk) in &chunks {
+        write(format!("index/{}.json", prefix), serde_json::to_string(chunk).unwrap_or_default())?;
+    }
+    let entry = Entry { version: 1, pages: count, prefix_chars: PREFIX_CHARS, chunks: chunks.into_keys().collect() };
+    write("entry.json".to_string(), serde_json::to_string(&entry).unwrap_or_default())?;
+    write("search.js".to_string(), include_str!("search.js").to_string())?;
+    Ok(written)
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use minijinja::{Environment, context};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    PageSummary, date_context, error::SsgError, folder_name_to_title,
+    nav::{PageRef, section_dir},
+    urls::UrlResolver,
+};
+
+/// How the pages of a section are ordered.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Newest first
+    #[default]
+    Date,
+    /// Alphabetically by title
+    Title,
+}
+
+/// Settings for the generated section pages, read from the `sections` block of `cats-ssg.json`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct SectionConfig {
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// Pages listed per page of a section; every page is listed at once when unset
+    pub per_page: Option<usize>,
+}
+
+/// A directory of the content tree, handed to the content-index template as `tree`.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContentTree {
+    /// Title derived from the directory name (see [`folder_name_to_title`]), empty for the content root
+    pub title: String,
+    /// Directory relative to the content root, e.g. `tech/rust`
+    pub path: String,
+    /// Pages of the directory, in the order they were given
+    pub pages: Vec<PageRef>,
+    /// Subdirectories holding pages, by name
+    pub sections: Vec<ContentTree>,
+}
+
+/// Build the [`ContentTree`] of `pages`, given as `(source path, page)` pairs, placing every page in
+/// its section under `base_path` (see `nav::section_dir`); pages outside `base_path` go to the root.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use ssg_generator_utils::{nav::PageRef, sections::content_tree};
+/// let page = |title: &str| PageRef { title: title.to_string(), href: format!("/{}/", title), summary: String::new() };
+/// let pages = [
+///     (PathBuf::from("content/tech/rust/async/index.md"), page("async")),
+///     (PathBuf::from("content/tech/intro.md"), page("intro")),
+///     (PathBuf::from("content/about.md"), page("about")),
+/// ];
+/// let tree = content_tree(pages.iter().map(|(src, page)| (src.as_path(), page)), "content".as_ref());
+/// assert_eq!(tree.pages[0].title, "about");
+/// assert_eq!(tree.sections[0].title, "Tech");
+/// assert_eq!(tree.sections[0].pages[0].title, "intro");
+/// assert_eq!(tree.sections[0].sections[0].path, "tech/rust");
+/// assert_eq!(tree.sections[0].sections[0].pages[0].title, "async");
+/// ```
+pub fn content_tree<'a>(pages: impl IntoIterator<Item = (&'a Path, &'a PageRef)>, base_path: &Path) -> ContentTree {
+    fn insert(node: &mut ContentTree, dir: &Path, components: &[&std::ffi::OsStr], page: PageRef) {
+        let Some((first, rest)) = components.split_first() else {
+            node.pages.push(page);
+            return;
+        };
+        let dir = dir.join(first);
+        let path = dir.to_string_lossy().replace('\\', "/");
+        let position = match node.sections.binary_search_by(|section| section.path.as_str().cmp(&path)) {
+            Ok(position) => position,
+            Err(position) => {
+                let title = folder_name_to_title(&dir);
+                node.sections.insert(position, ContentTree { title, path, ..Default::default() });
+                position
+            }
+        };
+        insert(&mut node.sections[position], &dir, rest, page);
+    }
+
+    let mut tree = ContentTree::default();
+    for (source, page) in pages {
+        let dir = section_dir(source).strip_prefix(base_path).unwrap_or(Path::new(""));
+        let components: Vec<_> = dir.iter().collect();
+        insert(&mut tree, Path::new(""), &components, page.clone());
+    }
+    tree
+}
+
+/// Directories, relative to `base_path`, that get a generated section page: every directory holding
+/// posts (see `nav::section_dir`) and its ancestors, except the content root and directories
+/// with an `index.md` of their own.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use ssg_generator_utils::sections::section_dirs;
+/// let base = std::env::temp_dir().join("section-dirs-doctest");
+/// std::fs::create_dir_all(base.join("notes")).unwrap();
+/// std::fs::write(base.join("notes/index.md"), "# Notes").unwrap();
+/// let sources = [base.join("tech/rust/async/index.md"), base.join("notes/todo.md"), base.join("about.md")];
+/// let dirs = section_dirs(sources.iter().map(PathBuf::as_path), &base);
+/// assert_eq!(dirs.into_iter().collect::<Vec<_>>(), vec![Path::new("tech"), Path::new("tech/rust")]);
+/// ```
+pub fn section_dirs<'a>(sources: impl IntoIterator<Item = &'a Path>, base_path: &Path) -> BTreeSet<PathBuf> {
+    let mut dirs = BTreeSet::new();
+    for source in sources {
+        let Ok(dir) = section_dir(source).strip_prefix(base_path) else {
+            continue;
+        };
+        dirs.extend(dir.ancestors().filter(|dir| !dir.as_os_str().is_empty()).map(Path::to_path_buf));
+    }
+    dirs.retain(|dir| !base_path.join(dir).join("index.md").exists());
+    dirs
+}
+
+/// Render a listing of every section of [`section_dirs`] through the `section.html` template to
+/// `<section>/index.html`, and its further pages to `<section>/page/<n>/index.html` with
+/// `per_page`, skipping paths already in `taken`.
+///
+/// The template receives `{ title, section, pages: [{ title, href, summary, date }], sections:
+/// [{ title, href }], pagination: { current, total, prev, next } }`, where `sections` lists the
+/// subsections and `prev`/`next` are the hrefs of the neighboring pages of the listing, if any.
+///
+/// Returns the output paths relative to `dist_path` so they can be added to the sitemap.
+pub fn write_section_pages(
+    dist_path: &Path,
+    base_path: &Path,
+    pages: &[PageSummary],
+    env: &Environment,
+    urls: &UrlResolver,
+    config: Option<&SectionConfig>,
+    taken: &BTreeSet<String>,
+) -> Result<Vec<String>, SsgError> {
+    let dirs = section_dirs(pages.iter().map(|page| page.source_path.as_path()), base_path);
+    if dirs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let config = config.cloned().unwrap_or_default();
+    let tmpl = env.get_template("section.html").map_err(SsgError::template("section.html"))?;
+    let permalink = |dir: &Path| dir.to_string_lossy().replace('\\', "/");
+
+    let mut members: BTreeMap<&Path, Vec<&PageSummary>> = BTreeMap::new();
+    for page in pages {
+        if let Ok(dir) = section_dir(&page.source_path).strip_prefix(base_path) {
+            members.entry(dir).or_default().push(page);
+        }
+    }
+    let mut written = Vec::new();
+    for dir in &dirs {
+        let mut children = members.get(dir.as_path()).cloned().unwrap_or_default();
+        match config.sort_by {
+            // `pages` is newest first already
+            SortBy::Date => {}
+            SortBy::Title => children.sort_by_key(|page| page.title.to_lowercase()),
+        }
+        let subsections: Vec<_> = dirs
+            .iter()
+            .filter(|sub| sub.parent() == Some(dir.as_path()))
+            .map(|sub| context! { title => folder_name_to_title(sub), href => urls.href(&format!("{}/", permalink(sub))) })
+            .collect();
+
+        let chunks: Vec<&[&PageSummary]> = match config.per_page {
+            Some(per_page) if per_page > 0 && !children.is_empty() => children.chunks(per_page).collect(),
+            _ => vec![&children[..]],
+        };
+        let page_href = |n: usize| match n {
+            1 => urls.href(&format!("{}/", permalink(dir))),
+            n => urls.href(&format!("{}/page/{}/", permalink(dir), n)),
+        };
+        for (i, chunk) in chunks.iter().enumerate() {
+            let current = i + 1;
+            let rel = match current {
+                1 => format!("{}/index.html", permalink(dir)),
+                n => format!("{}/page/{}/index.html", permalink(dir), n),
+            };
+            if taken.contains(&rel) {
+                continue;
+            }
+            let items: Vec<_> = chunk
+                .iter()
+                .map(|page| context! {
+                    title => page.title,
+                    href => page.href,
+                    summary => page.summary,
+                    date => page.date.as_ref().map(date_context),
+                })
+                .collect();
+            let rendered = tmpl
+                .render(context! {
+                    title => folder_name_to_title(dir),
+                    section => permalink(dir),
+                    pages => items,
+                    sections => subsections,
+                    pagination => context! {
+                        current => current,
+                        total => chunks.len(),
+                        prev => (current > 1).then(|| page_href(current - 1)),
+                        next => (current < chunks.len()).then(|| page_href(current + 1)),
+                    },
+                })
+                .map_err(SsgError::template("section.html"))?;
+
+            let dest = dist_path.join(&rel);
+            fs::create_dir_all(dest.parent().unwrap()).map_err(SsgError::io(dest.parent().unwrap()))?;
+            fs::write(&dest, rendered).map_err(SsgError::io(&dest))?;
+            written.push(rel);
+        }
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{Meta, authors::author_href, cards::CardConfig, escape_html, nav::Breadcrumb, parse_date, urls::UrlResolver};
+
+/// Open Graph and Twitter Card settings, read from the `seo` block of `cats-ssg.json`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct SeoConfig {
+    /// `og:site_name` of every page
+    pub site_name: Option<String>,
+    /// Share image for pages without an `image` of their own, as a site path or an absolute URL
+    pub image: Option<String>,
+    /// `twitter:site` handle, such as `@example`
+    pub twitter_site: Option<String>,
+    /// Author of pages without an `author` of their own, in their JSON-LD
+    pub author: Option<String>,
+    /// Logo of the `Organization` in the JSON-LD of the home page, as a site path or an absolute URL
+    pub logo: Option<String>,
+    /// Share images rendered for posts without an `image` of their own (see [`crate::cards`])
+    pub cards: Option<CardConfig>,
+    /// Add the tags of [`Seo::to_html`] and the script of [`json_ld_script`] to the `<head>` of every
+    /// page whose template does not already write them
+    #[serde(default)]
+    pub inject: bool,
+    /// Add a `BreadcrumbList` of the breadcrumbs of every page to its JSON-LD
+    #[serde(default)]
+    pub breadcrumbs: bool,
+}
+
+/// The Open Graph and Twitter Card data of a page, handed to templates as `seo`.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Seo {
+    /// `og:title`
+    pub title: String,
+    /// `og:description`, from the `description` metadata
+    pub description: Option<String>,
+    /// `og:url`, the canonical URL of the page (see [`Meta::canonical_url`])
+    pub url: String,
+    /// `og:type`: `website` for the home page, `article` for every other page
+    #[serde(rename = "type")]
+    pub og_type: String,
+    /// `og:image`, an absolute URL from the `image` metadata, or else the card of the page, or else the
+    /// configured default
+    pub image: Option<String>,
+    /// Absolute URL of the share image rendered for the page, when it has one
+    pub card: Option<String>,
+    /// `og:site_name`
+    pub site_name: Option<String>,
+    /// `twitter:card`: `summary_large_image` when there is an image, `summary` otherwise
+    pub twitter_card: String,
+    /// `twitter:site`
+    pub twitter_site: Option<String>,
+}
+
+impl Seo {
+    /// Build the data of the page titled `title` published at `href` (see [`UrlResolver::href`]), whose
+    /// share image is rendered at the permalink `card` (see [`crate::cards::card_permalink`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::{parse_front_matter, seo::{Seo, SeoConfig}, urls::UrlResolver};
+    /// let urls = UrlResolver::new("https://example.com", "/blog/");
+    /// let meta = parse_front_matter("description: All about cats\nimage: img/cat.png").unwrap();
+    /// let config = SeoConfig { site_name: Some("Cats".to_string()), ..Default::default() };
+    /// let seo = Seo::new("Cats", &meta, "/blog/cats.html", false, None, &urls, Some(&config));
+    /// assert_eq!(seo.url, "https://example.com/blog/cats.html");
+    /// assert_eq!(seo.og_type, "article");
+    /// assert_eq!(seo.image.as_deref(), Some("https://example.com/blog/img/cat.png"));
+    /// assert_eq!(seo.twitter_card, "summary_large_image");
+    /// ```
+    pub fn new(
+        title: &str,
+        meta: &Meta,
+        href: &str,
+        home: bool,
+        card: Option<&str>,
+        urls: &UrlResolver,
+        config: Option<&SeoConfig>,
+    ) -> Self {
+        let card = card.map(|card| urls.url(card));
+        let image = meta
+            .image
+            .as_deref()
+            .map(|image| urls.absolute_url(image))
+            .or_else(|| card.clone())
+            .or_else(|| config.and_then(|config| config.image.as_deref()).map(|image| urls.absolute_url(image)));
+        Seo {
+            title: title.to_string(),
+            description: meta.description.clone(),
+            url: meta.canonical_url(href, urls),
+            og_type: if home { "website" } else { "article" }.to_string(),
+            twitter_card: if image.is_some() { "summary_large_image" } else { "summary" }.to_string(),
+            image,
+            card,
+            site_name: config.and_then(|config| config.site_name.clone()),
+            twitter_site: config.and_then(|config| config.twitter_site.clone()),
+        }
+    }
+
+    /// The `<meta>` tags of every field that is set, one per line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::seo::Seo;
+    /// let seo = Seo { title: "Tom & Jerry".to_string(), url: "https://example.com/".to_string(), ..Default::default() };
+    /// let html = seo.to_html();
+    /// assert!(html.contains(r#"<meta property="og:title" content="Tom &amp; Jerry">"#));
+    /// assert!(!html.contains("og:image"));
+    /// ```
+    pub fn to_html(&self) -> String {
+        let tags = [
+            ("property", "og:title", Some(&self.title)),
+            ("property", "og:description", self.description.as_ref()),
+            ("property", "og:url", Some(&self.url)),
+            ("property", "og:type", Some(&self.og_type)),
+            ("property", "og:image", self.image.as_ref()),
+            ("property", "og:site_name", self.site_name.as_ref()),
+            ("name", "twitter:card", Some(&self.twitter_card)),
+            ("name", "twitter:site", self.twitter_site.as_ref()),
+            ("name", "twitter:title", Some(&self.title)),
+            ("name", "twitter:description", self.description.as_ref()),
+            ("name", "twitter:image", self.image.as_ref()),
+        ];
+        tags.into_iter()
+            .filter_map(|(attr, name, content)| {
+                let content = content.filter(|content| !content.is_empty())?;
+                Some(format!("<meta {}=\"{}\" content=\"{}\">\n", attr, name, escape_html(content)))
+            })
+            .collect()
+    }
+
+    /// The schema.org data of the page as JSON: a `BlogPosting` with its headline, dates, author,
+    /// image and description, or for the home page (`og_type` of `website`) a `WebSite` and the
+    /// `Organization` publishing it.
+    ///
+    /// `datePublished` is the `date` metadata and `dateModified` the `lastmod` one, falling back to
+    /// the publication date. The authors of the page (see [`Meta::author_names`]) are `Person`s linking to
+    /// their author pages, a list when there are several. Fields without a value are left out, and `</` is
+    /// escaped as `<\/` so the JSON can be written into a `<script>` as-is.
+    ///
+    /// With `breadcrumbs` set in `config`, a page with `breadcrumbs` (see [`crate::nav::PageNav`]) also
+    /// gets a `BreadcrumbList` of them ending with the page itself, next to its `BlogPosting`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::{nav::Breadcrumb, parse_front_matter, seo::{Seo, SeoConfig}, urls::UrlResolver};
+    /// let urls = UrlResolver::new("https://example.com", "/");
+    /// let meta = parse_front_matter("date: 2024-05-01\nauthor: Ana").unwrap();
+    /// let seo = Seo::new("Cats", &meta, "/cats.html", false, None, &urls, None);
+    /// let json: serde_json::Value = serde_json::from_str(&seo.json_ld(&meta, &[], &urls, None)).unwrap();
+    /// assert_eq!(json["@type"], "BlogPosting");
+    /// assert_eq!(json["author"]["name"], "Ana");
+    /// assert_eq!(json["author"]["url"], "https://example.com/authors/ana/");
+    /// assert_eq!(json["dateModified"], "2024-05-01T00:00:00+00:00");
+    /// assert!(json.get("image").is_none());
+    ///
+    /// let home = Breadcrumb { title: "Home".to_string(), href: "/".to_string() };
+    /// let config = SeoConfig { breadcrumbs: true, ..Default::default() };
+    /// let json: serde_json::Value = serde_json::from_str(&seo.json_ld(&meta, &[home], &urls, Some(&config))).unwrap();
+    /// assert_eq!(json[1]["@type"], "BreadcrumbList");
+    /// assert_eq!(json[1]["itemListElement"][1]["item"], "https://example.com/cats.html");
+    /// ```
+    pub fn json_ld(&self, meta: &Meta, breadcrumbs: &[Breadcrumb], urls: &UrlResolver, config: Option<&SeoConfig>) -> String {
+        let value = if self.og_type == "website" {
+            let site_url = urls.url("");
+            let name = self.site_name.clone().unwrap_or_else(|| self.title.clone());
+            let logo = config.and_then(|config| config.logo.as_deref()).map(|logo| urls.absolute_url(logo));
+            json!([
+                without_nulls(json!({
+                    "@context": "https://schema.org",
+                    "@type": "WebSite",
+                    "name": name,
+                    "url": site_url,
+                    "description": self.description,
+                })),
+                without_nulls(json!({
+                    "@context": "https://schema.org",
+                    "@type": "Organization",
+                    "name": name,
+                    "url": site_url,
+                    "logo": logo,
+                })),
+            ])
+        } else {
+            let published = meta.date.as_deref().and_then(parse_date).map(|date| date.to_rfc3339());
+            let modified = meta.lastmod.as_deref().and_then(parse_date).map(|date| date.to_rfc3339());
+            let mut authors: Vec<Value> = meta
+                .author_names()
+                .iter()
+                .map(|name| json!({ "@type": "Person", "name": name, "url": urls.absolute_url(&author_href(name, urls)) }))
+                .collect();
+            let author = match authors.len() {
+                0 => config.and_then(|config| config.author.as_deref()).map(|name| json!({ "@type": "Person", "name": name })),
+                1 => authors.pop(),
+                _ => Some(Value::Array(authors)),
+            };
+            without_nulls(json!({
+                "@context": "https://schema.org",
+                "@type": "BlogPosting",
+                "headline": self.title,
+                "mainEntityOfPage": self.url,
+                "datePublished": published,
+                "dateModified": modified.or_else(|| published.clone()),
+                "author": author,
+                "image": self.image,
+                "description": self.description,
+            }))
+        };
+        let value = if config.is_some_and(|config| config.breadcrumbs) && !breadcrumbs.is_empty() {
+            let mut values = match value {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            values.push(self.breadcrumb_list(breadcrumbs, urls));
+            Value::Array(values)
+        } else {
+            value
+        };
+        value.to_string().replace("</", "<\\/")
+    }
+
+    /// The `BreadcrumbList` of `breadcrumbs` followed by the page itself.
+    fn breadcrumb_list(&self, breadcrumbs: &[Breadcrumb], urls: &UrlResolver) -> Value {
+        let items: Vec<_> = breadcrumbs
+            .iter()
+            .map(|crumb| (crumb.title.as_str(), urls.absolute_url(&crumb.href)))
+            .chain([(self.title.as_str(), self.url.clone())])
+            .enumerate()
+            .map(|(i, (name, item))| json!({ "@type": "ListItem", "position": i + 1, "name": name, "item": item }))
+            .collect();
+        json!({
+            "@context": "https://schema.org",
+            "@type": "BreadcrumbList",
+            "itemListElement": items,
+        })
+    }
+}
+
+/// Drop the `null` fields of a JSON object.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter().filter(|(_, v)| !v.is_null()).collect::<Map<_, _>>()),
+        other => other,
+    }
+}
+
+/// Wrap `json_ld` (see [`Seo::json_ld`]) in a `<script type="application/ld+json">`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::seo::json_ld_script;
+/// assert_eq!(
+///     json_ld_script(r#"{"@type":"WebSite"}"#),
+///     r#"<script type="application/ld+json">{"@type":"WebSite"}</script>"#
+/// );
+/// ```
+pub fn json_ld_script(json_ld: &str) -> String {
+    format!("<script type=\"application/ld+json\">{}</script>", json_ld)
+}
@@ -0,0 +1,83 @@
+use std::{collections::BTreeMap, fs, path::Path};
+use minijinja::{Environment, context};
+
+use crate::{PageSummary, error::SsgError, nav::PageRef, slugify, urls::UrlResolver};
+
+/// Group the pages of `pages` declaring a `series` by series name, each series listing its parts
+/// from the oldest to the newest (undated parts in source order).
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{PageSummary, series::collect_series};
+/// let page = |title: &str, series: Option<&str>| PageSummary {
+///     title: title.to_string(),
+///     href: format!("/{}.html", title),
+///     tags: Vec::new(),
+///     description: None,
+///     summary: String::new(),
+///     series: series.map(str::to_string),
+///     date: None,
+///     lang: None,
+///     translation_key: None,
+///     draft: false,
+///     source_path: format!("{}.md", title).into(),
+///     output_path: format!("{}.html", title).into(),
+/// };
+/// let series = collect_series(&[page("2-pin", Some("Async")), page("1-futures", Some("Async")), page("other", None)]);
+/// let parts: Vec<_> = series["Async"].iter().map(|p| p.title.as_str()).collect();
+/// assert_eq!(parts, vec!["1-futures", "2-pin"]);
+/// assert_eq!(series.len(), 1);
+/// ```
+pub fn collect_series(pages: &[PageSummary]) -> BTreeMap<String, Vec<PageRef>> {
+    let mut members: BTreeMap<&str, Vec<&PageSummary>> = BTreeMap::new();
+    for page in pages {
+        if let Some(name) = &page.series {
+            members.entry(name).or_default().push(page);
+        }
+    }
+    members
+        .into_iter()
+        .map(|(name, mut parts)| {
+            parts.sort_by_key(|page| (page.date, &page.source_path));
+            (name.to_string(), parts.into_iter().map(PageRef::from).collect())
+        })
+        .collect()
+}
+
+/// Site-absolute href of the landing page of the series `name`.
+pub fn series_href(name: &str, urls: &UrlResolver) -> String {
+    urls.href(&format!("series/{}/", slugify(name)))
+}
+
+/// Render `series/<slug>/index.html` for every series through the `series.html` template, which
+/// receives `{ title, series, pages: [{ title, href, summary }] }` with the parts in order.
+///
+/// Returns the output paths relative to `dist_path` (e.g. `series/rust-async/index.html`) so they
+/// can be added to the sitemap.
+pub fn write_series_pages(
+    dist_path: &Path,
+    series: &BTreeMap<String, Vec<PageRef>>,
+    env: &Environment,
+) -> Result<Vec<String>, SsgError> {
+    let tmpl = env.get_template("series.html").map_err(SsgError::template("series.html"))?;
+    let mut written = Vec::new();
+
+    for (name, parts) in series {
+        let rendered = tmpl
+            .render(context! {
+                title => name,
+                series => name,
+                pages => parts,
+            })
+            .map_err(SsgError::template("series.html"))?;
+
+        let rel = format!("series/{}/index.html", slugify(name));
+        let dest = dist_path.join(&rel);
+        fs::create_dir_all(dest.parent().unwrap()).map_err(SsgError::io(dest.parent().unwrap()))?;
+        fs::write(&dest, rendered).map_err(SsgError::io(&dest))?;
+        written.push(rel);
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use minijinja::{Environment, Value, context};
+use regex::{Captures, Regex};
+
+use crate::replace_outside_code_fences;
+
+/// Template directory, relative to the templates root, holding one `<name>.html` per shortcode.
+pub const SHORTCODES_DIR: &str = "shortcodes";
+
+/// Expand every `{{< name args >}}` shortcode in `md` with the `shortcodes/<name>.html` template.
+///
+/// Arguments are separated by whitespace and may be quoted. `key=value` arguments become
+/// top-level template variables; the others are passed in order as `args`. Shortcodes inside
+/// fenced code blocks are left alone. A shortcode whose template is missing or fails to render
+/// is an error with `strict`; otherwise the error is printed and the shortcode left as is.
+///
+/// # Examples
+///
+/// ```
+/// use minijinja::Environment;
+/// use ssg_generator_utils::shortcodes::expand_shortcodes;
+/// let mut env = Environment::new();
+/// env.add_template("shortcodes/youtube.html", r#"<iframe src="https://youtube.com/embed/{{ id }}" title="{{ args[0] }}"></iframe>"#).unwrap();
+/// let md = "Watch:\n\n{{< youtube \"My talk\" id=abc123 >}}\n\n```\n{{< youtube id=raw >}}\n```\n";
+/// let expanded = expand_shortcodes(md, &env, false).unwrap();
+/// assert!(expanded.contains(r#"<iframe src="https://youtube.com/embed/abc123" title="My talk"></iframe>"#));
+/// assert!(expanded.contains("{{< youtube id=raw >}}"));
+/// assert!(expand_shortcodes("{{< missing >}}", &env, true).is_err());
+/// ```
+pub fn expand_shortcodes(md: &str, env: &Environment, strict: bool) -> Result<String, minijinja::Error> {
+    if !md.contains("{{<") {
+        return Ok(md.to_string());
+    }
+    let re = Regex::new(r"\{\{<\s*([A-Za-z0-9_-]+)\s*(.*?)\s*>\}\}").unwrap();
+    let mut first_error = None;
+    let expanded = replace_outside_code_fences(md, |line| {
+        re.replace_all(line, |caps: &Captures| {
+            render(env, caps).unwrap_or_else(|e| {
+                tracing::warn!(category = "shortcode", "Failed to expand shortcode `{}`: {}", &caps[1], e);
+                first_error.get_or_insert(e);
+                caps[0].to_string()
+            })
+        })
+    });
+    match first_error {
+        Some(e) if strict => Err(e),
+        _ => Ok(expanded),
+    }
+}
+
+fn render(env: &Environment, caps: &Captures) -> Result<String, minijinja::Error> {
+    let name = &caps[1];
+    let mut named = BTreeMap::new();
+    let mut positional = Vec::new();
+    for arg in split_args(&caps[2]) {
+        match arg.split_once('=') {
+            Some((key, value)) if !key.starts_with(['"', '\'']) => {
+                named.insert(key.to_string(), unquote(value).to_string());
+            }
+            _ => positional.push(unquote(&arg).to_string()),
+        }
+    }
+
+    env.get_template(&format!("{}/{}.html", SHORTCODES_DIR, name))
+        .and_then(|tmpl| {
+            tmpl.render(context! {
+                name => name,
+                args => positional,
+                ..Value::from_serialize(&named)
+            })
+        })
+}
+
+/// Split `args` on whitespace outside of quotes.
+fn split_args(args: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for c in args.chars() {
+        match c {
+            '"' | '\'' if quote == Some(c) => {
+                quote = None;
+                current.push(c);
+            }
+            '"' | '\'' if quote.is_none() => {
+                quote = Some(c);
+                current.push(c);
+            }
+            c if c.is_whitespace() && quote.is_none() => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+fn unquote(value: &str) -> &str {
+    for q in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(q).and_then(|v| v.strip_suffix(q)) {
+            return inner;
+        }
+    }
+    value
+}
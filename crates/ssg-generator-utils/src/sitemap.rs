@@ -1,26 +1,239 @@
+use chrono::{DateTime, FixedOffset, SecondsFormat};
 use quick_xml::Writer;
 use quick_xml::events::{Event, BytesStart};
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{Cursor, Write};
+use std::path::Path;
 
-pub fn write_sitemap(urls: &[&str], output: &str) -> std::io::Result<()> {
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
-    writer.write_event(Event::Decl(quick_xml::events::BytesDecl::new("1.0", Some("UTF-8"), None)))?;
-    let mut urlset = BytesStart::new("urlset");
-    urlset.push_attribute(("xmlns", "http://www.sitemaps.org/schemas/sitemap/0.9"));
-    writer.write_event(Event::Start(urlset))?;
+use crate::{i18n::Alternate, urls::UrlResolver};
+
+/// Most URLs a single sitemap may list.
+pub const MAX_URLS: usize = 50_000;
+
+/// Largest size of a single uncompressed sitemap, in bytes.
+pub const MAX_BYTES: usize = 50 * 1024 * 1024;
+
+const XMLNS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+const XMLNS_XHTML: &str = "http://www.w3.org/1999/xhtml";
+
+/// How often a page is expected to change, as hinted to crawlers by `<changefreq>`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeFreq {
+    Always,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Never,
+}
+
+impl ChangeFreq {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeFreq::Always => "always",
+            ChangeFreq::Hourly => "hourly",
+            ChangeFreq::Daily => "daily",
+            ChangeFreq::Weekly => "weekly",
+            ChangeFreq::Monthly => "monthly",
+            ChangeFreq::Yearly => "yearly",
+            ChangeFreq::Never => "never",
+        }
+    }
+}
+
+/// A `<url>` of the sitemap; only `loc` is required.
+///
+/// The build lists every page without `noindex: true` or `exclude_from_sitemap: true` at its href,
+/// dated by its `lastmod` metadata, or else the last git commit touching its source, or else its date,
+/// with its `changefreq` and `priority` metadata and its translations as `alternates` (see
+/// [`crate::i18n::alternates`]), and then the tag, series, author and section pages.
+#[derive(Debug, Clone, Default)]
+pub struct SitemapEntry {
+    /// Absolute URL of the page
+    pub loc: String,
+    pub lastmod: Option<DateTime<FixedOffset>>,
+    pub changefreq: Option<ChangeFreq>,
+    /// Priority relative to the other pages of the site, clamped to `0.0..=1.0`
+    pub priority: Option<f32>,
+    /// Language versions of the page, written as `<xhtml:link rel="alternate" hreflang>` (see
+    /// [`crate::i18n::alternates`])
+    pub alternates: Vec<Alternate>,
+}
+
+/// Write `entries` to `output` as a sitemap, omitting the optional elements that are not set and
+/// declaring the `xhtml` namespace only when an entry has alternates.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{i18n::Alternate, parse_date, sitemap::{write_sitemap, ChangeFreq, SitemapEntry}};
+/// let out = std::env::temp_dir().join("sitemap-doctest.xml");
+/// let entries = [
+///     SitemapEntry {
+///         loc: "https://example.com/post.html".to_string(),
+///         lastmod: parse_date("2024-05-01"),
+///         changefreq: Some(ChangeFreq::Monthly),
+///         priority: Some(0.8),
+///         alternates: Vec::new(),
+///     },
+///     SitemapEntry { loc: "https://example.com/tags/".to_string(), ..Default::default() },
+///     SitemapEntry {
+///         loc: "https://example.com/es/".to_string(),
+///         alternates: vec![Alternate { hreflang: "es".to_string(), url: "https://example.com/es/".to_string() }],
+///         ..Default::default()
+///     },
+/// ];
+/// write_sitemap(&entries, out.to_str().unwrap()).unwrap();
+/// let xml = std::fs::read_to_string(&out).unwrap();
+/// assert!(xml.contains(r#"<xhtml:link rel="alternate" hreflang="es" href="https://example.com/es/"/>"#));
+/// assert!(xml.contains("<lastmod>2024-05-01T00:00:00Z</lastmod><changefreq>monthly</changefreq><priority>0.8</priority>"));
+/// assert!(xml.contains("<url><loc>https://example.com/tags/</loc></url>"));
+/// ```
+pub fn write_sitemap(entries: &[SitemapEntry], output: &str) -> std::io::Result<()> {
+    let urls = entries.iter().map(url_element).collect::<std::io::Result<Vec<_>>>()?;
+    write_set(Path::new(output), "urlset", &urls, has_alternates(entries))
+}
+
+/// Write `entries` under `dist_path` as `sitemap.xml`, or, when they do not fit in one sitemap,
+/// as `sitemap-1.xml`, `sitemap-2.xml`, ... listed by a `sitemap_index.xml`.
+///
+/// A sitemap holds at most `chunk_size` URLs (default and maximum [`MAX_URLS`]) and [`MAX_BYTES`].
+/// The index points at the parts through `urls` and gives each the latest `lastmod` of its entries.
+/// Returns the files written, relative to `dist_path`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{sitemap::{write_sitemaps, SitemapEntry}, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("sitemaps-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// let urls = UrlResolver::new("https://example.com", "/blog/");
+/// let entries: Vec<_> = (1..=3)
+///     .map(|n| SitemapEntry { loc: urls.url(&format!("post-{}.html", n)), ..Default::default() })
+///     .collect();
+/// assert_eq!(write_sitemaps(&entries, &dist, &urls, None).unwrap(), ["sitemap.xml"]);
+///
+/// let written = write_sitemaps(&entries, &dist, &urls, Some(2)).unwrap();
+/// assert_eq!(written, ["sitemap-1.xml", "sitemap-2.xml", "sitemap_index.xml"]);
+/// let index = std::fs::read_to_string(dist.join("sitemap_index.xml")).unwrap();
+/// assert!(index.contains("<sitemap><loc>https://example.com/blog/sitemap-2.xml</loc></sitemap>"));
+/// ```
+pub fn write_sitemaps(
+    entries: &[SitemapEntry],
+    dist_path: &Path,
+    urls: &UrlResolver,
+    chunk_size: Option<usize>,
+) -> std::io::Result<Vec<String>> {
+    let max_urls = chunk_size.unwrap_or(MAX_URLS).clamp(1, MAX_URLS);
+    // Leaves room for the declaration and the enclosing element
+    let max_bytes = MAX_BYTES - 1024;
+    let xhtml = has_alternates(entries);
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut size = 0;
+    for entry in entries {
+        let element = url_element(entry)?;
+        match chunks.last_mut() {
+            Some(chunk) if chunk.urls.len() < max_urls && size + element.len() <= max_bytes => {
+                chunk.lastmod = chunk.lastmod.max(entry.lastmod);
+                size += element.len();
+                chunk.urls.push(element);
+            }
+            _ => {
+                size = element.len();
+                chunks.push(Chunk { urls: vec![element], lastmod: entry.lastmod });
+            }
+        }
+    }
+
+    if chunks.len() <= 1 {
+        let urls = chunks.pop().map(|chunk| chunk.urls).unwrap_or_default();
+        write_set(&dist_path.join("sitemap.xml"), "urlset", &urls, xhtml)?;
+        return Ok(vec!["sitemap.xml".to_string()]);
+    }
+    let mut written = Vec::new();
+    let mut sitemaps = Vec::new();
+    for (n, Chunk { urls: chunk, lastmod }) in chunks.iter().enumerate() {
+        let name = format!("sitemap-{}.xml", n + 1);
+        write_set(&dist_path.join(&name), "urlset", chunk, xhtml)?;
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        writer.write_event(Event::Start(BytesStart::new("sitemap")))?;
+        write_element(&mut writer, "loc", &urls.url(&name))?;
+        if let Some(lastmod) = lastmod {
+            write_element(&mut writer, "lastmod", &lastmod.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+        }
+        writer.write_event(Event::End(quick_xml::events::BytesEnd::new("sitemap")))?;
+        sitemaps.push(writer.into_inner().into_inner());
+        written.push(name);
+    }
+    write_set(&dist_path.join("sitemap_index.xml"), "sitemapindex", &sitemaps, false)?;
+    written.push("sitemap_index.xml".to_string());
+    Ok(written)
+}
+
+/// The `<url>` elements of one sitemap, with the latest `lastmod` among them.
+struct Chunk {
+    urls: Vec<Vec<u8>>,
+    lastmod: Option<DateTime<FixedOffset>>,
+}
 
-    for url in urls {
-        writer.write_event(Event::Start(BytesStart::new("url")))?;
-        writer.write_event(Event::Start(BytesStart::new("loc")))?;
-        writer.write_event(Event::Text(quick_xml::events::BytesText::from_escaped(*url)))?;
-        writer.write_event(Event::End(quick_xml::events::BytesEnd::new("loc")))?;
-        writer.write_event(Event::End(quick_xml::events::BytesEnd::new("url")))?;
+/// The `<url>` element of `entry`, omitting the optional elements that are not set.
+fn url_element(entry: &SitemapEntry) -> std::io::Result<Vec<u8>> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer.write_event(Event::Start(BytesStart::new("url")))?;
+    write_element(&mut writer, "loc", &entry.loc)?;
+    if let Some(lastmod) = entry.lastmod {
+        write_element(&mut writer, "lastmod", &lastmod.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+    }
+    if let Some(changefreq) = entry.changefreq {
+        write_element(&mut writer, "changefreq", changefreq.as_str())?;
+    }
+    if let Some(priority) = entry.priority {
+        write_element(&mut writer, "priority", &format!("{:.1}", priority.clamp(0.0, 1.0)))?;
+    }
+    for alternate in &entry.alternates {
+        let mut link = BytesStart::new("xhtml:link");
+        link.push_attribute(("rel", "alternate"));
+        link.push_attribute(("hreflang", alternate.hreflang.as_str()));
+        link.push_attribute(("href", alternate.url.as_str()));
+        writer.write_event(Event::Empty(link))?;
     }
+    writer.write_event(Event::End(quick_xml::events::BytesEnd::new("url")))?;
+    Ok(writer.into_inner().into_inner())
+}
 
-    writer.write_event(Event::End(quick_xml::events::BytesEnd::new("urlset")))?;
+/// Whether any of `entries` lists alternates, which need the `xhtml` namespace.
+fn has_alternates(entries: &[SitemapEntry]) -> bool {
+    entries.iter().any(|entry| !entry.alternates.is_empty())
+}
+
+/// Write `children`, already serialized, inside a `root` element in the sitemap namespace, and in
+/// the `xhtml` one too with `xhtml`.
+fn write_set(output: &Path, root: &str, children: &[Vec<u8>], xhtml: bool) -> std::io::Result<()> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer.write_event(Event::Decl(quick_xml::events::BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    let mut set = BytesStart::new(root);
+    set.push_attribute(("xmlns", XMLNS));
+    if xhtml {
+        set.push_attribute(("xmlns:xhtml", XMLNS_XHTML));
+    }
+    writer.write_event(Event::Start(set))?;
+    for child in children {
+        writer.get_mut().write_all(child)?;
+    }
+    writer.write_event(Event::End(quick_xml::events::BytesEnd::new(root)))?;
 
     let result = writer.into_inner().into_inner();
     let mut file = File::create(output)?;
     file.write_all(&result)
 }
+
+fn write_element(writer: &mut Writer<Cursor<Vec<u8>>>, name: &str, text: &str) -> std::io::Result<()> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    writer.write_event(Event::Text(quick_xml::events::BytesText::new(text)))?;
+    writer.write_event(Event::End(quick_xml::events::BytesEnd::new(name)))?;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::urls::UrlResolver;
+
+/// How the service worker fills its cache.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheStrategy {
+    /// Download every matching output when the worker installs, so the whole site works offline
+    #[default]
+    Precache,
+    /// Only cache what visitors fetch, as they fetch it
+    Runtime,
+}
+
+/// Service worker settings, read from the `service_worker` block of `cats-ssg.json`.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct ServiceWorkerConfig {
+    #[serde(default)]
+    pub strategy: CacheStrategy,
+    /// Extensions of the outputs precached with [`CacheStrategy::Precache`] (default: html, css and js)
+    pub precache_extensions: Option<Vec<String>>,
+}
+
+/// Outputs precached when no `precache_extensions` are configured.
+const DEFAULT_PRECACHE_EXTENSIONS: &[&str] = &["html", "css", "js"];
+
+/// Where the service worker is written, relative to the output directory; at the root so it
+/// controls the whole site.
+pub const SW_PERMALINK: &str = "sw.js";
+
+/// `<script>` registering the service worker of the site, handed to templates as `sw_register`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{sw::register_snippet, urls::UrlResolver};
+/// assert_eq!(
+///     register_snippet(&UrlResolver::new("https://example.com", "/blog/")),
+///     r#"<script>if ("serviceWorker" in navigator) navigator.serviceWorker.register("/blog/sw.js");</script>"#
+/// );
+/// ```
+pub fn register_snippet(urls: &UrlResolver) -> String {
+    format!(
+        "<script>if (\"serviceWorker\" in navigator) navigator.serviceWorker.register({});</script>",
+        serde_json::to_string(&urls.href(SW_PERMALINK)).unwrap_or_default()
+    )
+}
+
+/// Write `sw.js` to `dist_path`, precaching the `outputs` (relative to `dist_path`) whose extension
+/// the strategy of `config` selects.
+///
+/// The worker (see `src/sw.js`) answers same-origin `GET` requests from its cache while refreshing
+/// it from the network, and falls back to the network for anything it has not cached yet. Its cache is named
+/// after a hash of the precached files, so any change to them replaces the whole cache on the next
+/// visit.
+///
+/// Returns the precached hrefs.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{sw::{write_service_worker, ServiceWorkerConfig}, urls::UrlResolver};
+/// let dist = std::env::temp_dir().join("sw-doctest");
+/// std::fs::create_dir_all(&dist).unwrap();
+/// std::fs::write(dist.join("index.html"), "<p>Hi</p>").unwrap();
+/// let outputs = ["index.html".to_string(), "cat.png".to_string()].into();
+/// let urls = UrlResolver::new("https://example.com", "/").with_pretty_urls(true);
+/// let precached = write_service_worker(&dist, &outputs, &ServiceWorkerConfig::default(), &urls).unwrap();
+/// assert_eq!(precached, vec!["/"]);
+/// assert!(std::fs::read_to_string(dist.join("sw.js")).unwrap().contains(r#"const PRECACHE = ["/"];"#));
+/// ```
+pub fn write_service_worker(
+    dist_path: &Path,
+    outputs: &BTreeSet<String>,
+    config: &ServiceWorkerConfig,
+    urls: &UrlResolver,
+) -> std::io::Result<Vec<String>> {
+    let extensions: Vec<&str> = match &config.precache_extensions {
+        Some(extensions) => extensions.iter().map(String::as_str).collect(),
+        None => DEFAULT_PRECACHE_EXTENSIONS.to_vec(),
+    };
+    let mut hasher = DefaultHasher::new();
+    let mut precached = Vec::new();
+    if config.strategy == CacheStrategy::Precache {
+        for rel in outputs.iter().filter(|rel| rel.as_str() != SW_PERMALINK) {
+            let ext = Path::new(rel).extension().and_then(|e| e.to_str()).unwrap_or("");
+            if !extensions.contains(&ext) {
+                continue;
+            }
+            let href = urls.href(rel);
+            (&href, fs::read(dist_path.join(rel))?).hash(&mut hasher);
+            precached.push(href);
+        }
+    }
+
+    let script = include_str!("sw.js")
+        .replace("__CACHE__", &format!("ssg-{:016x}", hasher.finish()))
+        .replace("__PRECACHE__", &serde_json::to_string(&precached).unwrap_or_default());
+    fs::write(dist_path.join(SW_PERMALINK), script)?;
+    Ok(precached)
+}
@@ -0,0 +1,150 @@
+use std::{collections::BTreeMap, fs, path::Path};
+use minijinja::{Environment, context};
+
+use crate::{error::SsgError, feed, slugify, urls::UrlResolver};
+
+/// A tag and the pages declaring it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagPages {
+    /// Spelling of the tag on the first page declaring it
+    pub name: String,
+    /// The pages as `(title, href)` pairs sorted by title
+    pub pages: Vec<(String, String)>,
+}
+
+/// Group `(title, href, tags)` triples by tag.
+///
+/// Tags are keyed by their slug (see [`slugify`]) and kept in its alphabetical order, so spellings
+/// differing only in case or punctuation share one page, named as on the first page declaring the
+/// tag. Tags without any letter or digit have no slug and are left out.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::tags::collect_tags;
+/// let pages = vec![
+///     ("B".to_string(), "/b.html".to_string(), vec!["Rust".to_string(), "++".to_string()]),
+///     ("A".to_string(), "/a.html".to_string(), vec!["rust".to_string(), "web".to_string()]),
+/// ];
+/// let tags = collect_tags(pages);
+/// assert_eq!(tags["rust"].name, "Rust");
+/// assert_eq!(tags["rust"].pages, vec![("A".to_string(), "/a.html".to_string()), ("B".to_string(), "/b.html".to_string())]);
+/// assert_eq!(tags["web"].pages.len(), 1);
+/// assert_eq!(tags.len(), 2);
+/// ```
+pub fn collect_tags(pages: impl IntoIterator<Item = (String, String, Vec<String>)>) -> BTreeMap<String, TagPages> {
+    let mut tags: BTreeMap<String, TagPages> = BTreeMap::new();
+    for (title, href, page_tags) in pages {
+        for tag in page_tags {
+            let slug = slugify(&tag);
+            if slug.is_empty() {
+                continue;
+            }
+            let entry = tags.entry(slug).or_insert_with(|| TagPages { name: tag, pages: Vec::new() });
+            entry.pages.push((title.clone(), href.clone()));
+        }
+    }
+    for tag in tags.values_mut() {
+        tag.pages.sort();
+        tag.pages.dedup();
+    }
+    tags
+}
+
+/// Path of the feed of `tag` in the language directory `dir` (empty for the default language),
+/// relative to the output directory.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::tags::tag_feed_permalink;
+/// assert_eq!(tag_feed_permalink("", "Rust Async"), "tags/rust-async/feed.xml");
+/// assert_eq!(tag_feed_permalink("es", "Rust"), "es/tags/rust/feed.xml");
+/// ```
+pub fn tag_feed_permalink(dir: &str, tag: &str) -> String {
+    in_dir(dir, &format!("tags/{}/feed.xml", slugify(tag)))
+}
+
+fn in_dir(dir: &str, rel: &str) -> String {
+    if dir.is_empty() { rel.to_string() } else { format!("{}/{}", dir, rel) }
+}
+
+/// Render `tags/<slug>/index.html` for every tag of `tags` (keyed by slug, see [`collect_tags`])
+/// plus a `tags/index.html` overview, under the language directory `dir` (see
+/// [`crate::i18n::PageLanguage::output_dir`]), which is empty for the default language.
+///
+/// Both kinds of page are rendered through `template_name`. Tag pages receive
+/// `{ title, tag, pages: [{ title, href }] }` and the overview receives
+/// `{ title, tags: [{ name, href, count }] }`, so the template can tell them apart with
+/// `tag is defined`. Both also get the href of the overview as `tags_href` and, outside the default
+/// language, `lang`. Every href is site-absolute, including the base path of `urls`.
+///
+/// With `feeds`, tag pages also receive the absolute URL of the feed of the tag as `feed_url` and
+/// its discovery `<link>` as `feed_link` (see [`tag_feed_permalink`]), which only lists the pages of
+/// the language; both are `none` otherwise.
+///
+/// Returns the output paths relative to `dist_path` (e.g. `tags/rust/index.html`) so they can
+/// be added to the sitemap.
+pub fn write_tag_pages(
+    dist_path: &Path,
+    dir: &str,
+    tags: &BTreeMap<String, TagPages>,
+    env: &Environment,
+    template_name: &str,
+    urls: &UrlResolver,
+    feeds: bool,
+) -> Result<Vec<String>, SsgError> {
+    let tmpl = env.get_template(template_name).map_err(SsgError::template(template_name))?;
+    let mut written = Vec::new();
+    let mut overview = Vec::new();
+    let tags_href = urls.href(&in_dir(dir, "tags/"));
+    let lang = (!dir.is_empty()).then_some(dir);
+
+    for (slug, TagPages { name: tag, pages }) in tags {
+        let items: Vec<_> = pages
+            .iter()
+            .map(|(title, href)| context! { title => title, href => href })
+            .collect();
+
+        let feed_url = feeds.then(|| urls.url(&tag_feed_permalink(dir, tag)));
+        let rendered = tmpl
+            .render(context! {
+                title => format!("Tagged: {}", tag),
+                tag => tag,
+                pages => items,
+                feed_link => feed_url.as_deref().map(|feed_url| feed::discovery_link(&format!("Tagged: {}", tag), feed_url)),
+                feed_url => feed_url,
+                tags_href => tags_href,
+                lang => lang,
+            })
+            .map_err(SsgError::template(template_name))?;
+
+        let rel = in_dir(dir, &format!("tags/{}/index.html", slug));
+        let dest = dist_path.join(&rel);
+        fs::create_dir_all(dest.parent().unwrap()).map_err(SsgError::io(dest.parent().unwrap()))?;
+        fs::write(&dest, rendered).map_err(SsgError::io(&dest))?;
+        written.push(rel);
+
+        overview.push(context! {
+            name => tag,
+            href => urls.href(&in_dir(dir, &format!("tags/{}/", slug))),
+            count => pages.len(),
+        });
+    }
+
+    let rendered = tmpl
+        .render(context! {
+            title => "Tags",
+            tags => overview,
+            tags_href => tags_href,
+            lang => lang,
+        })
+        .map_err(SsgError::template(template_name))?;
+    let rel = in_dir(dir, "tags/index.html");
+    let dest = dist_path.join(&rel);
+    fs::create_dir_all(dest.parent().unwrap()).map_err(SsgError::io(dest.parent().unwrap()))?;
+    fs::write(&dest, rendered).map_err(SsgError::io(&dest))?;
+    written.push(rel);
+
+    Ok(written)
+}
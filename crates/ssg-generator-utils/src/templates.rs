@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use minijinja::Error;
+
+/// Templates compiled into the binary, used when the templates directory lacks them.
+///
+/// Page templates (`base.html` unless a page names its `template`) receive `title`, `body`,
+/// `description`, `keywords` (with the tags too under `merge_tags_keywords: true`), `tags`,
+/// `page_slug`, the whole metadata as `meta` and every unknown metadata key as a top-level variable,
+/// along with:
+/// - `date`, from the `date` metadata or else the first git commit adding the source or its
+///   modification time (see [`crate::date_context`]), and `summary`, the text before a `<!--more-->`
+///   marker or else the first paragraph, standing in for a missing `description` (see [`crate::summarize`]);
+/// - `toc`, the headings of the page as `{ level, text, slug }`, `slug` being the `id` of the heading;
+/// - `canonical_url`, `seo` and `json_ld` (see [`crate::Meta::canonical_url`] and [`crate::seo::Seo`]);
+/// - `related`, `prev`, `next`, `breadcrumbs`, `menus` with the items leading to the page `active`, and
+///   with `series` metadata, the `name`, `href`, `parts` and `index` of the page in `series` (see
+///   [`crate::nav::site_nav`]);
+/// - `contributors`, the authors of the commits touching the source (see
+///   [`crate::authors::contributors`]), and `authors`, those of the page as `{ name, href }` (see
+///   [`crate::Meta::author_names`]);
+/// - `edit_url`, `microformats` and `comments` when enabled (see [`crate::PageOptions`]);
+/// - on a multilingual site, `lang`, the other versions of the page as `translations` of
+///   `{ lang, title, href }` and every version as `alternates` of `{ hreflang, url }` (see
+///   [`crate::i18n::alternates`]).
+///
+/// Every template gets the `site` global, with `domain`, `base_path`, `build_time` and every page as a
+/// [`crate::PageSummary`], the files of the data directory as `data`, the `menus`, `domain`,
+/// `base_path` and `default_lang` globals, those of the enabled features (`syntax_css`,
+/// `sw_register`, ...), the filters of [`crate::filters::register_filters`] and the `asset` function
+/// of [`crate::filters::register_asset_function`].
+pub const DEFAULT_TEMPLATES: &[(&str, &str)] = &[
+    ("author.html", include_str!("../templates/author.html")),
+    ("base.html", include_str!("../templates/base.html")),
+    ("content-index.html", include_str!("../templates/content-index.html")),
+    ("section.html", include_str!("../templates/section.html")),
+    ("series.html", include_str!("../templates/series.html")),
+    ("tag.html", include_str!("../templates/tag.html")),
+];
+
+/// Build a Minijinja loader that reads templates from `templates_path`, falling back to
+/// [`DEFAULT_TEMPLATES`] for names that do not exist there.
+///
+/// This lets a bare folder of Markdown build without any templates of its own, while any file in
+/// `templates_path` (including one named like a default) takes precedence.
+///
+/// # Examples
+///
+/// ```
+/// use minijinja::{Environment, context};
+/// use ssg_generator_utils::templates::loader;
+/// let mut env = Environment::new();
+/// env.set_loader(loader(std::env::temp_dir().join("no-templates-here")));
+/// let html = env.get_template("base.html").unwrap().render(context! {
+///     title => "Hello",
+///     body => "<p>Hi</p>",
+///     base_path => "/",
+/// }).unwrap();
+/// assert!(html.contains("<title>Hello</title>"));
+/// assert!(html.contains("<p>Hi</p>"));
+/// ```
+pub fn loader(templates_path: impl AsRef<Path>) -> impl Fn(&str) -> Result<Option<String>, Error> + Send + Sync + 'static {
+    let from_dir = minijinja::path_loader(templates_path);
+    move |name| {
+        if let Some(source) = from_dir(name)? {
+            return Ok(Some(source));
+        }
+        Ok(DEFAULT_TEMPLATES
+            .iter()
+            .find(|(default, _)| *default == name)
+            .map(|(_, source)| source.to_string()))
+    }
+}
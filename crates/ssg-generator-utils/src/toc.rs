@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{escape_html, slugify};
+
+/// Inline marker replaced by the rendered table of contents when it sits on its own paragraph.
+pub const TOC_MARKER: &str = "<p>[TOC]</p>";
+
+/// A heading of the page, in document order.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    /// Heading level, 1 to 6
+    pub level: u8,
+    /// Plain text of the heading
+    pub text: String,
+    /// `id` given to the heading, unique within the page
+    pub slug: String,
+}
+
+/// Hands out heading ids, suffixing repeats with `-1`, `-2`, ... like GitHub does.
+#[derive(Default)]
+pub struct SlugCounter {
+    seen: HashMap<String, usize>,
+}
+
+impl SlugCounter {
+    /// Slugify `text` and make the result unique among the slugs handed out so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ssg_generator_utils::toc::SlugCounter;
+    /// let mut slugs = SlugCounter::default();
+    /// assert_eq!(slugs.unique("Setup"), "setup");
+    /// assert_eq!(slugs.unique("Setup"), "setup-1");
+    /// assert_eq!(slugs.unique("!!!"), "section");
+    /// ```
+    pub fn unique(&mut self, text: &str) -> String {
+        let mut base = slugify(text);
+        if base.is_empty() {
+            base = "section".to_string();
+        }
+        let count = self.seen.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 { base.clone() } else { format!("{}-{}", base, count) };
+        *count += 1;
+        if slug != base {
+            self.seen.entry(slug.clone()).or_insert(1);
+        }
+        slug
+    }
+}
+
+/// Render `entries` as nested `<ul>` lists inside `<nav class="toc">`.
+///
+/// Nesting follows the heading levels relative to the shallowest heading, so a page starting at
+/// `h2` does not get an empty outer list.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::toc::{render_toc, TocEntry};
+/// let entries = vec![
+///     TocEntry { level: 2, text: "Intro".into(), slug: "intro".into() },
+///     TocEntry { level: 3, text: "A & B".into(), slug: "a-b".into() },
+/// ];
+/// assert_eq!(
+///     render_toc(&entries),
+///     r##"<nav class="toc"><ul><li><a href="#intro">Intro</a><ul><li><a href="#a-b">A &amp; B</a></li></ul></li></ul></nav>"##
+/// );
+/// ```
+pub fn render_toc(entries: &[TocEntry]) -> String {
+    let Some(min_level) = entries.iter().map(|e| e.level).min() else {
+        return String::new();
+    };
+    let mut html = String::from(r#"<nav class="toc">"#);
+    let mut depth = 0;
+    for entry in entries {
+        let level = (entry.level - min_level + 1) as usize;
+        if level > depth {
+            while depth < level {
+                html.push_str("<ul>");
+                depth += 1;
+                if depth < level {
+                    html.push_str("<li>");
+                }
+            }
+        } else {
+            html.push_str("</li>");
+            while depth > level {
+                html.push_str("</ul></li>");
+                depth -= 1;
+            }
+        }
+        html.push_str(&format!(
+            r##"<li><a href="#{}">{}</a>"##,
+            entry.slug,
+            escape_html(&entry.text)
+        ));
+    }
+    html.push_str("</li>");
+    while depth > 1 {
+        html.push_str("</ul></li>");
+        depth -= 1;
+    }
+    html.push_str("</ul></nav>");
+    html
+}
@@ -0,0 +1,241 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset};
+use regex::{Captures, Regex};
+
+use crate::{
+    Meta,
+    i18n::{I18nConfig, PageLanguage, page_language},
+};
+
+/// Builds every link of the site from its `domain`, its `base_path` and a permalink.
+///
+/// A permalink is a path relative to the output directory, such as `posts/foo.html` or
+/// `tags/rust/`. Hrefs are site-absolute (`/my-blog/posts/foo.html`) and URLs add the domain
+/// (`https://example.com/my-blog/posts/foo.html`). The base path is normalized to start and end
+/// with `/`, so `my-blog`, `/my-blog` and `/my-blog/` are the same site, and an empty one is `/`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::urls::UrlResolver;
+/// let urls = UrlResolver::new("https://example.com/", "my-blog");
+/// assert_eq!(urls.base_path(), "/my-blog/");
+/// assert_eq!(urls.href("posts/foo.html"), "/my-blog/posts/foo.html");
+/// assert_eq!(urls.url("tags/rust/"), "https://example.com/my-blog/tags/rust/");
+/// assert_eq!(urls.absolute_url("/my-blog/posts/foo.html"), "https://example.com/my-blog/posts/foo.html");
+/// assert_eq!(urls.absolute_url("posts/foo.html"), "https://example.com/my-blog/posts/foo.html");
+///
+/// let root = UrlResolver::new("https://example.com", "");
+/// assert_eq!(root.href("index.html"), "/index.html");
+/// ```
+#[derive(Debug, Clone)]
+pub struct UrlResolver {
+    domain: String,
+    base_path: String,
+    permalink: Option<String>,
+    pretty_urls: bool,
+    i18n: Option<I18nConfig>,
+}
+
+impl UrlResolver {
+    pub fn new(domain: &str, base_path: &str) -> Self {
+        let base_path = match base_path.trim_matches('/') {
+            "" => "/".to_string(),
+            trimmed => format!("/{}/", trimmed),
+        };
+        UrlResolver { domain: domain.trim_end_matches('/').to_string(), base_path, permalink: None, pretty_urls: false, i18n: None }
+    }
+
+    /// Place pages according to the permalink `pattern` (see [`UrlResolver::page_path`]); `None`
+    /// keeps the layout of the source tree.
+    pub fn with_permalink(mut self, pattern: Option<&str>) -> Self {
+        self.permalink = pattern.map(str::to_string);
+        self
+    }
+
+    /// Write every page as `<slug>/index.html` and link to its directory, so `/posts/foo/` is served
+    /// without the `.html` extension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use ssg_generator_utils::{parse_front_matter, urls::UrlResolver};
+    /// let urls = UrlResolver::new("https://example.com", "/blog/").with_pretty_urls(true);
+    /// let meta = parse_front_matter("title: Hi").unwrap();
+    /// let path = urls.page_path(Path::new("posts/foo.md"), &meta, None);
+    /// assert_eq!(path, Path::new("posts/foo/index.html"));
+    /// assert_eq!(urls.href("posts/foo/index.html"), "/blog/posts/foo/");
+    /// assert_eq!(urls.href("syntax.css"), "/blog/syntax.css");
+    /// ```
+    pub fn with_pretty_urls(mut self, pretty_urls: bool) -> Self {
+        self.pretty_urls = pretty_urls;
+        self
+    }
+
+    /// Tell the languages of pages apart by the first directory or suffix of their sources, and
+    /// write the pages of every language but the default one under a directory named after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use ssg_generator_utils::{i18n::I18nConfig, parse_front_matter, urls::UrlResolver};
+    /// let config = I18nConfig { languages: vec!["en".to_string(), "es".to_string()], default_language: None };
+    /// let urls = UrlResolver::new("https://example.com", "/").with_languages(Some(&config));
+    /// let meta = parse_front_matter("title: Hola").unwrap();
+    /// assert_eq!(urls.page_path(Path::new("about.es.md"), &meta, None), Path::new("es/about.html"));
+    /// assert_eq!(urls.page_path(Path::new("es/about.md"), &meta, None), Path::new("es/about.html"));
+    /// assert_eq!(urls.page_path(Path::new("en/about.md"), &meta, None), Path::new("about.html"));
+    /// ```
+    pub fn with_languages(mut self, config: Option<&I18nConfig>) -> Self {
+        self.i18n = config.cloned();
+        self
+    }
+
+    /// The languages of the site, when it has several.
+    pub fn i18n(&self) -> Option<&I18nConfig> {
+        self.i18n.as_ref()
+    }
+
+    /// The language of the page whose source is `rel_src` (relative to the content root), for a
+    /// site with several (see [`crate::i18n::page_language`]).
+    pub fn language(&self, rel_src: &Path) -> Option<PageLanguage> {
+        self.i18n.as_ref().map(|config| page_language(rel_src, config))
+    }
+
+    /// The domain, without a trailing `/`.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The base path, starting and ending with `/`.
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    /// Site-absolute href of `permalink`; with pretty URLs, `index.html` is left out.
+    pub fn href(&self, permalink: &str) -> String {
+        let permalink = permalink.trim_start_matches('/');
+        let permalink = match permalink.strip_suffix("index.html") {
+            Some(dir) if self.pretty_urls && (dir.is_empty() || dir.ends_with('/')) => dir,
+            _ => permalink,
+        };
+        format!("{}{}", self.base_path, permalink)
+    }
+
+    /// Full URL of `permalink`.
+    pub fn url(&self, permalink: &str) -> String {
+        format!("{}{}", self.domain, self.href(permalink))
+    }
+
+    /// Full URL of a site path given with or without the base path; URLs with a scheme are
+    /// returned unchanged.
+    pub fn absolute_url(&self, path: &str) -> String {
+        if path.contains("://") {
+            return path.to_string();
+        }
+        let permalink = path
+            .strip_prefix(self.base_path.trim_end_matches('/'))
+            .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+            .unwrap_or(path);
+        self.url(permalink)
+    }
+
+    /// Output path, relative to the output directory, of the page whose source is `rel_src`
+    /// (relative to the content root).
+    ///
+    /// The page's `permalink` metadata, or else the pattern given to [`UrlResolver::with_permalink`]
+    /// for pages other than the root `index.md`, is expanded with:
+    ///
+    /// - `:year`, `:month` and `:day` from `date`;
+    /// - `:slug`, the `page_slug` metadata or else the file stem (the directory name for `index.md`);
+    /// - `:section`, the first directory of the source, empty for pages at the root;
+    /// - `:path`, the directories of the source leading to the slug.
+    ///
+    /// Patterns ending with `/` produce an `index.html` in that directory, and `.html` is added to
+    /// those without an extension. Without any pattern the source tree is mirrored, with an
+    /// `index.md` that has a `page_slug` moved to a directory named after the slug. With
+    /// [`UrlResolver::with_pretty_urls`], any other `.html` page is moved to an `index.html` in a
+    /// directory named after it. With [`UrlResolver::with_languages`], the source path is taken
+    /// without its language, and the pages of other languages than the default one are placed under a
+    /// directory named after theirs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use ssg_generator_utils::{parse_front_matter, urls::UrlResolver};
+    /// let urls = UrlResolver::new("https://example.com", "/").with_permalink(Some("/:year/:month/:slug/"));
+    /// let date = chrono::DateTime::parse_from_rfc3339("2024-05-01T00:00:00Z").unwrap();
+    /// let meta = parse_front_matter("title: Hi").unwrap();
+    /// let path = urls.page_path(Path::new("posts/hello/index.md"), &meta, Some(&date));
+    /// assert_eq!(path, Path::new("2024/05/hello/index.html"));
+    ///
+    /// let meta = parse_front_matter("permalink: /:section/:slug").unwrap();
+    /// let path = urls.page_path(Path::new("posts/hello.md"), &meta, Some(&date));
+    /// assert_eq!(path, Path::new("posts/hello.html"));
+    ///
+    /// let mirror = UrlResolver::new("https://example.com", "/");
+    /// let meta = parse_front_matter("page_slug: hi").unwrap();
+    /// let path = mirror.page_path(Path::new("posts/hello/index.md"), &meta, Some(&date));
+    /// assert_eq!(path, Path::new("posts/hi/index.html"));
+    /// ```
+    pub fn page_path(&self, rel_src: &Path, meta: &Meta, date: Option<&DateTime<FixedOffset>>) -> PathBuf {
+        let language = self.language(rel_src);
+        let path = self.pattern_path(language.as_ref().map_or(rel_src, |language| &language.key), meta, date);
+        let is_page = path.extension().is_some_and(|ext| ext == "html");
+        let path = if self.pretty_urls && is_page && path.file_name().is_some_and(|f| f != "index.html") {
+            path.with_extension("").join("index.html")
+        } else {
+            path
+        };
+        match (language, &self.i18n) {
+            (Some(language), Some(config)) => language.output_dir(config).join(path),
+            _ => path,
+        }
+    }
+
+    fn pattern_path(&self, rel_src: &Path, meta: &Meta, date: Option<&DateTime<FixedOffset>>) -> PathBuf {
+        let is_index = rel_src.file_name().is_some_and(|f| f == "index.md");
+        // The directory holding an index.md is the page itself, not part of its path
+        let dir = rel_src.parent().unwrap_or(Path::new(""));
+        let (dir, stem) = match (is_index, dir.file_name()) {
+            (true, Some(name)) => (dir.parent().unwrap_or(Path::new("")), name.to_string_lossy()),
+            _ => (dir, rel_src.file_stem().unwrap_or_default().to_string_lossy()),
+        };
+        let slug = meta.page_slug.as_deref().unwrap_or(&stem);
+
+        // The site pattern leaves the home page where it is
+        let site_pattern = self.permalink.as_deref().filter(|_| rel_src != Path::new("index.md"));
+        let Some(pattern) = meta.permalink.as_deref().or(site_pattern) else {
+            return match &meta.page_slug {
+                Some(slug) if is_index => dir.join(slug).join("index.html"),
+                _ => rel_src.with_extension("html"),
+            };
+        };
+
+        let dir = dir.to_string_lossy().replace('\\', "/");
+        let section = dir.split('/').next().unwrap_or("");
+        let placeholder = Regex::new(r":([a-z]+)").unwrap();
+        let expanded = placeholder.replace_all(pattern, |caps: &Captures| match &caps[1] {
+            "year" => date.map(|d| d.format("%Y").to_string()).unwrap_or_default(),
+            "month" => date.map(|d| d.format("%m").to_string()).unwrap_or_default(),
+            "day" => date.map(|d| d.format("%d").to_string()).unwrap_or_default(),
+            "slug" => slug.to_string(),
+            "section" => section.to_string(),
+            "path" => dir.clone(),
+            _ => caps[0].to_string(),
+        });
+
+        // Empty placeholders leave `//` behind
+        let mut path: PathBuf = expanded.split('/').filter(|part| !part.is_empty()).collect();
+        if expanded.ends_with('/') || path.as_os_str().is_empty() {
+            path.push("index.html");
+        } else if path.extension().is_none() {
+            path.set_extension("html");
+        }
+        path
+    }
+}
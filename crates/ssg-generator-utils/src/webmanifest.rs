@@ -0,0 +1,116 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use image::{ImageError, imageops::FilterType};
+use serde::{Deserialize, Serialize};
+
+use crate::{escape_html, urls::UrlResolver};
+
+/// Where the web app manifest is written, relative to the output directory.
+pub const MANIFEST_PERMALINK: &str = "manifest.webmanifest";
+
+/// Icons rendered from the logo, as `(file name, size)`; the last two are listed in the manifest.
+const ICONS: &[(&str, u32)] = &[
+    ("favicon-16x16.png", 16),
+    ("favicon-32x32.png", 32),
+    ("apple-touch-icon.png", 180),
+    ("icon-192x192.png", 192),
+    ("icon-512x512.png", 512),
+];
+
+/// Settings for the web app manifest and icons, read from the `web_manifest` block of `cats-ssg.json`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WebManifestConfig {
+    /// Square image every icon is rendered from, ideally at least 512 pixels wide
+    pub logo: PathBuf,
+    pub name: String,
+    /// Name shown where space is limited, such as under a home screen icon
+    pub short_name: Option<String>,
+    pub description: Option<String>,
+    /// Color of the browser UI, also written as the `theme-color` meta tag
+    pub theme_color: Option<String>,
+    /// Color of the splash screen shown while the site loads as an installed app
+    pub background_color: Option<String>,
+    /// How an installed site is shown, such as `standalone` (the default) or `browser`
+    pub display: Option<String>,
+}
+
+/// `<link>` and `<meta>` tags pointing at the manifest and icons of [`write_web_manifest`], handed
+/// to templates as `manifest_tags`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::{urls::UrlResolver, webmanifest::{head_tags, WebManifestConfig}};
+/// let config = WebManifestConfig {
+///     logo: "logo.png".into(),
+///     name: "Cats".into(),
+///     short_name: None,
+///     description: None,
+///     theme_color: Some("#663399".into()),
+///     background_color: None,
+///     display: None,
+/// };
+/// let tags = head_tags(&config, &UrlResolver::new("https://example.com", "/blog/"));
+/// assert!(tags.starts_with(r#"<link rel="manifest" href="/blog/manifest.webmanifest">"#));
+/// assert!(tags.contains(r#"<link rel="apple-touch-icon" sizes="180x180" href="/blog/apple-touch-icon.png">"#));
+/// assert!(tags.ends_with(r##"<meta name="theme-color" content="#663399">"##));
+/// ```
+pub fn head_tags(config: &WebManifestConfig, urls: &UrlResolver) -> String {
+    let mut tags = vec![format!(r#"<link rel="manifest" href="{}">"#, escape_html(&urls.href(MANIFEST_PERMALINK)))];
+    for (file, size) in &ICONS[..2] {
+        tags.push(format!(
+            r#"<link rel="icon" type="image/png" sizes="{size}x{size}" href="{}">"#,
+            escape_html(&urls.href(file))
+        ));
+    }
+    tags.push(format!(
+        r#"<link rel="apple-touch-icon" sizes="180x180" href="{}">"#,
+        escape_html(&urls.href(ICONS[2].0))
+    ));
+    if let Some(color) = &config.theme_color {
+        tags.push(format!(r#"<meta name="theme-color" content="{}">"#, escape_html(color)));
+    }
+    tags.join("\n")
+}
+
+/// Render the icons of the site from the logo of `config` and write them to `dist_path`, along with
+/// a `manifest.webmanifest` describing the site as an installable app that starts at its home page.
+///
+/// Returns the output paths relative to `dist_path`.
+pub fn write_web_manifest(dist_path: &Path, config: &WebManifestConfig, urls: &UrlResolver) -> Result<Vec<String>, ImageError> {
+    let logo = image::open(&config.logo)?;
+    fs::create_dir_all(dist_path)?;
+    let mut written = Vec::new();
+    for (file, size) in ICONS {
+        logo.resize_to_fill(*size, *size, FilterType::Lanczos3)
+            .save_with_format(dist_path.join(file), image::ImageFormat::Png)?;
+        written.push(file.to_string());
+    }
+
+    let icons: Vec<_> = ICONS[3..]
+        .iter()
+        .map(|(file, size)| serde_json::json!({ "src": urls.href(file), "sizes": format!("{size}x{size}"), "type": "image/png" }))
+        .collect();
+    let mut manifest = serde_json::json!({
+        "name": config.name,
+        "short_name": config.short_name.as_ref().unwrap_or(&config.name),
+        "start_url": urls.href(""),
+        "scope": urls.href(""),
+        "display": config.display.as_deref().unwrap_or("standalone"),
+        "icons": icons,
+    });
+    for (key, value) in [
+        ("description", &config.description),
+        ("theme_color", &config.theme_color),
+        ("background_color", &config.background_color),
+    ] {
+        if let Some(value) = value {
+            manifest[key] = value.as_str().into();
+        }
+    }
+    fs::write(dist_path.join(MANIFEST_PERMALINK), serde_json::to_string_pretty(&manifest).unwrap_or_default())?;
+    written.push(MANIFEST_PERMALINK.to_string());
+    Ok(written)
+}
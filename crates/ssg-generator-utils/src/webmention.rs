@@ -0,0 +1,303 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use ureq::ResponseExt;
+
+use crate::{check::unescape, urls::UrlResolver};
+
+/// What the last [`send_webmentions`] knew of every page, written to its state file.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct State {
+    /// By source URL
+    pages: BTreeMap<String, PageState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct PageState {
+    /// Hash of the content of the page when its webmentions were last sent
+    hash: u64,
+    /// Outcome of the last webmention sent to every target the page linked to
+    targets: BTreeMap<String, Mention>,
+}
+
+/// Outcome of sending one webmention, recorded in the state file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Mention {
+    /// Endpoint the target advertised, if any
+    pub endpoint: Option<String>,
+    pub outcome: MentionOutcome,
+    /// Seconds since the Unix epoch
+    pub sent_at: u64,
+}
+
+/// How a webmention went.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MentionOutcome {
+    /// The endpoint accepted it with this status, such as `202`
+    Accepted { status: u16 },
+    /// The endpoint answered with an error status
+    Rejected { status: u16 },
+    /// The target advertises no endpoint
+    NoEndpoint,
+    /// The target or its endpoint could not be reached
+    Failed { reason: String },
+    /// Not sent: a dry run
+    Pending,
+}
+
+impl MentionOutcome {
+    /// Whether the mention never reached its target and is to be sent again.
+    pub fn is_failed(&self) -> bool {
+        matches!(self, MentionOutcome::Failed { .. })
+    }
+}
+
+/// A webmention from a page of the site (`source`) to a page it links to (`target`).
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SentMention {
+    pub source: String,
+    pub target: String,
+    #[serde(flatten)]
+    pub mention: Mention,
+}
+
+impl fmt::Display for SentMention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}: ", self.source, self.target)?;
+        match &self.mention.outcome {
+            MentionOutcome::Accepted { status } => write!(f, "accepted ({})", status),
+            MentionOutcome::Rejected { status } => write!(f, "rejected ({})", status),
+            MentionOutcome::NoEndpoint => write!(f, "no webmention endpoint"),
+            MentionOutcome::Failed { reason } => write!(f, "failed: {}", reason),
+            MentionOutcome::Pending => write!(f, "to send"),
+        }
+    }
+}
+
+/// Content of the page `html` that webmentions are sent for: its first `<article>`, or else its
+/// `<main>`, or else the whole page, so links of the navigation and footer are left out.
+fn content(html: &str) -> &str {
+    for (open, close) in [("<article", "</article>"), ("<main", "</main>")] {
+        if let Some(start) = html.find(open)
+            && let Some(end) = html.rfind(close)
+            && end > start
+        {
+            return &html[start..end];
+        }
+    }
+    html
+}
+
+/// The `http(s)` links of `html` that lead outside of the site at `site_url`, without their
+/// fragment and in order, each once.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::webmention::outbound_links;
+/// let html = r#"<nav><a href="https://elsewhere.example/">Nav</a></nav><article><a href="https://cats.example/post#top">Post</a> <a href="https://example.com/about/">About</a> <a href="https://cats.example/post">Again</a></article>"#;
+/// assert_eq!(outbound_links(html, "https://example.com/"), ["https://cats.example/post"]);
+/// ```
+pub fn outbound_links(html: &str, site_url: &str) -> Vec<String> {
+    let href_re = Regex::new(r#"(?i)<a\s[^>]*?\bhref\s*=\s*["']([^"']*)["']"#).unwrap();
+    let mut links = Vec::new();
+    for caps in href_re.captures_iter(content(html)) {
+        let href = unescape(&caps[1]);
+        let url = href.split('#').next().unwrap_or_default();
+        let external = url.starts_with("http://") || url.starts_with("https://");
+        if external && !url.starts_with(site_url) && !links.iter().any(|link| link == url) {
+            links.push(url.to_string());
+        }
+    }
+    links
+}
+
+/// `href` resolved against the URL `base`.
+///
+/// # Examples
+///
+/// ```
+/// use ssg_generator_utils::webmention::resolve_url;
+/// let base = "https://cats.example/posts/naps";
+/// assert_eq!(resolve_url(base, "/webmention"), "https://cats.example/webmention");
+/// assert_eq!(resolve_url(base, "mention?x=1"), "https://cats.example/posts/mention?x=1");
+/// assert_eq!(resolve_url(base, "//hooks.example/wm"), "https://hooks.example/wm");
+/// assert_eq!(resolve_url(base, ""), base);
+/// ```
+pub fn resolve_url(base: &str, href: &str) -> String {
+    let (scheme, rest) = base.split_once("://").unwrap_or(("https", base));
+    let origin = &base[..scheme.len() + 3 + rest.find('/').unwrap_or(rest.len())];
+    if href.is_empty() {
+        base.to_string()
+    } else if href.contains("://") {
+        href.to_string()
+    } else if let Some(rest) = href.strip_prefix("//") {
+        format!("{}://{}", scheme, rest)
+    } else if href.starts_with('/') {
+        format!("{}{}", origin, href)
+    } else if href.starts_with(['?', '#']) {
+        format!("{}{}", base.split(['?', '#']).next().unwrap_or(base), href)
+    } else {
+        let path = base.split(['?', '#']).next().unwrap_or(base);
+        let dir = path.rfind('/').filter(|&i| i >= origin.len()).map_or(format!("{}/", origin), |i| path[..=i].to_string());
+        format!("{}{}", dir, href)
+    }
+}
+
+/// The webmention endpoint `target` advertises: the first `rel="webmention"` of its `Link` headers,
+/// or else of its `<link>` and `<a>` elements, resolved against its URL after redirects.
+fn discover_endpoint(agent: &ureq::Agent, target: &str) -> Result<Option<String>, String> {
+    let mut response = agent.get(target).call().map_err(|e| e.to_string())?;
+    let base = response.get_uri().to_string();
+    let header_re = Regex::new(r#"<([^>]*)>\s*;[^,]*\brel\s*=\s*"?[^",]*\bwebmention\b"#).unwrap();
+    for value in response.headers().get_all("link") {
+        if let Some(caps) = value.to_str().ok().and_then(|value| header_re.captures(value)) {
+            return Ok(Some(resolve_url(&base, &caps[1])));
+        }
+    }
+    let is_html = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("html"));
+    if !is_html {
+        return Ok(None);
+    }
+    let html = response.body_mut().read_to_string().map_err(|e| e.to_string())?;
+    let element_re = Regex::new(r#"(?is)<(?:link|a)\s[^>]*>"#).unwrap();
+    let rel_re = Regex::new(r#"(?i)\brel\s*=\s*["']?[^"'>]*\bwebmention\b"#).unwrap();
+    let href_re = Regex::new(r#"(?i)\bhref\s*=\s*["']([^"']*)["']"#).unwrap();
+    let endpoint = element_re
+        .find_iter(&html)
+        .filter(|element| rel_re.is_match(element.as_str()))
+        .find_map(|element| href_re.captures(element.as_str()).map(|caps| unescape(&caps[1])));
+    Ok(endpoint.map(|href| resolve_url(&base, &href)))
+}
+
+/// Send webmentions for the pages of the site built at `dist_path` that are new, changed or deleted
+/// since the last run, as recorded in `state_path`.
+///
+/// The pages are the HTML files listed by the build manifest at `manifest_path` (see
+/// [`crate::prune::record_outputs`]), so only what the generator wrote counts. A page counts as
+/// changed when the hash of its content (its first `<article>`, or else its `<main>`) differs from
+/// the recorded one. A webmention is sent to every outbound link of its content (see
+/// [`outbound_links`]) and to the targets it linked to before, so they learn of removed links. A page
+/// no longer in the manifest notifies every target it linked to, which then finds it gone. Mentions
+/// that [`MentionOutcome::Failed`] are sent again on the next run, changed or not. The endpoint of
+/// each target is discovered following the Webmention spec, and the source is the URL of the page
+/// given by `urls`. Redirect stubs are skipped.
+///
+/// With `dry_run`, nothing is requested nor recorded, and every mention is [`MentionOutcome::Pending`].
+/// Otherwise the outcome of every mention and the hash of every page are written to `state_path`,
+/// so a first run over an existing site notifies every target it links to.
+pub fn send_webmentions(
+    dist_path: &Path,
+    urls: &UrlResolver,
+    manifest_path: &Path,
+    state_path: &Path,
+    dry_run: bool,
+) -> std::io::Result<Vec<SentMention>> {
+    let mut state: State = fs::read_to_string(state_path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let site_url = urls.url("");
+    let refresh_re = Regex::new(r#"(?i)<meta\s[^>]*http-equiv\s*=\s*["']?refresh"#).unwrap();
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_global(Some(Duration::from_secs(10)))
+        .user_agent(concat!("cats-ssg/", env!("CARGO_PKG_VERSION"), " webmention sender"))
+        .build()
+        .into();
+    let send = |source: &str, target: &str| {
+        if dry_run {
+            return Mention { endpoint: None, outcome: MentionOutcome::Pending, sent_at: now };
+        }
+        let (endpoint, outcome) = match discover_endpoint(&agent, target) {
+            Ok(Some(endpoint)) => {
+                let outcome = match agent.post(&endpoint).send_form([("source", source), ("target", target)]) {
+                    Ok(response) if response.status().is_success() => MentionOutcome::Accepted { status: response.status().as_u16() },
+                    Ok(response) => MentionOutcome::Rejected { status: response.status().as_u16() },
+                    Err(e) => MentionOutcome::Failed { reason: e.to_string() },
+                };
+                (Some(endpoint), outcome)
+            }
+            Ok(None) => (None, MentionOutcome::NoEndpoint),
+            Err(reason) => (None, MentionOutcome::Failed { reason }),
+        };
+        Mention { endpoint, outcome, sent_at: now }
+    };
+
+    let manifest = fs::read_to_string(manifest_path)?;
+    let mut pages: Vec<&str> = manifest.lines().filter(|file| file.ends_with(".html")).collect();
+    pages.sort();
+    let mut sent = Vec::new();
+    let mut sources = BTreeSet::new();
+    for page in pages {
+        let Ok(html) = fs::read_to_string(dist_path.join(page)) else {
+            continue;
+        };
+        if refresh_re.is_match(&html) {
+            continue;
+        }
+        let source = urls.url(page);
+        sources.insert(source.clone());
+        let mut hasher = DefaultHasher::new();
+        content(&html).hash(&mut hasher);
+        let hash = hasher.finish();
+        let previous = state.pages.remove(&source);
+        let (mut page_state, targets): (PageState, BTreeSet<String>) = match previous {
+            Some(previous) if previous.hash == hash => {
+                let failed = previous.targets.iter().filter(|(_, mention)| mention.outcome.is_failed()).map(|(target, _)| target.clone()).collect();
+                (previous, failed)
+            }
+            previous => {
+                let mut targets: BTreeSet<String> = outbound_links(&html, &site_url).into_iter().collect();
+                targets.extend(previous.into_iter().flat_map(|previous| previous.targets.into_keys()));
+                (PageState { hash, targets: BTreeMap::new() }, targets)
+            }
+        };
+        for target in targets {
+            let mention = send(&source, &target);
+            tracing::debug!("Webmention {} -> {}: {:?}", source, target, mention.outcome);
+            page_state.targets.insert(target.clone(), mention.clone());
+            sent.push(SentMention { source: source.clone(), target, mention });
+        }
+        state.pages.insert(source, page_state);
+    }
+
+    // Pages gone from the site, kept only while some of their targets could not be told
+    let deleted: Vec<String> = state.pages.keys().filter(|source| !sources.contains(*source)).cloned().collect();
+    for source in deleted {
+        let previous = state.pages.remove(&source).unwrap_or_default();
+        let mut page_state = PageState { hash: previous.hash, targets: BTreeMap::new() };
+        for target in previous.targets.into_keys() {
+            let mention = send(&source, &target);
+            tracing::debug!("Webmention {} -> {}: {:?}", source, target, mention.outcome);
+            if mention.outcome.is_failed() {
+                page_state.targets.insert(target.clone(), mention.clone());
+            }
+            sent.push(SentMention { source: source.clone(), target, mention });
+        }
+        if !page_state.targets.is_empty() {
+            state.pages.insert(source, page_state);
+        }
+    }
+
+    if !dry_run {
+        if let Some(parent) = state_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(state_path, serde_json::to_string_pretty(&state)?)?;
+    }
+    Ok(sent)
+}
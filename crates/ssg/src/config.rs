@@ -0,0 +1,207 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+use ssg_generator_utils::{comments::CommentsProvider, highlight, split_front_matter, templates::DEFAULT_TEMPLATES};
+use syntect::highlighting::ThemeSet;
+
+use crate::Config;
+
+/// Prefix of the environment variables setting config fields.
+pub const ENV_PREFIX: &str = "SSG_";
+
+/// Config file names looked up in a directory, in order of precedence.
+pub const CONFIG_FILES: &[&str] = &["cats-ssg.json", "cats-ssg.toml", "cats-ssg.yml", "cats-ssg.yaml"];
+
+/// The nearest config file: the first of [`CONFIG_FILES`] found in `start`, or else in the closest
+/// directory above it holding one.
+pub fn find_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find_map(|dir| CONFIG_FILES.iter().map(|name| dir.join(name)).find(|path| path.is_file()))
+}
+
+/// Read the config file at `path`: TOML for a `.toml` extension, YAML for `.yml` and `.yaml`, and
+/// JSON otherwise.
+pub fn load_config(path: &Path) -> Result<Config, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| e.to_string()),
+        Some("yml" | "yaml") => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        _ => serde_json::from_str(&content).map_err(|e| e.to_string()),
+    }
+}
+
+/// Resolve the paths of `config`, read from a config file in `dir`, against that directory rather
+/// than the working directory, so a project builds the same from any of its subdirectories.
+///
+/// The content, output and cache directories get their default resolved too, and the other
+/// directories and files with a default (`templates`, `static`, ...) only when it exists there, so
+/// they stay optional. `#rrggbb` card backgrounds and paths that are already absolute are kept.
+pub fn resolve_paths(config: &mut Config, dir: &Path) {
+    let resolve = |path: Option<&str>, default: &str| Some(dir.join(path.unwrap_or(default)).to_string_lossy().into_owned());
+    let resolve_optional = |path: Option<&str>, default: &str| match path {
+        Some(path) => resolve(Some(path), default),
+        None => resolve(None, default).filter(|path| Path::new(path).exists()),
+    };
+    config.base = resolve(config.base.as_deref(), "pages");
+    config.dist = resolve(config.dist.as_deref(), "dist");
+    config.cache_dir = resolve(config.cache_dir.as_deref(), ".cats-ssg-cache");
+    config.templates = resolve_optional(config.templates.as_deref(), "templates");
+    config.static_dir = resolve_optional(config.static_dir.as_deref(), "static");
+    config.data_dir = resolve_optional(config.data_dir.as_deref(), "data");
+    config.archetype = resolve_optional(config.archetype.as_deref(), "archetypes/default.md");
+    if let Some(syntaxes) = &mut config.syntaxes {
+        *syntaxes = dir.join(&*syntaxes).to_string_lossy().into_owned();
+    }
+    if let Some(dist) = config.gemini.as_mut().and_then(|gemini| gemini.dist.as_mut()) {
+        *dist = dir.join(&*dist);
+    }
+    if let Some(web_manifest) = &mut config.web_manifest {
+        web_manifest.logo = dir.join(&web_manifest.logo);
+    }
+    if let Some(public_key) = config.activitypub.as_mut().and_then(|activitypub| activitypub.public_key.as_mut()) {
+        *public_key = dir.join(&*public_key);
+    }
+    if let Some(cards) = config.seo.as_mut().and_then(|seo| seo.cards.as_mut()) {
+        cards.font = dir.join(&cards.font).to_string_lossy().into_owned();
+        if let Some(background) = cards.background.as_mut().filter(|background| !background.starts_with('#')) {
+            *background = dir.join(&*background).to_string_lossy().into_owned();
+        }
+    }
+    if let Some(tailwind) = &mut config.tailwind {
+        tailwind.input = resolve(tailwind.input.as_deref(), "main.css");
+    }
+}
+
+/// Config from the `SSG_*` variables of `vars`, each setting the field named by the rest of the
+/// variable in lowercase, e.g. `SSG_DOMAIN` or `SSG_BASE_PATH`.
+///
+/// Values are read as JSON when the field accepts that, so `SSG_PRETTY_URLS=true`,
+/// `SSG_RELATED_POSTS=3` and `SSG_FEED='{"title": "Blog"}'` work, and as plain strings otherwise.
+/// Variables naming no field are ignored.
+pub fn env_config(vars: impl IntoIterator<Item = (String, String)>) -> Result<Config, String> {
+    let field = |key: &str, value: &Value| {
+        serde_json::from_value::<Config>(Value::Object(Map::from_iter([(key.to_string(), value.clone())])))
+    };
+    let mut fields = Map::new();
+    for (name, raw) in vars {
+        let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let key = key.to_lowercase();
+        let value = serde_json::from_str::<Value>(&raw)
+            .ok()
+            .filter(|value| field(&key, value).is_ok())
+            .unwrap_or(Value::String(raw));
+        field(&key, &value).map_err(|e| format!("{}: {}", name, e))?;
+        fields.insert(key, value);
+    }
+    serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
+}
+
+/// Problems with the merged `config` that would break or misdirect a build, one message each: missing
+/// directories and files (including the web manifest logo and the ActivityPub public key), a `domain` or `repo_url` that is not an `http(s)` URL, templates named by the tag template
+/// setting or the `extends` of an archetype that neither the templates directory nor the built-in
+/// ones provide, code themes syntect does not know, giscus comments without their ids, and an `i18n`
+/// block without languages or with a default language it does not list.
+///
+/// Directories with a default (`static`, `data`, ...) are only required when set explicitly.
+pub fn validate(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    let base = Path::new(config.base.as_deref().unwrap_or("pages"));
+    if !base.is_dir() {
+        problems.push(format!("content directory {} does not exist", base.display()));
+    }
+    let optional_paths = [
+        ("templates directory", &config.templates),
+        ("static directory", &config.static_dir),
+        ("data directory", &config.data_dir),
+        ("syntax packdump", &config.syntaxes),
+        ("archetype", &config.archetype),
+    ];
+    for (what, path) in optional_paths {
+        if let Some(path) = path
+            && !Path::new(path).exists()
+        {
+            problems.push(format!("{} {} does not exist", what, path));
+        }
+    }
+    if let Some(web_manifest) = &config.web_manifest
+        && !web_manifest.logo.is_file()
+    {
+        problems.push(format!("web manifest logo {} does not exist", web_manifest.logo.display()));
+    }
+    if let Some(public_key) = config.activitypub.as_ref().and_then(|activitypub| activitypub.public_key.as_ref())
+        && !public_key.is_file()
+    {
+        problems.push(format!("ActivityPub public key {} does not exist", public_key.display()));
+    }
+    if let Some(comments) = &config.comments
+        && comments.provider == CommentsProvider::Giscus
+        && [&comments.repo_id, &comments.category, &comments.category_id].iter().any(|field| field.is_none())
+    {
+        problems.push("giscus comments need repo_id, category and category_id".to_string());
+    }
+
+    if let Some(domain) = &config.domain {
+        let host = domain
+            .strip_prefix("https://")
+            .or_else(|| domain.strip_prefix("http://"))
+            .and_then(|rest| rest.split('/').next());
+        if !host.is_some_and(|host| !host.is_empty() && !host.contains(char::is_whitespace)) {
+            problems.push(format!("domain {} is not an http(s) URL such as https://example.com/", domain));
+        }
+    }
+    if let Some(repo_url) = &config.repo_url
+        && !repo_url.starts_with("https://")
+        && !repo_url.starts_with("http://")
+    {
+        problems.push(format!("repo_url {} is not an http(s) URL such as https://github.com/owner/repo", repo_url));
+    }
+    if let Some(i18n) = &config.i18n {
+        if i18n.languages.is_empty() {
+            problems.push("i18n lists no languages".to_string());
+        } else if !i18n.languages.iter().any(|lang| lang == i18n.default_language()) {
+            problems.push(format!("i18n default_language {} is not one of its languages", i18n.default_language()));
+        }
+    }
+
+    let templates = Path::new(config.templates.as_deref().unwrap_or("templates"));
+    let template_exists = |name: &str| templates.join(name).is_file() || DEFAULT_TEMPLATES.iter().any(|(n, _)| *n == name);
+    let tag_template = config.tag_template.as_deref().unwrap_or("tag.html");
+    if !template_exists(tag_template) {
+        problems.push(format!("tag template {} does not exist in {}", tag_template, templates.display()));
+    }
+    let archetype = Path::new(config.archetype.as_deref().unwrap_or("archetypes/default.md"));
+    let archetypes = fs::read_dir(archetype.parent().unwrap_or(Path::new("")))
+        .map(|entries| entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_else(|_| Vec::new());
+    for path in archetypes {
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let extends = split_front_matter(&source).0.and_then(|yaml| {
+            yaml.lines()
+                .find_map(|line| line.strip_prefix("extends:"))
+                .map(|name| name.trim().trim_matches(['"', '\'']).to_string())
+        });
+        if let Some(name) = extends
+            && !template_exists(&name)
+        {
+            problems.push(format!("archetype {} extends {}, which does not exist in {}", path.display(), name, templates.display()));
+        }
+    }
+
+    let themes = ThemeSet::load_defaults();
+    let code_themes = [Some(config.code_theme.as_deref().unwrap_or(highlight::DEFAULT_CODE_THEME)), config.code_theme_light.as_deref()];
+    for theme in code_themes.into_iter().flatten() {
+        if !themes.themes.contains_key(theme) {
+            let known: Vec<_> = themes.themes.keys().map(String::as_str).collect();
+            problems.push(format!("unknown code theme {} (known: {})", theme, known.join(", ")));
+        }
+    }
+    problems
+}
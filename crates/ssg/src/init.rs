@@ -0,0 +1,52 @@
+use std::{fs, io, path::Path};
+use ssg_generator_utils::templates::DEFAULT_TEMPLATES;
+
+const CONFIG: &str = r#"{
+  "domain": "https://example.com",
+  "base_path": "/"
+}
+"#;
+
+const HELLO_WORLD_MD: &str = "# Hello, world!
+
+This page lives in `pages/hello-world/index.md`. Its title and description come from the
+`meta.yml` next to it.
+
+Run `ssg serve --watch` and edit this file to see the site rebuild.
+";
+
+const HELLO_WORLD_META: &str = "title: Hello, world!
+description: The first page of a new site.
+tags:
+  - welcome
+";
+
+/// Scaffold a starter project in `dir`: config, templates, a sample page and `.nojekyll`.
+///
+/// Existing files are left untouched, so running it on a half-initialized project only fills the gaps.
+pub fn init(dir: &Path) -> io::Result<()> {
+    let mut files = vec![
+        (dir.join("cats-ssg.json"), CONFIG),
+        (dir.join("pages/hello-world/index.md"), HELLO_WORLD_MD),
+        (dir.join("pages/hello-world/meta.yml"), HELLO_WORLD_META),
+        (dir.join(".nojekyll"), ""),
+    ];
+    files.extend(
+        DEFAULT_TEMPLATES
+            .iter()
+            .map(|(name, source)| (dir.join("templates").join(name), *source)),
+    );
+
+    for (path, content) in files {
+        if path.exists() {
+            tracing::info!("Skipping existing {}", path.display());
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+        tracing::info!("Created {}", path.display());
+    }
+    Ok(())
+}
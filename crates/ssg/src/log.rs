@@ -0,0 +1,52 @@
+use std::{io::IsTerminal, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use tracing::Level;
+use tracing_subscriber::fmt::format::debug_fn;
+
+/// How log lines are written to stderr.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines holding just the level and message.
+    #[default]
+    Text,
+    /// One JSON object per line with `level`, `message` and, for warnings and errors, the
+    /// `category` and `path` they are about.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown log format `{}` (expected `text` or `json`)", other)),
+        }
+    }
+}
+
+/// Install the global `tracing` subscriber, logging events at `level` and above in `format`.
+pub fn init(level: Level, format: LogFormat) {
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .with_writer(std::io::stderr);
+    match format {
+        // `category` and `path` are already part of every message
+        LogFormat::Text => builder
+            .without_time()
+            .with_ansi(std::io::stderr().is_terminal())
+            .fmt_fields(debug_fn(|writer, field, value| {
+                if field.name() == "message" {
+                    write!(writer, "{:?}", value)
+                } else {
+                    Ok(())
+                }
+            }))
+            .init(),
+        LogFormat::Json => builder.json().flatten_event(true).init(),
+    }
+}
@@ -3,15 +3,53 @@ use std::{
     fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
+    sync::Arc,
+    thread,
 };
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use glob::glob;
-use ssg_generator_utils::{generate_site, load_meta};
-use syntect::parsing::SyntaxSet;
+use ssg_generator_utils::{
+    PageOptions, SiteOptions,
+    activitypub::ActivityPubConfig,
+    analytics::AnalyticsConfig,
+    check::{ExternalProblem, LinkCheckConfig, check_external_links, check_links, check_llms_txt},
+    comments::CommentsConfig,
+    deploy::DeployConfig,
+    export::export_single_html,
+    feed::{FeedConfig, PodcastConfig},
+    gemini::GeminiConfig,
+    generate_site,
+    git::EditLinks,
+    highlight::{HighlightMode, MermaidMode},
+    i18n::I18nConfig,
+    images::ImageConfig,
+    llms::LlmsConfig,
+    load_dir_meta,
+    menus::Menus,
+    microformats::MicroformatsConfig,
+    robots::RobotsConfig,
+    sections::SectionConfig,
+    seo::SeoConfig,
+    sw::ServiceWorkerConfig,
+    urls::UrlResolver,
+    webmanifest::WebManifestConfig,
+    webmention::{MentionOutcome, send_webmentions},
+};
 use tailwindcss_oxide::scanner::{Scanner, sources::PublicSourceEntry};
+use log::LogFormat;
+use tailwind::TailwindConfig;
 use serde::{Deserialize, Serialize};
 
-#[derive(Parser, Debug, Deserialize, Serialize, Default)]
+mod config;
+mod init;
+mod log;
+mod new;
+mod serve;
+mod syntaxes;
+mod tailwind;
+mod watch;
+
+#[derive(Parser, Debug, Deserialize, Serialize, Default, Clone)]
 #[command(author, version, about = "Static site generator", long_about = None)]
 struct Config {
     /// Content source directory
@@ -34,23 +72,346 @@ struct Config {
     #[arg(long)]
     base_path: Option<String>,
 
-    /// Path to a JSON configuration file
+    /// Where pages are written, e.g. `/:year/:month/:slug/` or `/:section/:slug/` (default: mirror the content tree)
+    #[arg(long)]
+    permalink: Option<String>,
+
+    /// Write every page as `<slug>/index.html` and link to it without the `.html` extension
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pretty_urls: Option<bool>,
+
+    /// Languages of a multilingual site, told apart by `pages/<lang>/` subtrees or `<name>.<lang>.md` suffixes; not
+    /// settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    i18n: Option<I18nConfig>,
+
+    /// Repository of the sources on GitHub or GitLab, giving every page an "edit this page" link (e.g.,
+    /// https://github.com/ana/blog)
+    #[arg(long)]
+    repo_url: Option<String>,
+
+    /// Branch the "edit this page" links open the sources on (default: main)
+    #[arg(long)]
+    edit_branch: Option<String>,
+
+    /// Pages sharing tags listed as related to each page (default: 5)
+    #[arg(long)]
+    related_posts: Option<usize>,
+
+    /// Split the sitemap into parts listed by sitemap_index.xml past this many URLs (default: 50000)
+    #[arg(long)]
+    sitemap_chunk_size: Option<usize>,
+
+    /// Path to a JSON, TOML or YAML configuration file (default: the nearest cats-ssg.json, .toml, .yml or
+    /// .yaml in the current directory or above it). Every field of the file can also be set through an
+    /// `SSG_<FIELD>` environment variable, such as `SSG_DOMAIN`, which overrides it. Flags take an
+    /// optional value, so `--prune=false` or `SSG_PRUNE=false` turns off a flag set by the file
     #[arg(long)]
     config: Option<String>,
 
     /// Dump syntaxes and exit
-    #[arg(long)]
-    #[serde(default)]
-    dump: bool,
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    dump: Option<bool>,
 
     /// Comma-separated list of languages to omit from syntax highlighting
     #[arg(long)]
     omit_languages: Option<String>,
 
     /// Disable syntax highlighting altogether
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    no_syntax_highlighting: Option<bool>,
+
+    /// How code is highlighted: `inline` styles or `classes` plus a generated syntax.css (default: inline)
+    #[arg(long)]
+    highlight_mode: Option<HighlightMode>,
+
+    /// How `mermaid` code blocks are rendered: `wrapper`, `ignore` or `highlight` (default: ignore)
+    #[arg(long)]
+    mermaid: Option<MermaidMode>,
+
+    /// Syntax packdump to use instead of the bundled one (as written by --dump)
+    #[arg(long)]
+    syntaxes: Option<String>,
+
+    /// Directory for build caches such as a rebuilt syntax packdump (default: .cats-ssg-cache)
+    #[arg(long)]
+    cache_dir: Option<String>,
+
+    /// Render every page instead of reusing unchanged ones from the cache directory
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    no_cache: Option<bool>,
+
+    /// Delete files a previous build wrote to the output directory that this build no longer produces
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    prune: Option<bool>,
+
+    /// Also write gzip (.gz) and brotli (.br) copies of every HTML, CSS, XML and text output
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    precompress: Option<bool>,
+
+    /// Write a chunked full-text search index of the pages and a client script to `search/`
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    search_index: Option<bool>,
+
+    /// Also write a plain-text rendition of every page next to its HTML, as `.txt`
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    plain_text: Option<bool>,
+
+    /// Write the metadata, HTML and text of every page as JSON to `api/pages/` and an index of them to `api/index.json`
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    content_api: Option<bool>,
+
+    /// Also write `llms-full.txt`, concatenating the Markdown copied for LLMs of every page
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    llms_full: Option<bool>,
+
+    /// Also write the build summary and per-phase timings to build-report.json in the output directory
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    build_report: Option<bool>,
+
+    /// Syntect theme for code blocks (default: base16-ocean.dark)
     #[arg(long)]
+    code_theme: Option<String>,
+
+    /// Syntect theme used for code blocks when the visitor prefers a light color scheme;
+    /// requires `--highlight-mode classes`
+    #[arg(long)]
+    code_theme_light: Option<String>,
+
+    /// Directory copied as-is into the output root (default: static)
+    #[arg(long)]
+    static_dir: Option<String>,
+
+    /// Also copy the stylesheets, scripts, images and fonts of the static directory under a name carrying
+    /// a hash of their content; templates link that copy with `asset("app.css")`
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    fingerprint_assets: Option<bool>,
+
+    /// Number of images per page loaded eagerly before lazy loading kicks in (default: 1)
+    #[arg(long)]
+    eager_images: Option<usize>,
+
+    /// Directory of YAML/JSON/TOML files exposed to templates as `data` (default: data)
+    #[arg(long)]
+    data_dir: Option<String>,
+
+    /// Template for pages created with `new` (default: archetypes/default.md, or a built-in one)
+    #[arg(long)]
+    archetype: Option<String>,
+
+    /// Template used for tag pages and the tag overview (default: tag.html)
+    #[arg(long)]
+    tag_template: Option<String>,
+
+    /// Fail the build on missing templates, undefined variables and render errors
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    strict_templates: Option<bool>,
+
+    /// Fail the build on front matter or metadata files that do not parse or have unknown keys
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    strict_meta: Option<bool>,
+
+    /// Build the pages marked `draft: true` too, which are otherwise left out of the site
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    drafts: Option<bool>,
+
+    /// Report internal links and anchors of the generated pages that do not resolve; with
+    /// `--strict-templates` they fail the build
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    check_links: Option<bool>,
+
+    /// RSS feed settings; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    feed: Option<FeedConfig>,
+
+    /// iTunes-compatible podcast of the pages with `audio` metadata, written only when set; not settable from the
+    /// command line
+    #[arg(skip)]
+    #[serde(default)]
+    podcast: Option<PodcastConfig>,
+
+    /// Image optimization settings; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    images: Option<ImageConfig>,
+
+    /// robots.txt rules; not settable from the command line
+    #[arg(skip)]
     #[serde(default)]
-    no_syntax_highlighting: bool,
+    robots: Option<RobotsConfig>,
+
+    /// Open Graph and Twitter Card settings; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    seo: Option<SeoConfig>,
+
+    /// Author `h-card` of the IndieWeb microformats added to every page, written only when set; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    microformats: Option<MicroformatsConfig>,
+
+    /// giscus or utterances comments under every post, written only when set; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    comments: Option<CommentsConfig>,
+
+    /// Plausible, GoatCounter or Umami snippet added to every page, left out of `serve` builds; not settable from
+    /// the command line
+    #[arg(skip)]
+    #[serde(default)]
+    analytics: Option<AnalyticsConfig>,
+
+    /// `_redirects` and `_headers` settings; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    deploy: Option<DeployConfig>,
+
+    /// Sorting and pagination of the generated section pages; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    sections: Option<SectionConfig>,
+
+    /// Named menus handed to templates, overriding `menus.yml` in the data directory; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    menus: Option<Menus>,
+
+    /// Service worker caching the site for offline visits, written only when set; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    service_worker: Option<ServiceWorkerConfig>,
+
+    /// Token budgets of the Markdown copies for LLMs and `llms-full.txt`, and order of the `llms.txt` sections; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    llms: Option<LlmsConfig>,
+
+    /// Web app manifest and the icons rendered from its logo; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    web_manifest: Option<WebManifestConfig>,
+
+    /// Gemini capsule mirroring the site as gemtext, written only when set; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    gemini: Option<GeminiConfig>,
+
+    /// ActivityPub actor, outbox and WebFinger response letting the Fediverse follow the site, written only when set; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    activitypub: Option<ActivityPubConfig>,
+
+    /// External link checking settings for `check --external`; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    link_check: Option<LinkCheckConfig>,
+
+    /// Tailwind CSS build producing the site stylesheet from the scanned pages; not settable from the command line
+    #[arg(skip)]
+    #[serde(default)]
+    tailwind: Option<TailwindConfig>,
+
+    /// Only log warnings and errors
+    #[arg(short, long, num_args = 0..=1, require_equals = true, default_missing_value = "true", conflicts_with = "verbose")]
+    quiet: Option<bool>,
+
+    /// Also log every page rendered and every request served
+    #[arg(short, long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    verbose: Option<bool>,
+
+    /// How log lines are written: `text` or one `json` object per line (default: text)
+    #[arg(long)]
+    log_format: Option<LogFormat>,
+
+    #[command(subcommand)]
+    #[serde(skip)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Build the site and serve the output directory over HTTP
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:4000")]
+        addr: String,
+
+        /// Rebuild when content or templates change and live-reload open pages
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Create a starter project with a config, templates and a sample page
+    Init {
+        /// Directory to create the project in
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Delete the output directory
+    Clean,
+    /// Build the site and fail if any internal link or anchor of the generated pages does not resolve
+    Check {
+        /// Also request every external link, reporting error statuses and redirects
+        #[arg(long)]
+        external: bool,
+    },
+    /// Build the site and fail if `llms.txt` breaks the llms.txt structure or lists files the site lacks
+    CheckLlms {
+        /// Also fail on entries without a description
+        #[arg(long)]
+        require_descriptions: bool,
+    },
+    /// Create a draft page from the archetype template, built only with `--drafts` until it drops `draft: true`
+    New {
+        /// Title of the page; its slug names the page directory
+        title: String,
+    },
+    /// Inspect the configuration merged from the command line, environment and config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Build the site and export it in another format
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
+    },
+    /// Build the site and notify the pages it links to
+    Webmentions {
+        #[command(subcommand)]
+        action: WebmentionAction,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ConfigAction {
+    /// Print the effective configuration and report missing paths, templates and themes without building
+    Check,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum WebmentionAction {
+    /// Send webmentions for the pages new, changed or deleted since the last run, retrying those that failed
+    Send {
+        /// List the webmentions to send without sending or recording them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ExportFormat {
+    /// Write the whole site, or one section of it, as a single self-contained HTML file
+    SingleHtml {
+        /// Directory of the output to export, such as `posts`, instead of the whole site
+        #[arg(long)]
+        section: Option<String>,
+
+        /// File to write
+        #[arg(long, short, default_value = "site.html")]
+        output: PathBuf,
+    },
 }
 
 impl Config {
@@ -61,14 +422,91 @@ impl Config {
             dist: self.dist.or(other.dist),
             domain: self.domain.or(other.domain),
             base_path: self.base_path.or(other.base_path),
+            permalink: self.permalink.or(other.permalink),
+            pretty_urls: self.pretty_urls.or(other.pretty_urls),
+            i18n: self.i18n.or(other.i18n),
+            repo_url: self.repo_url.or(other.repo_url),
+            edit_branch: self.edit_branch.or(other.edit_branch),
+            related_posts: self.related_posts.or(other.related_posts),
+            sitemap_chunk_size: self.sitemap_chunk_size.or(other.sitemap_chunk_size),
             config: self.config.or(other.config),
-            dump: self.dump || other.dump,
+            dump: self.dump.or(other.dump),
             omit_languages: self.omit_languages.or(other.omit_languages),
-            no_syntax_highlighting: self.no_syntax_highlighting || other.no_syntax_highlighting,
+            no_syntax_highlighting: self.no_syntax_highlighting.or(other.no_syntax_highlighting),
+            highlight_mode: self.highlight_mode.or(other.highlight_mode),
+            mermaid: self.mermaid.or(other.mermaid),
+            syntaxes: self.syntaxes.or(other.syntaxes),
+            cache_dir: self.cache_dir.or(other.cache_dir),
+            no_cache: self.no_cache.or(other.no_cache),
+            prune: self.prune.or(other.prune),
+            precompress: self.precompress.or(other.precompress),
+            search_index: self.search_index.or(other.search_index),
+            plain_text: self.plain_text.or(other.plain_text),
+            content_api: self.content_api.or(other.content_api),
+            llms_full: self.llms_full.or(other.llms_full),
+            build_report: self.build_report.or(other.build_report),
+            code_theme: self.code_theme.or(other.code_theme),
+            code_theme_light: self.code_theme_light.or(other.code_theme_light),
+            static_dir: self.static_dir.or(other.static_dir),
+            fingerprint_assets: self.fingerprint_assets.or(other.fingerprint_assets),
+            eager_images: self.eager_images.or(other.eager_images),
+            data_dir: self.data_dir.or(other.data_dir),
+            archetype: self.archetype.or(other.archetype),
+            tag_template: self.tag_template.or(other.tag_template),
+            strict_templates: self.strict_templates.or(other.strict_templates),
+            strict_meta: self.strict_meta.or(other.strict_meta),
+            drafts: self.drafts.or(other.drafts),
+            check_links: self.check_links.or(other.check_links),
+            quiet: self.quiet.or(other.quiet),
+            verbose: self.verbose.or(other.verbose),
+            log_format: self.log_format.or(other.log_format),
+            feed: self.feed.or(other.feed),
+            podcast: self.podcast.or(other.podcast),
+            images: self.images.or(other.images),
+            robots: self.robots.or(other.robots),
+            seo: self.seo.or(other.seo),
+            microformats: self.microformats.or(other.microformats),
+            comments: self.comments.or(other.comments),
+            analytics: self.analytics.or(other.analytics),
+            deploy: self.deploy.or(other.deploy),
+            sections: self.sections.or(other.sections),
+            menus: self.menus.or(other.menus),
+            service_worker: self.service_worker.or(other.service_worker),
+            llms: self.llms.or(other.llms),
+            web_manifest: self.web_manifest.or(other.web_manifest),
+            gemini: self.gemini.or(other.gemini),
+            activitypub: self.activitypub.or(other.activitypub),
+            link_check: self.link_check.or(other.link_check),
+            tailwind: self.tailwind.or(other.tailwind),
+            command: self.command.or(other.command),
         }
     }
 }
 
+/// Resolver of the URLs of the site, built the way the generator builds it so commands run after a
+/// build see the same URLs.
+fn url_resolver(config: &Config) -> UrlResolver {
+    UrlResolver::new(config.domain.as_deref().unwrap_or(DEFAULT_DOMAIN), config.base_path.as_deref().unwrap_or(""))
+        .with_permalink(config.permalink.as_deref())
+        .with_pretty_urls(config.pretty_urls.unwrap_or(false))
+        .with_languages(config.i18n.as_ref())
+}
+
+/// Write a space-separated `candidates.txt` of the classes the Tailwind scanner finds in the HTML files
+/// of `dist`, returning its path.
+fn write_candidates(dist: &Path) -> PathBuf {
+    let mut scanner = Scanner::new(vec![PublicSourceEntry {
+        base: dist.to_string_lossy().to_string(),
+        pattern: "**/*.html".into(),
+        negated: false,
+    }]);
+    let candidates_path = dist.join("candidates.txt");
+    if let Err(e) = fs::write(&candidates_path, scanner.scan().join(" ")) {
+        tracing::warn!(category = "io", path = %candidates_path.display(), "Failed to write candidates.txt: {}", e);
+    }
+    candidates_path
+}
+
 fn get_md_files(base_path: &Path) -> Vec<PathBuf> {
     let pattern = base_path.join("**/*.md").to_string_lossy().to_string();
     glob(&pattern)
@@ -77,32 +515,56 @@ fn get_md_files(base_path: &Path) -> Vec<PathBuf> {
         .collect()
 }
 
-fn dump_syntaxes() {
-    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
-    builder
-        .add_from_folder("crates/ssg-generator-utils/syntaxes", true)
-        .expect("Failed to load syntaxes");
-    let ps = builder.build();
+/// Site URL used when no `domain` is configured.
+const DEFAULT_DOMAIN: &str = "https://shadowrunner8095.github.io/my-blog/";
+
+/// Directory holding the extra `.sublime-syntax` definitions and the bundled packdump, relative to
+/// the project directory.
+const SYNTAXES_DIR: &str = "crates/ssg-generator-utils/syntaxes";
+
+/// File in the cache directory listing the outputs of the last build, used by --prune.
+const OUTPUTS_MANIFEST: &str = "outputs.txt";
 
-    let file = File::create("crates/ssg-generator-utils/syntaxes/syntaxes.packdump").unwrap();
+/// File in the cache directory holding the pages rendered by the last build.
+const PAGE_CACHE: &str = "pages.json";
+
+/// File in the cache directory remembering the external links that answered recently, used by `check --external`.
+const LINK_CACHE: &str = "external-links.json";
+
+/// File in the cache directory holding the external links `check --external` found broken or redirected.
+const LINK_REPORT: &str = "link-report.json";
+
+/// File in the cache directory recording the pages `webmentions send` went through and the webmentions it sent.
+const WEBMENTION_STATE: &str = "webmentions.json";
+
+fn dump_syntaxes(project_dir: &Path) {
+    let syntaxes_dir = project_dir.join(SYNTAXES_DIR);
+    let ps = syntaxes::build_syntax_set(&syntaxes_dir);
+
+    let file = File::create(syntaxes_dir.join("syntaxes.packdump")).unwrap();
     syntect::dumps::dump_to_writer(&ps, &file).unwrap();
-    println!("SyntaxSet dumped to syntaxes.packdump");
+    tracing::info!("SyntaxSet dumped to syntaxes.packdump");
 
-    let mut list_file = File::create("syntaxes_supported.txt").unwrap();
+    let mut list_file = File::create(project_dir.join("syntaxes_supported.txt")).unwrap();
     for syntax in ps.syntaxes() {
         writeln!(list_file, "{}", syntax.name).unwrap();
     }
-    println!("Supported syntaxes list saved to syntaxes_supported.txt");
+    tracing::info!("Supported syntaxes list saved to syntaxes_supported.txt");
 }
 
-/// Entrypoint for the CLI: generate a static site or dump editor syntaxes.
+/// Entrypoint for the CLI: generate a static site, serve it, or dump editor syntaxes.
 ///
-/// Parses CLI arguments and either:
-/// - when `--dump` is set: dumps bundled syntaxes and exits; or
+/// Parses CLI arguments, which win over `SSG_*` environment variables, which win over the config file (see
+/// [`config::env_config`] and [`config::find_config`]), and either:
+/// - when `--dump` is set: dumps bundled syntaxes and exits;
+/// - with the `serve` subcommand: builds the site and serves `dist` under the configured `base_path`,
+///   rebuilding and live-reloading open pages on content or template changes when `--watch` is set; or
 /// - otherwise: generates the site from Markdown under the configured `base` directory into `dist`,
-///   loading metadata from `base/meta.yml` and passing optional `llm_title` and `llm_description` into the generator.
-/// The function also creates the `dist` directory if missing, writes a space-separated `candidates.txt` of scanned HTML files,
-/// and prints progress/errors to stdout/stderr.
+///   loading metadata from the metadata file of `base` (such as `base/meta.yml`) and passing optional `llm_title` and `llm_description` into the generator.
+///
+/// Building also creates the `dist` directory if missing, writes a space-separated `candidates.txt` of scanned HTML files
+/// (compiled into the site stylesheet by the Tailwind CLI when the config file has a `tailwind` block),
+/// and logs progress and errors on stderr: only warnings and errors with `--quiet`, every page and request with `--verbose`.
 ///
 /// Notes:
 /// - The CLI `domain` value should include the protocol and a trailing slash (e.g. `https://example.com/`).
@@ -113,32 +575,213 @@ fn dump_syntaxes() {
 /// ```no_run
 /// // Run the program as a binary; example shows typical CLI invocation.
 /// // $ my_ssg --base pages --dist dist --domain https://example.com/
+/// // $ my_ssg serve --addr 127.0.0.1:4000 --watch
+/// // $ my_ssg init my-site
+/// // $ my_ssg new "My Post Title"
+/// // $ my_ssg clean
+/// // $ my_ssg config check
 /// std::env::set_var("RUST_BACKTRACE", "0");
 /// // `main()` is the process entrypoint and will perform filesystem operations when run.
 /// crate::main();
 /// ```
 fn main() {
     let cli_config = Config::parse();
+    let vars = std::env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    // Reported once logging is set up, which depends on the config
+    let mut config_errors = Vec::new();
+    let env_config = config::env_config(vars).unwrap_or_else(|e| {
+        config_errors.push(format!("Invalid environment variable {}", e));
+        Config::default()
+    });
+
+    let (config_path, found) = match (cli_config.config.as_ref().or(env_config.config.as_ref()), &cli_config.command) {
+        (Some(path), _) => (Some(PathBuf::from(path)), false),
+        // A new project does not belong to the one around it
+        (None, Some(Command::Init { .. })) => (None, false),
+        (None, _) => (config::find_config(&std::env::current_dir().expect("Failed to read the current directory")), true),
+    };
+    // Paths in a config file that was looked up are relative to its directory, wherever the binary
+    // runs from, and so is the syntaxes directory of the project
+    let project_dir = config_path.as_ref().filter(|_| found).and_then(|path| path.parent()).unwrap_or(Path::new("")).to_path_buf();
+    let file_config: Config = match config_path {
+        Some(path) if path.exists() => match config::load_config(&path) {
+            Ok(mut file_config) => {
+                if found {
+                    config::resolve_paths(&mut file_config, &project_dir);
+                }
+                file_config
+            }
+            Err(e) => {
+                config_errors.push(format!("Failed to parse config file {}: {}", path.display(), e));
+                Config::default()
+            }
+        },
+        _ => Config::default(),
+    };
+
+    // The command line wins over the environment, which wins over the config file
+    let config = cli_config.merge(env_config.merge(file_config));
 
-    let config_path = cli_config.config.as_deref().unwrap_or("cats-ssg.json");
-    let file_config: Config = if Path::new(config_path).exists() {
-        let file = File::open(config_path).expect("Failed to open config file");
-        serde_json::from_reader(file).expect("Failed to parse config file")
+    let level = if config.quiet.unwrap_or(false) {
+        tracing::Level::WARN
+    } else if config.verbose.unwrap_or(false) {
+        tracing::Level::DEBUG
     } else {
-        Config::default()
+        tracing::Level::INFO
     };
+    log::init(level, config.log_format.unwrap_or_default());
+    if !config_errors.is_empty() {
+        for error in &config_errors {
+            tracing::error!(category = "config", "{}", error);
+        }
+        std::process::exit(1);
+    }
 
-    let config = cli_config.merge(file_config);
+    if config.dump.unwrap_or(false) {
+        dump_syntaxes(&project_dir);
+        return;
+    }
 
-    if config.dump {
-        dump_syntaxes();
+    if let Some(Command::Init { dir }) = &config.command {
+        if let Err(e) = init::init(dir) {
+            tracing::error!("Failed to initialize {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
         return;
     }
 
+    if let Some(Command::Clean) = &config.command {
+        let dist = Path::new(config.dist.as_deref().unwrap_or("dist"));
+        let cache_dir = Path::new(config.cache_dir.as_deref().unwrap_or(".cats-ssg-cache"));
+        if dist.exists() {
+            if let Err(e) = fs::remove_dir_all(dist) {
+                tracing::error!("Failed to delete {}: {}", dist.display(), e);
+                std::process::exit(1);
+            }
+            tracing::info!("Deleted {}", dist.display());
+        }
+        // Nothing recorded there exists anymore
+        let _ = fs::remove_file(cache_dir.join(OUTPUTS_MANIFEST));
+        return;
+    }
+
+    if let Some(Command::New { title }) = &config.command {
+        let base = Path::new(config.base.as_deref().unwrap_or("pages"));
+        let archetype = Path::new(config.archetype.as_deref().unwrap_or("archetypes/default.md"));
+        match new::new_page(base, title, archetype) {
+            Ok(path) => tracing::info!("Created {}", path.display()),
+            Err(e) => {
+                tracing::error!("Failed to create page: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Config { action: ConfigAction::Check }) = &config.command {
+        println!("{}", serde_json::to_string_pretty(&config).expect("Failed to serialize config"));
+        let problems = config::validate(&config);
+        for problem in &problems {
+            tracing::error!(category = "config", "{}", problem);
+        }
+        if !problems.is_empty() {
+            std::process::exit(1);
+        }
+        tracing::info!("Configuration is valid");
+        return;
+    }
+
+    if !build(&config, &project_dir) && matches!(config.command, None | Some(Command::Check { .. } | Command::CheckLlms { .. } | Command::Export { .. } | Command::Webmentions { .. })) {
+        std::process::exit(1);
+    }
+
+    if let Some(Command::CheckLlms { require_descriptions }) = &config.command {
+        let dist = Path::new(config.dist.as_deref().unwrap_or("dist"));
+        let urls = url_resolver(&config);
+        match check_llms_txt(dist, &urls, *require_descriptions) {
+            Ok(problems) if problems.is_empty() => tracing::info!("llms.txt is valid"),
+            Ok(problems) => {
+                for problem in &problems {
+                    tracing::error!(category = "llms", path = %dist.join("llms.txt").display(), "{}", problem);
+                }
+                tracing::error!("llms.txt has {} problem(s)", problems.len());
+                std::process::exit(1);
+            }
+            Err(e) => {
+                tracing::error!(category = "io", "Failed to check {}: {}", dist.join("llms.txt").display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(Command::Export { format: ExportFormat::SingleHtml { section, output } }) = &config.command {
+        let dist = Path::new(config.dist.as_deref().unwrap_or("dist"));
+        let urls = url_resolver(&config);
+        match export_single_html(dist, &urls, section.as_deref()).and_then(|html| fs::write(output, html)) {
+            Ok(()) => tracing::info!("Exported to {}", output.display()),
+            Err(e) => {
+                tracing::error!(category = "io", "Failed to export {}: {}", output.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(Command::Webmentions { action: WebmentionAction::Send { dry_run } }) = &config.command {
+        let dist = Path::new(config.dist.as_deref().unwrap_or("dist"));
+        let cache_dir = Path::new(config.cache_dir.as_deref().unwrap_or(".cats-ssg-cache"));
+        let urls = url_resolver(&config);
+        match send_webmentions(dist, &urls, &cache_dir.join(OUTPUTS_MANIFEST), &cache_dir.join(WEBMENTION_STATE), *dry_run) {
+            Ok(sent) => {
+                for mention in &sent {
+                    match mention.mention.outcome {
+                        MentionOutcome::Rejected { .. } | MentionOutcome::Failed { .. } => {
+                            tracing::warn!(category = "webmention", "{}", mention)
+                        }
+                        _ => tracing::info!("{}", mention),
+                    }
+                }
+                tracing::info!("Went through {} webmention(s), results recorded in {}", sent.len(), cache_dir.join(WEBMENTION_STATE).display());
+            }
+            Err(e) => {
+                tracing::error!(category = "io", "Failed to send webmentions: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(Command::Serve { addr, watch }) = &config.command {
+        let live_reload = watch.then(|| {
+            let live_reload = Arc::new(serve::LiveReload::default());
+            let notifier = Arc::clone(&live_reload);
+            let config = config.clone();
+            let project_dir = project_dir.clone();
+            thread::spawn(move || {
+                let base = Path::new(config.base.as_deref().unwrap_or("pages"));
+                let templates_path = Path::new(config.templates.as_deref().unwrap_or("templates"));
+                watch::watch(&[base, templates_path], || {
+                    tracing::info!("Change detected, rebuilding...");
+                    build(&config, &project_dir);
+                    notifier.notify();
+                });
+            });
+            live_reload
+        });
+
+        let dist = Path::new(config.dist.as_deref().unwrap_or("dist"));
+        let base_path = config.base_path.as_deref().unwrap_or("");
+        if let Err(e) = serve::serve(dist, base_path, addr, live_reload) {
+            tracing::error!("Failed to serve {}: {}", dist.display(), e);
+        }
+    }
+}
+
+/// Run a full build of the site described by `config`, whose project (holding the syntaxes directory)
+/// is `project_dir`, returning whether it succeeded.
+fn build(config: &Config, project_dir: &Path) -> bool {
     let base = Path::new(config.base.as_deref().unwrap_or("pages"));
     let templates_path = Path::new(config.templates.as_deref().unwrap_or("templates"));
     let dist = Path::new(config.dist.as_deref().unwrap_or("dist"));
-    let domain = config.domain.as_deref().unwrap_or("https://shadowrunner8095.github.io/my-blog/");
+    let domain = config.domain.as_deref().unwrap_or(DEFAULT_DOMAIN);
     let base_path = config.base_path.as_deref().unwrap_or("");
 
     if !dist.exists() {
@@ -146,13 +789,12 @@ fn main() {
     }
     let md_files = get_md_files(base);
 
-    let content_index_path = Path::new("crates/ssg-generator-utils/content-index.html");
-    let main_meta_inf = load_meta(&base.join("meta.yml"));
+    let main_meta_inf = load_dir_meta(base);
 
     let llms_title = main_meta_inf.llm_title.as_deref();
     let llms_description = main_meta_inf.llm_description.as_deref();
 
-    let omit_languages: HashSet<String> = match config.omit_languages {
+    let omit_languages: HashSet<String> = match &config.omit_languages {
         Some(langs) => langs
             .split(',')
             .map(String::from)
@@ -165,33 +807,165 @@ fn main() {
         }
     };
 
-    if let Err(e) = generate_site(
-        md_files,
-        base,
-        dist,
+    // Syntax definitions edited since the last --dump are rebuilt into the cache
+    let cache_dir = Path::new(config.cache_dir.as_deref().unwrap_or(".cats-ssg-cache"));
+    let syntaxes_path = config.syntaxes.as_ref().map(PathBuf::from).or_else(|| {
+        syntaxes::fresh_packdump(&project_dir.join(SYNTAXES_DIR), cache_dir)
+    });
+    // Visits of the local preview are not counted
+    let analytics = config.analytics.as_ref().filter(|_| !matches!(config.command, Some(Command::Serve { .. })));
+    let edit_links = config
+        .repo_url
+        .as_deref()
+        .map(|repo_url| EditLinks::new(repo_url, config.edit_branch.as_deref().unwrap_or("main"), base));
+
+    let data_path = Path::new(config.data_dir.as_deref().unwrap_or("data"));
+    let static_path = Path::new(config.static_dir.as_deref().unwrap_or("static"));
+    let manifest_path = cache_dir.join(OUTPUTS_MANIFEST);
+    let page_cache_path = (!config.no_cache.unwrap_or(false)).then(|| cache_dir.join(PAGE_CACHE));
+    let stylesheet = config.tailwind.as_ref().map(|tailwind_config| {
+        move || {
+            let candidates_path = write_candidates(dist);
+            let css = tailwind::build_css(tailwind_config, dist, &candidates_path, cache_dir)?;
+            Ok(css.strip_prefix(dist).unwrap_or(&css).to_string_lossy().replace('\\', "/"))
+        }
+    });
+    let options = SiteOptions {
         domain,
         base_path,
-        templates_path,
-        content_index_path,
-        Some(true),
+        permalink_pattern: config.permalink.as_deref(),
+        pretty_urls: config.pretty_urls.unwrap_or(false),
+        i18n: config.i18n.as_ref(),
+        templates_path: Some(templates_path),
         llms_title,
         llms_description,
-        &omit_languages,
-        config.no_syntax_highlighting,
-    ) {
-        eprintln!("Failed to generate site: {}", e);
+        llms_full: config.llms_full.unwrap_or(false),
+        code_theme: config.code_theme.as_deref(),
+        code_theme_light: config.code_theme_light.as_deref(),
+        syntaxes_path: syntaxes_path.as_deref(),
+        data_path: Some(data_path),
+        drafts: config.drafts.unwrap_or(false),
+        feed_config: config.feed.as_ref(),
+        podcast: config.podcast.as_ref(),
+        robots_config: config.robots.as_ref(),
+        deploy_config: config.deploy.as_ref(),
+        related_posts: config.related_posts,
+        section_config: config.sections.as_ref(),
+        menus: config.menus.as_ref(),
+        service_worker: config.service_worker.as_ref(),
+        web_manifest: config.web_manifest.as_ref(),
+        search_index: config.search_index.unwrap_or(false),
+        activitypub: config.activitypub.as_ref(),
+        tag_template: config.tag_template.as_deref(),
+        static_path: Some(static_path),
+        fingerprint_assets: config.fingerprint_assets.unwrap_or(false),
+        manifest_path: Some(&manifest_path),
+        prune: config.prune.unwrap_or(false),
+        precompress: config.precompress.unwrap_or(false),
+        page_cache_path: page_cache_path.as_deref(),
+        sitemap_chunk_size: config.sitemap_chunk_size,
+        stylesheet: stylesheet.as_ref().map(|stylesheet| stylesheet as _),
+        content_index_path: None,
+        page: PageOptions {
+            generate_llm_txt_by_default: Some(true),
+            llms_config: config.llms.as_ref(),
+            plain_text: config.plain_text.unwrap_or(false),
+            gemini: config.gemini.as_ref(),
+            content_api: config.content_api.unwrap_or(false),
+            omit_languages: Some(&omit_languages),
+            disable_syntax_highlighting: config.no_syntax_highlighting.unwrap_or(false),
+            highlight_mode: config.highlight_mode.unwrap_or_default(),
+            mermaid: config.mermaid.unwrap_or_default(),
+            image_config: config.images.as_ref(),
+            eager_images: Some(config.eager_images.unwrap_or(1)),
+            seo_config: config.seo.as_ref(),
+            microformats: config.microformats.as_ref(),
+            comments: config.comments.as_ref(),
+            analytics,
+            edit_links: edit_links.as_ref(),
+            strict_templates: config.strict_templates.unwrap_or(false),
+            strict_meta: config.strict_meta.unwrap_or(false),
+        },
+    };
+    let site = match generate_site(md_files, base, dist, &options) {
+        Ok(site) => site,
+        Err(e) => {
+            tracing::error!(category = e.category(), path = e.path().map(|p| display(p.display())), "Failed to generate site: {}", e);
+            return false;
+        }
+    };
+    for e in &site.errors {
+        tracing::error!(category = e.category(), path = e.path().map(|p| display(p.display())), "{}", e);
+    }
+    tracing::info!("{}", site.report);
+    if config.build_report.unwrap_or(false) {
+        let report_path = dist.join("build-report.json");
+        let json = serde_json::to_string_pretty(&site.report).expect("Failed to serialize build report");
+        if let Err(e) = fs::write(&report_path, json) {
+            tracing::warn!(category = "io", path = %report_path.display(), "Failed to write {}: {}", report_path.display(), e);
+        }
+    }
+    if (config.strict_templates.unwrap_or(false) || config.strict_meta.unwrap_or(false)) && !site.errors.is_empty() {
+        tracing::error!("Build failed with {} error(s)", site.errors.len());
+        return false;
     }
 
-    let mut scanner = Scanner::new(vec![PublicSourceEntry{
-        base: dist.to_string_lossy().to_string(),
-        pattern: "**/*.html".into(),
-        negated: false,
-    }]);
+    let checking = matches!(config.command, Some(Command::Check { .. }));
+    let mut broken_links = 0;
+    if config.check_links.unwrap_or(false) || checking {
+        let urls = url_resolver(config);
+        match check_links(dist, &urls) {
+            Ok(broken) => {
+                for link in &broken {
+                    tracing::warn!(category = "link", path = %dist.join(&link.page).display(), "{}", link);
+                }
+                tracing::info!("Checked links: {} broken", broken.len());
+                broken_links = broken.len();
+            }
+            Err(e) => {
+                tracing::error!(category = "io", path = %dist.display(), "Failed to check links in {}: {}", dist.display(), e);
+                return false;
+            }
+        }
+    }
 
-    let candidates_path = dist.join("candidates.txt");
-    if let Err(e) = fs::write(&candidates_path, scanner.scan().join(" ")) {
-        eprintln!("Failed to write candidates.txt: {}", e);
+    if let Some(Command::Check { external: true }) = &config.command {
+        let link_check = config.link_check.clone().unwrap_or_default();
+        let problems = match check_external_links(dist, &link_check, &cache_dir.join(LINK_CACHE)) {
+            Ok(problems) => problems,
+            Err(e) => {
+                tracing::error!(category = "io", "Failed to check external links: {}", e);
+                return false;
+            }
+        };
+        for link in &problems {
+            tracing::warn!(category = "link", "{}", link);
+        }
+        let report_path = cache_dir.join(LINK_REPORT);
+        let json = serde_json::to_string_pretty(&problems).expect("Failed to serialize link report");
+        if let Err(e) = fs::write(&report_path, json) {
+            tracing::warn!(category = "io", path = %report_path.display(), "Failed to write {}: {}", report_path.display(), e);
+        }
+        tracing::info!("Checked external links: {} problem(s), report written to {}", problems.len(), report_path.display());
+        // Redirects and unreachable hosts are worth a look but often transient
+        broken_links += problems.iter().filter(|link| matches!(link.problem, ExternalProblem::Status { .. })).count();
+    }
+    if broken_links > 0 && (checking || config.strict_templates.unwrap_or(false)) {
+        tracing::error!("Build failed with {} broken link(s)", broken_links);
+        return false;
+    }
+
+    // The stylesheet is compiled during the build, and candidates only listed after it otherwise
+    if config.tailwind.is_none() {
+        write_candidates(dist);
+    }
+    if let Some(tailwind_config) = &config.tailwind
+        && !site.outputs.contains(tailwind_config.output())
+    {
+        tracing::error!(category = "tailwind", "Failed to compile the stylesheet with Tailwind");
+        return false;
     }
 
-    println!("All done!");
+    tracing::info!("All done!");
+    true
 }
@@ -0,0 +1,43 @@
+use std::{fs, path::{Path, PathBuf}};
+use minijinja::{Environment, context};
+use ssg_generator_utils::slugify;
+
+/// Archetype used when the configured one does not exist.
+const DEFAULT_ARCHETYPE: &str = r#"---
+title: {{ title | tojson }}
+date: {{ date }}
+draft: true
+tags: []
+---
+
+Write your post here.
+"#;
+
+/// Create `<base>/<slug>/index.md` for a post titled `title`, rendered from the archetype template.
+///
+/// The archetype at `archetype` (or a built-in one when that file is missing) receives `title`,
+/// `slug` and today's `date` (`YYYY-MM-DD`). Fails if the page directory already exists.
+pub fn new_page(base: &Path, title: &str, archetype: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let slug = slugify(title);
+    let dir = base.join(&slug);
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()).into());
+    }
+
+    let source = fs::read_to_string(archetype).unwrap_or_else(|_| DEFAULT_ARCHETYPE.to_string());
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    let content = env.render_str(
+        &source,
+        context! {
+            title => title,
+            slug => slug,
+            date => chrono::Local::now().format("%Y-%m-%d").to_string(),
+        },
+    )?;
+
+    fs::create_dir_all(&dir)?;
+    let path = dir.join("index.md");
+    fs::write(&path, content)?;
+    Ok(path)
+}
@@ -0,0 +1,223 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+use tiny_http::{Header, Request, Response, Server, StatusCode};
+
+/// Path of the server-sent events endpoint used by the live-reload script.
+const LIVE_RELOAD_PATH: &str = "/__livereload";
+
+/// Script injected before `</body>` of every served HTML page while live reload is active.
+const LIVE_RELOAD_SCRIPT: &str =
+    "<script>new EventSource(\"/__livereload\").onmessage = () => location.reload();</script>";
+
+/// Build generation counter shared between the watcher and connected browsers.
+///
+/// The watcher calls [`LiveReload::notify`] after every rebuild; each open event stream waits
+/// for the generation to change and then tells its page to reload.
+#[derive(Default)]
+pub struct LiveReload {
+    generation: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl LiveReload {
+    /// Signal every connected page that a rebuild has completed.
+    pub fn notify(&self) {
+        *self.generation.lock().unwrap() += 1;
+        self.changed.notify_all();
+    }
+
+    /// Keep a server-sent events stream open, emitting `reload` after each rebuild.
+    ///
+    /// Returns once the client disconnects, which is detected by a failing write of either an
+    /// event or the periodic keep-alive comment.
+    fn stream(&self, request: Request) -> std::io::Result<()> {
+        let mut writer = request.into_writer();
+        writer.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )?;
+        writer.flush()?;
+
+        let mut seen = *self.generation.lock().unwrap();
+        loop {
+            let generation = self.generation.lock().unwrap();
+            let (generation, _) = self
+                .changed
+                .wait_timeout_while(generation, Duration::from_secs(15), |g| *g == seen)
+                .unwrap();
+            let message: &[u8] = if *generation != seen {
+                seen = *generation;
+                b"data: reload\n\n"
+            } else {
+                b": keep-alive\n\n"
+            };
+            drop(generation);
+            writer.write_all(message)?;
+            writer.flush()?;
+        }
+    }
+}
+
+/// Serve the contents of `dist` over HTTP at `addr`, mounted under `base_path`.
+///
+/// Requests are resolved the same way GitHub Pages resolves them:
+/// - the request path must start with `base_path` (a request for `/` is redirected there);
+/// - directories are served through their `index.html`, redirecting to a trailing slash first
+///   so relative links keep working;
+/// - anything that does not resolve gets a 404 with `dist/404.html` as the body when it exists.
+///
+/// When `live_reload` is set, HTML responses get a small script that listens on
+/// `/__livereload` and reloads the page whenever the shared [`LiveReload`] is notified.
+///
+/// Blocks forever handling requests; only live-reload streams get their own thread.
+pub fn serve(
+    dist: &Path,
+    base_path: &str,
+    addr: &str,
+    live_reload: Option<Arc<LiveReload>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let server = Server::http(addr)?;
+    let prefix = base_path.trim_end_matches('/');
+    tracing::info!("Serving {} at http://{}{}/", dist.display(), addr, prefix);
+
+    for request in server.incoming_requests() {
+        let url = request.url().split(['?', '#']).next().unwrap_or("/").to_string();
+        tracing::debug!("{} {}", request.method(), url);
+        if let Some(live_reload) = live_reload.as_ref().filter(|_| url == LIVE_RELOAD_PATH) {
+            let live_reload = Arc::clone(live_reload);
+            thread::spawn(move || live_reload.stream(request));
+            continue;
+        }
+        if let Err(e) = respond(request, dist, prefix, &url, live_reload.is_some()) {
+            tracing::warn!(category = "serve", "Failed to respond to {}: {}", url, e);
+        }
+    }
+
+    Ok(())
+}
+
+fn respond(request: Request, dist: &Path, prefix: &str, url: &str, inject_reload: bool) -> std::io::Result<()> {
+    let Some(rel) = url.strip_prefix(prefix).filter(|rel| rel.is_empty() || rel.starts_with('/')) else {
+        if url == "/" {
+            return redirect(request, &format!("{}/", prefix));
+        }
+        return not_found(request, dist);
+    };
+
+    let Some(mut path) = resolve(dist, &percent_decode(rel)) else {
+        return not_found(request, dist);
+    };
+
+    if path.is_dir() {
+        if !url.ends_with('/') {
+            return redirect(request, &format!("{}/", url));
+        }
+        path = path.join("index.html");
+    }
+
+    if inject_reload && path.extension().is_some_and(|e| e == "html") {
+        return match fs::read_to_string(&path) {
+            Ok(html) => {
+                let response = Response::from_string(inject_live_reload(&html)).with_header(content_type(&path));
+                request.respond(response)
+            }
+            Err(_) => not_found(request, dist),
+        };
+    }
+
+    match File::open(&path) {
+        Ok(file) if path.is_file() => {
+            let response = Response::from_file(file).with_header(content_type(&path));
+            request.respond(response)
+        }
+        _ => not_found(request, dist),
+    }
+}
+
+/// Insert the live-reload script right before the closing `</body>`, or append it.
+fn inject_live_reload(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], LIVE_RELOAD_SCRIPT, &html[pos..]),
+        None => format!("{}{}", html, LIVE_RELOAD_SCRIPT),
+    }
+}
+
+/// Map a decoded request path onto `dist`, refusing anything that escapes it.
+fn resolve(dist: &Path, rel: &str) -> Option<PathBuf> {
+    let mut path = dist.to_path_buf();
+    for component in Path::new(rel.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+fn redirect(request: Request, location: &str) -> std::io::Result<()> {
+    let header = Header::from_bytes("Location", location).unwrap();
+    request.respond(Response::empty(StatusCode(301)).with_header(header))
+}
+
+fn not_found(request: Request, dist: &Path) -> std::io::Result<()> {
+    let page = dist.join("404.html");
+    match fs::read(&page) {
+        Ok(body) => {
+            let response = Response::from_data(body)
+                .with_status_code(404)
+                .with_header(content_type(&page));
+            request.respond(response)
+        }
+        Err(_) => request.respond(Response::from_string("404 Not Found").with_status_code(404)),
+    }
+}
+
+fn content_type(path: &Path) -> Header {
+    let mime = match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    };
+    Header::from_bytes("Content-Type", mime).unwrap()
+}
+
+/// Decode `%XX` escapes in a URL path, leaving malformed escapes untouched.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%' && i + 2 < bytes.len())
+            .then(|| std::str::from_utf8(&bytes[i + 1..i + 3]).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = escaped {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
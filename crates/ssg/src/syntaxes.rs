@@ -0,0 +1,59 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use glob::glob;
+use syntect::parsing::SyntaxSet;
+
+/// Name of the packdump, both next to the syntax sources and in the cache directory.
+const PACKDUMP: &str = "syntaxes.packdump";
+
+/// Build a syntax set from syntect's defaults plus every definition under `dir`.
+pub fn build_syntax_set(dir: &Path) -> SyntaxSet {
+    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+    builder
+        .add_from_folder(dir, true)
+        .expect("Failed to load syntaxes");
+    builder.build()
+}
+
+/// Return a packdump that is up to date with the `.sublime-syntax` files under `dir`.
+///
+/// When no definition is newer than the `syntaxes.packdump` bundled next to them, `None` is returned
+/// and the bundled copy is used. Otherwise the set is rebuilt into `cache_dir` (unless the cached
+/// dump there is already newer) and its path returned.
+pub fn fresh_packdump(dir: &Path, cache_dir: &Path) -> Option<PathBuf> {
+    let pattern = dir.join("**/*.sublime-syntax").to_string_lossy().to_string();
+    let newest_source = glob(&pattern)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|p| modified(&p))
+        .max()?;
+
+    if modified(&dir.join(PACKDUMP)).is_some_and(|t| t >= newest_source) {
+        return None;
+    }
+    let cached = cache_dir.join(PACKDUMP);
+    if modified(&cached).is_some_and(|t| t >= newest_source) {
+        return Some(cached);
+    }
+
+    tracing::info!("Syntax definitions changed, rebuilding {}", cached.display());
+    let ps = build_syntax_set(dir);
+    let written = fs::create_dir_all(cache_dir)
+        .and_then(|_| File::create(&cached))
+        .map_err(|e| e.to_string())
+        .and_then(|file| syntect::dumps::dump_to_writer(&ps, &file).map_err(|e| e.to_string()));
+    match written {
+        Ok(()) => Some(cached),
+        Err(e) => {
+            tracing::warn!(category = "io", path = %cached.display(), "Failed to write {}: {}", cached.display(), e);
+            None
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
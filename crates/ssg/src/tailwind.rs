@@ -0,0 +1,62 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Tailwind CSS build run once the pages are written, read from the `tailwind` block of the config
+/// file.
+///
+/// The stylesheet is only compiled with this block, through the Tailwind CLI, since the scanner built
+/// into the binary finds the classes of the pages but cannot generate CSS. Without it, builds only
+/// write the `candidates.txt` of those classes for an external Tailwind run.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct TailwindConfig {
+    /// Command running the Tailwind CLI, split on whitespace, e.g. `pnpm exec tailwindcss` (default: tailwindcss)
+    pub cli: Option<String>,
+    /// Stylesheet importing Tailwind (default: main.css)
+    pub input: Option<String>,
+    /// Where the compiled CSS is written, relative to the output directory (default: styles.css)
+    pub output: Option<String>,
+    /// Minify the compiled CSS (default: true)
+    pub minify: Option<bool>,
+}
+
+impl TailwindConfig {
+    /// Where the compiled CSS is written, relative to the output directory.
+    pub fn output(&self) -> &str {
+        self.output.as_deref().unwrap_or("styles.css")
+    }
+}
+
+/// Compile the input stylesheet of `config` with the Tailwind CLI into `dist`, generating every
+/// class found in `candidates` (the scan of the generated pages) on top of those the CLI detects.
+///
+/// The CLI is handed an entry stylesheet written to `cache_dir` that imports the input and adds
+/// `candidates` as a `@source`. Returns the path of the compiled CSS.
+pub fn build_css(config: &TailwindConfig, dist: &Path, candidates: &Path, cache_dir: &Path) -> io::Result<PathBuf> {
+    let input = fs::canonicalize(config.input.as_deref().unwrap_or("main.css"))?;
+    let candidates = fs::canonicalize(candidates)?;
+    let css_path = |path: &Path| path.to_string_lossy().replace('\\', "/");
+    let entry = format!("@import \"{}\";\n@source \"{}\";\n", css_path(&input), css_path(&candidates));
+    fs::create_dir_all(cache_dir)?;
+    let entry_path = cache_dir.join("tailwind.css");
+    fs::write(&entry_path, entry)?;
+
+    let output = dist.join(config.output());
+    let cli = config.cli.as_deref().unwrap_or("tailwindcss");
+    let mut words = cli.split_whitespace();
+    let program = words.next().ok_or_else(|| io::Error::other("empty Tailwind CLI command"))?;
+    let mut command = Command::new(program);
+    command.args(words).arg("--input").arg(&entry_path).arg("--output").arg(&output);
+    if config.minify.unwrap_or(true) {
+        command.arg("--minify");
+    }
+    let status = command.status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {}", cli, status)));
+    }
+    Ok(output)
+}
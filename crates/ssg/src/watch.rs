@@ -0,0 +1,52 @@
+use std::{
+    fs,
+    path::Path,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// How often the watched directories are polled for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Poll `paths` for changes and call `on_change` after every modification.
+///
+/// A change is any difference in the number of files or the newest modification time found
+/// under the watched paths, so added, edited and deleted files all trigger a rebuild.
+/// Blocks forever.
+pub fn watch(paths: &[&Path], mut on_change: impl FnMut()) {
+    let mut last = fingerprint(paths);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let current = fingerprint(paths);
+        if current != last {
+            last = current;
+            on_change();
+        }
+    }
+}
+
+fn fingerprint(paths: &[&Path]) -> (usize, Option<SystemTime>) {
+    let mut state = (0, None);
+    for path in paths {
+        visit(path, &mut state);
+    }
+    state
+}
+
+fn visit(path: &Path, state: &mut (usize, Option<SystemTime>)) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    if metadata.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                visit(&entry.path(), state);
+            }
+        }
+    } else {
+        state.0 += 1;
+        if let Ok(modified) = metadata.modified() {
+            state.1 = state.1.max(Some(modified));
+        }
+    }
+}